@@ -0,0 +1,79 @@
+//! Serve the readings of a sensor on a Linux serial port to Prometheus.
+//!
+//! Every scrape reads the sensor once:
+//! `cargo run --example prometheus_exporter --features cli -- /dev/ttyAMA0 0.0.0.0:9672`
+
+use std::process::ExitCode;
+
+use mhzx::export::prometheus;
+use mhzx::linux::LinuxSensor;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const USAGE: &str =
+    "usage: prometheus_exporter <serial port> [listen address, default 0.0.0.0:9672]";
+
+async fn serve(device: &str, address: &str) -> Result<(), String> {
+    let mut sensor =
+        LinuxSensor::open(device).map_err(|e| format!("could not open {device}: {e}"))?;
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|e| format!("could not listen on {address}: {e}"))?;
+    println!("serving metrics on http://{address}/metrics");
+
+    loop {
+        let (mut stream, peer) = listener.accept().await.map_err(|e| e.to_string())?;
+        // the request itself does not matter, every path gets the metrics
+        let mut request = [0u8; 1024];
+        if let Err(e) = stream.read(&mut request).await {
+            eprintln!("could not read request from {peer}: {e}");
+            continue;
+        }
+
+        let response = match sensor.read_co2().await {
+            Ok(measurement) => {
+                let mut body = String::new();
+                prometheus::encode(&measurement, &mut body);
+                format!(
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Type: text/plain; version=0.0.4\r\n\
+                     Content-Length: {}\r\n\
+                     Connection: close\r\n\r\n{body}",
+                    body.len()
+                )
+            }
+            Err(e) => {
+                eprintln!("could not read sensor: {e}");
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_owned()
+            }
+        };
+        if let Err(e) = stream.write_all(response.as_bytes()).await {
+            eprintln!("could not respond to {peer}: {e}");
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (device, address) = match args.as_slice() {
+        [device] => (device.as_str(), "0.0.0.0:9672"),
+        [device, address] => (device.as_str(), address.as_str()),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("could not start tokio runtime");
+    match runtime.block_on(serve(device, address)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mhzx::{decode_chunks, Deframer, DEFAULT_RESYNC_LIMIT};
+
+// The first byte selects the expected command, the second the size of the
+// reads. The rest is the data the sensor "sends".
+fuzz_target!(|data: &[u8]| {
+    let [expected, chunk_size, bytes @ ..] = data else {
+        return;
+    };
+    let chunk_size = (*chunk_size as usize).max(1);
+
+    if let Ok(package) = decode_chunks(bytes.chunks(chunk_size), *expected, DEFAULT_RESYNC_LIMIT) {
+        assert_eq!(package.as_bytes()[0], 0xFF);
+        assert_eq!(package.as_bytes()[1], *expected);
+    }
+
+    let mut deframer = Deframer::default();
+    for byte in bytes {
+        if let Some(frame) = deframer.push(*byte) {
+            assert_eq!(frame.as_bytes()[0], 0xFF);
+        }
+    }
+});
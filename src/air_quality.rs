@@ -0,0 +1,81 @@
+use crate::Measurement;
+
+/// Indoor air quality judged by CO2 concentration.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AirQuality {
+    Excellent,
+    Good,
+    Moderate,
+    Poor,
+    Unacceptable,
+}
+
+/// Highest CO2 concentration, in ppm, still counting as each [`AirQuality`].
+/// Anything above `poor` is [`AirQuality::Unacceptable`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AirQualityThresholds {
+    pub excellent: u16,
+    pub good: u16,
+    pub moderate: u16,
+    pub poor: u16,
+}
+
+impl AirQualityThresholds {
+    /// Commonly used thresholds for indoor air.
+    pub const DEFAULT: Self = AirQualityThresholds {
+        excellent: 600,
+        good: 800,
+        moderate: 1000,
+        poor: 1400,
+    };
+
+    pub fn classify(&self, co2: u16) -> AirQuality {
+        if co2 <= self.excellent {
+            AirQuality::Excellent
+        } else if co2 <= self.good {
+            AirQuality::Good
+        } else if co2 <= self.moderate {
+            AirQuality::Moderate
+        } else if co2 <= self.poor {
+            AirQuality::Poor
+        } else {
+            AirQuality::Unacceptable
+        }
+    }
+}
+
+impl Default for AirQualityThresholds {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl Measurement {
+    /// Classify the air quality using [`AirQualityThresholds::DEFAULT`].
+    pub fn air_quality(&self) -> AirQuality {
+        self.air_quality_with(&AirQualityThresholds::DEFAULT)
+    }
+
+    /// Classify the air quality using custom `thresholds`.
+    pub fn air_quality_with(&self, thresholds: &AirQualityThresholds) -> AirQuality {
+        thresholds.classify(self.co2.get())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify() {
+        let thresholds = AirQualityThresholds::DEFAULT;
+        assert_eq!(thresholds.classify(400), AirQuality::Excellent);
+        assert_eq!(thresholds.classify(600), AirQuality::Excellent);
+        assert_eq!(thresholds.classify(601), AirQuality::Good);
+        assert_eq!(thresholds.classify(1000), AirQuality::Moderate);
+        assert_eq!(thresholds.classify(1200), AirQuality::Poor);
+        assert_eq!(thresholds.classify(5000), AirQuality::Unacceptable);
+    }
+}
@@ -0,0 +1,147 @@
+use embedded_io_async::{Read, Write};
+
+use crate::model::{reports_temperature, Mhz14A, Mhz16, Mhz19B, Mhz19C, Model};
+use crate::observer::{NoObserver, Observer};
+use crate::pacing::{NoPacing, Pacing};
+use crate::{
+    Error, FirmwareVersion, Measurement, RawMeasurement, ZeroCalibration, DEFAULT_RX_BUFFER, MHZ,
+};
+
+/// A sensor of any supported model, so a mixed fleet fits in one array.
+///
+/// ```
+/// # futures::executor::block_on(async {
+/// use mhzx::model::Mhz14A;
+/// use mhzx::sim::FakeMhz;
+/// use mhzx::{AnyMhz, MHZ};
+///
+/// let (a, b) = (FakeMhz::new(), FakeMhz::new());
+/// let (tx_a, rx_a) = a.split();
+/// let (tx_b, rx_b) = b.split();
+/// let mut fleet: [AnyMhz<_, _>; 2] = [
+///     MHZ::from_tx_rx(tx_a, rx_a).into(),
+///     MHZ::from_tx_rx_with_model(tx_b, rx_b, Mhz14A).into(),
+/// ];
+/// for sensor in &mut fleet {
+///     let measurement = sensor.read_co2().await.unwrap();
+///     println!("{}: {}", sensor.model_name(), measurement.co2);
+/// }
+/// # });
+/// ```
+pub enum AnyMhz<Tx, Rx, const RX_BUF: usize = DEFAULT_RX_BUFFER, P = NoPacing, O = NoObserver> {
+    Mhz19B(MHZ<Tx, Rx, Mhz19B, RX_BUF, P, O>),
+    Mhz19C(MHZ<Tx, Rx, Mhz19C, RX_BUF, P, O>),
+    Mhz14A(MHZ<Tx, Rx, Mhz14A, RX_BUF, P, O>),
+    Mhz16(MHZ<Tx, Rx, Mhz16, RX_BUF, P, O>),
+}
+
+macro_rules! dispatch {
+    ($self:ident, $sensor:ident => $body:expr) => {
+        match $self {
+            AnyMhz::Mhz19B($sensor) => $body,
+            AnyMhz::Mhz19C($sensor) => $body,
+            AnyMhz::Mhz14A($sensor) => $body,
+            AnyMhz::Mhz16($sensor) => $body,
+        }
+    };
+}
+
+impl<Tx, Rx, const RX_BUF: usize, P, O> AnyMhz<Tx, Rx, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    P: Pacing,
+    O: Observer,
+{
+    /// [`Model::NAME`] of the sensor.
+    pub fn model_name(&self) -> &'static str {
+        match self {
+            AnyMhz::Mhz19B(_) => Mhz19B::NAME,
+            AnyMhz::Mhz19C(_) => Mhz19C::NAME,
+            AnyMhz::Mhz14A(_) => Mhz14A::NAME,
+            AnyMhz::Mhz16(_) => Mhz16::NAME,
+        }
+    }
+
+    /// [`Model::Temperature`] of the sensor.
+    pub fn reports_temperature(&self) -> bool {
+        match self {
+            AnyMhz::Mhz19B(_) => reports_temperature::<Mhz19B>(),
+            AnyMhz::Mhz19C(_) => reports_temperature::<Mhz19C>(),
+            AnyMhz::Mhz14A(_) => reports_temperature::<Mhz14A>(),
+            AnyMhz::Mhz16(_) => reports_temperature::<Mhz16>(),
+        }
+    }
+
+    pub async fn read_co2(&mut self) -> Result<Measurement, Error<Tx::Error, Rx::Error>> {
+        dispatch!(self, sensor => sensor.read_co2().await)
+    }
+
+    pub async fn read_co2_raw(&mut self) -> Result<RawMeasurement, Error<Tx::Error, Rx::Error>> {
+        dispatch!(self, sensor => sensor.read_co2_raw().await)
+    }
+
+    /// See [`MHZ::calibrate_zero`].
+    pub async fn calibrate_zero(
+        &mut self,
+        fresh_air: ZeroCalibration,
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        dispatch!(self, sensor => sensor.calibrate_zero(fresh_air).await)
+    }
+
+    /// None if the model does not report its firmware version.
+    pub async fn read_firmware_version(
+        &mut self,
+    ) -> Result<Option<FirmwareVersion>, Error<Tx::Error, Rx::Error>> {
+        match self {
+            AnyMhz::Mhz19B(sensor) => sensor.read_firmware_version().await.map(Some),
+            AnyMhz::Mhz19C(sensor) => sensor.read_firmware_version().await.map(Some),
+            AnyMhz::Mhz14A(_) | AnyMhz::Mhz16(_) => Ok(None),
+        }
+    }
+
+    /// Turn automatic baseline correction on or off. Returns false, without
+    /// sending anything, if the model does not support it.
+    pub async fn set_abc(&mut self, enabled: bool) -> Result<bool, Error<Tx::Error, Rx::Error>> {
+        match self {
+            AnyMhz::Mhz19B(sensor) => sensor.set_abc(enabled).await.map(|()| true),
+            AnyMhz::Mhz19C(sensor) => sensor.set_abc(enabled).await.map(|()| true),
+            AnyMhz::Mhz14A(sensor) => sensor.set_abc(enabled).await.map(|()| true),
+            AnyMhz::Mhz16(_) => Ok(false),
+        }
+    }
+}
+
+macro_rules! from_model {
+    ($($model:ident),*) => {$(
+        impl<Tx, Rx, const RX_BUF: usize, P, O> From<MHZ<Tx, Rx, $model, RX_BUF, P, O>>
+            for AnyMhz<Tx, Rx, RX_BUF, P, O>
+        {
+            fn from(sensor: MHZ<Tx, Rx, $model, RX_BUF, P, O>) -> Self {
+                AnyMhz::$model(sensor)
+            }
+        }
+    )*};
+}
+
+from_model!(Mhz19B, Mhz19C, Mhz14A, Mhz16);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::FakeMhz;
+    use futures::executor::block_on;
+
+    #[test]
+    fn unsupported_commands_are_skipped() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor: AnyMhz<_, _> = MHZ::from_tx_rx_with_model(tx, rx, Mhz16).into();
+
+        assert_eq!(sensor.model_name(), "MH-Z16");
+        assert_eq!(block_on(sensor.set_abc(false)), Ok(false));
+        assert_eq!(block_on(sensor.read_firmware_version()), Ok(None));
+        assert_eq!(sim.state().commands_received, 0);
+        assert_eq!(block_on(sensor.read_co2()).unwrap().co2, 400);
+    }
+}
@@ -0,0 +1,100 @@
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+
+use crate::model::Model;
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::stats::{Accumulator, Summary};
+use crate::{Error, Ppm, MHZ};
+
+/// Time between the samples of [`MHZ::read_co2_averaged`], the sensor
+/// updates its output about once per second.
+pub const AVERAGING_INTERVAL_MS: u32 = 1000;
+
+/// Samples closer then this to the median are never outliers, the sensor
+/// is only accurate to ±50ppm.
+pub const OUTLIER_MIN_DEVIATION_PPM: u16 = 50;
+
+/// Result of [`MHZ::read_co2_averaged`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Averaged {
+    /// Statistics of the samples that were kept.
+    pub summary: Summary,
+    /// Number of samples discarded as outlier.
+    pub discarded: usize,
+}
+
+impl Averaged {
+    /// The mean of the samples that were kept.
+    pub fn co2(&self) -> Ppm {
+        Ppm(self.summary.mean)
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Take `N` samples [`AVERAGING_INTERVAL_MS`] apart and average them.
+    ///
+    /// Samples deviating from the median by more then three times the
+    /// median absolute deviation, and more then
+    /// [`OUTLIER_MIN_DEVIATION_PPM`], are discarded. Fails on the first
+    /// failed read.
+    pub async fn read_co2_averaged<const N: usize>(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<Averaged, Error<Tx::Error, Rx::Error>> {
+        const { assert!(N > 0, "need at least one sample") };
+        let mut samples = [0u16; N];
+        for (i, sample) in samples.iter_mut().enumerate() {
+            if i > 0 {
+                delay.delay_ms(AVERAGING_INTERVAL_MS).await;
+            }
+            *sample = self.read_co2().await?.co2.get();
+        }
+        Ok(average(samples))
+    }
+}
+
+fn average<const N: usize>(mut samples: [u16; N]) -> Averaged {
+    samples.sort_unstable();
+    let median = samples[N / 2];
+
+    let mut deviations = samples.map(|s| s.abs_diff(median));
+    deviations.sort_unstable();
+    let mad = deviations[N / 2];
+    let max_deviation = mad.saturating_mul(3).max(OUTLIER_MIN_DEVIATION_PPM);
+
+    let mut accumulator = Accumulator::new();
+    for sample in samples {
+        if sample.abs_diff(median) <= max_deviation {
+            accumulator.push(sample);
+        }
+    }
+    let summary = accumulator.summary().expect("the median is always kept");
+    Averaged {
+        discarded: N - summary.count as usize,
+        summary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discards_outliers() {
+        let averaged = average([800, 810, 790, 5000, 805]);
+        assert_eq!(averaged.discarded, 1);
+        assert_eq!(averaged.co2(), 801);
+        assert_eq!((averaged.summary.min, averaged.summary.max), (790, 810));
+
+        assert_eq!(average([400]).co2(), 400);
+    }
+}
@@ -0,0 +1,156 @@
+//! A ready-made task that keeps reading the sensor and publishes the latest
+//! result, consumers subscribe instead of talking to the sensor.
+//!
+//! ```no_run
+//! # async fn run<Tx, Rx>(tx: Tx, rx: Rx, mut delay: impl embedded_hal_async::delay::DelayNs)
+//! # where
+//! #     Tx: embedded_io_async::Write<Error = core::convert::Infallible>,
+//! #     Rx: embedded_io_async::Read<Error = core::convert::Infallible>,
+//! # {
+//! use core::convert::Infallible;
+//! use core::time::Duration;
+//! use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+//! use embassy_sync::watch::Watch;
+//! use mhzx::{background, Error, Measurement, MHZ};
+//!
+//! let latest: Watch<NoopRawMutex, Result<Measurement, Error<Infallible>>, 2> = Watch::new();
+//!
+//! // in the sensor task
+//! let mut sensor = MHZ::from_tx_rx(tx, rx);
+//! background::run(&mut sensor, &mut delay, Duration::from_secs(5), 2, &latest.sender()).await;
+//!
+//! // in a consumer task
+//! let measurement = latest.receiver().unwrap().changed().await;
+//! # }
+//! ```
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::signal::Signal;
+use embassy_sync::watch::{DynSender, Sender};
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+
+use crate::model::Model;
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Error, IntoMillis, Measurement, MHZ, MIN_COMMAND_INTERVAL_MS};
+
+/// Somewhere to put the latest result, implemented for the embassy-sync
+/// [`Watch`](embassy_sync::watch::Watch) senders and [`Signal`].
+pub trait Publish<T> {
+    fn publish(&self, value: T);
+}
+
+impl<M: RawMutex, T: Clone, const N: usize> Publish<T> for Sender<'_, M, T, N> {
+    fn publish(&self, value: T) {
+        self.send(value)
+    }
+}
+
+impl<T: Clone> Publish<T> for DynSender<'_, T> {
+    fn publish(&self, value: T) {
+        self.send(value)
+    }
+}
+
+impl<M: RawMutex, T> Publish<T> for Signal<M, T> {
+    fn publish(&self, value: T) {
+        self.signal(value)
+    }
+}
+
+/// Read the sensor every `interval` and publish the result, forever.
+/// Failed reads are retried up to `retries` times before the error is
+/// published.
+pub async fn run<Tx, Rx, M, const RX_BUF: usize, P, O>(
+    sensor: &mut MHZ<Tx, Rx, M, RX_BUF, P, O>,
+    delay: &mut impl DelayNs,
+    interval: impl IntoMillis,
+    retries: u8,
+    publisher: &impl Publish<Result<Measurement, Error<Tx::Error, Rx::Error>>>,
+) -> !
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    let interval_ms = interval.into_millis();
+    loop {
+        let mut result = sensor.read_co2().await;
+        for _ in 0..retries {
+            if result.is_ok() {
+                break;
+            }
+            debug!("read failed, retrying");
+            delay.delay_ms(MIN_COMMAND_INTERVAL_MS).await;
+            sensor.observe().retry();
+            result = sensor.read_co2().await;
+        }
+        publisher.publish(result);
+        delay.delay_ms(interval_ms).await;
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::sim::FakeMhz;
+    use core::convert::Infallible;
+    use core::future::poll_fn;
+    use core::pin::pin;
+    use core::task::Poll;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use embassy_sync::watch::Watch;
+    use futures::executor::block_on;
+    use futures::future::{select, Either};
+
+    /// Lets other futures run once per delay.
+    struct YieldingDelay;
+
+    impl DelayNs for YieldingDelay {
+        async fn delay_ns(&mut self, _ns: u32) {
+            let mut yielded = false;
+            poll_fn(|cx| {
+                if yielded {
+                    return Poll::Ready(());
+                }
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            })
+            .await
+        }
+    }
+
+    #[test]
+    fn publishes_with_retries() {
+        let watch: Watch<NoopRawMutex, Result<Measurement, Error<Infallible>>, 1> = Watch::new();
+        let mut receiver = watch.receiver().unwrap();
+        let sender = watch.sender();
+        let sim = FakeMhz::new();
+        sim.set_co2(700);
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        let mut delay = YieldingDelay;
+
+        let mut reader = pin!(run(&mut sensor, &mut delay, 5000u32, 1, &sender));
+        block_on(async {
+            let Either::Right((first, _)) = select(reader.as_mut(), pin!(receiver.changed())).await;
+            assert_eq!(first.unwrap().co2, 700);
+
+            // one failure is retried, two are published
+            sim.corrupt_next(1);
+            let Either::Right((second, _)) =
+                select(reader.as_mut(), pin!(receiver.changed())).await;
+            assert_eq!(second.unwrap().co2, 700);
+            assert_eq!(sim.state().commands_received, 3);
+
+            sim.corrupt_next(2);
+            let Either::Right((third, _)) = select(reader.as_mut(), pin!(receiver.changed())).await;
+            assert!(matches!(third, Err(Error::InvalidChecksum { .. })));
+            assert_eq!(sim.state().commands_received, 5);
+        });
+    }
+}
@@ -0,0 +1,116 @@
+//! Commission and debug a sensor from the command line.
+
+use std::process::ExitCode;
+
+use mhzx::linux::LinuxSensor;
+use mhzx::model::Range;
+use mhzx::ZeroCalibration;
+
+const USAGE: &str = "\
+usage: mhz-cli <serial device> <command>
+
+commands:
+    read                        read the CO2 concentration and temperature
+    raw                         read the raw CO2 concentration
+    range <2000|5000|10000>     set the detection range in ppm
+    abc <on|off>                turn automatic baseline correction on or off
+    calibrate --fresh-air       zero point calibration, the sensor must have
+                                been in fresh (~400ppm) air for 20 minutes
+    version                     read the firmware version";
+
+enum Command {
+    Read,
+    Raw,
+    Range(Range),
+    Abc(bool),
+    Calibrate,
+    Version,
+}
+
+fn parse(args: &[String]) -> Result<(String, Command), String> {
+    let [device, command, rest @ ..] = args else {
+        return Err("expected a serial device and a command".into());
+    };
+    let command = match (command.as_str(), rest) {
+        ("read", []) => Command::Read,
+        ("raw", []) => Command::Raw,
+        ("version", []) => Command::Version,
+        ("range", [ppm]) => Command::Range(match ppm.as_str() {
+            "2000" => Range::Ppm2000,
+            "5000" => Range::Ppm5000,
+            "10000" => Range::Ppm10000,
+            other => return Err(format!("unsupported range: {other}")),
+        }),
+        ("abc", [state]) => Command::Abc(match state.as_str() {
+            "on" => true,
+            "off" => false,
+            other => return Err(format!("abc must be on or off, got: {other}")),
+        }),
+        ("calibrate", [flag]) if flag == "--fresh-air" => Command::Calibrate,
+        ("calibrate", _) => {
+            return Err(
+                "calibrate requires --fresh-air to confirm the sensor is in fresh air".into(),
+            )
+        }
+        (other, _) => return Err(format!("unknown command or wrong arguments: {other}")),
+    };
+    Ok((device.clone(), command))
+}
+
+async fn run(device: &str, command: Command) -> Result<(), String> {
+    let mut sensor =
+        LinuxSensor::open(device).map_err(|e| format!("could not open {device}: {e}"))?;
+    match command {
+        Command::Read => {
+            let m = sensor.read_co2().await.map_err(|e| e.to_string())?;
+            println!("co2: {}", m.co2);
+            println!("temperature: {}°C", m.temp_celsius());
+        }
+        Command::Raw => {
+            let m = sensor.read_co2_raw().await.map_err(|e| e.to_string())?;
+            println!("co2 (unclamped): {}ppm", m.co2);
+            println!("temperature adc: {}", m.adc_temp);
+            println!("minimum light adc: {}", m.adc_min_light);
+        }
+        Command::Range(range) => sensor.set_range(range).await.map_err(|e| e.to_string())?,
+        Command::Abc(enabled) => sensor.set_abc(enabled).await.map_err(|e| e.to_string())?,
+        Command::Calibrate => sensor
+            .calibrate_zero(ZeroCalibration::confirm_fresh_air())
+            .await
+            .map_err(|e| e.to_string())?,
+        Command::Version => {
+            let version = sensor
+                .read_firmware_version()
+                .await
+                .map_err(|e| e.to_string())?;
+            match version.as_str() {
+                Some(version) => println!("firmware version: {version}"),
+                None => println!("firmware version (not ascii): {:02x?}", version.0),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (device, command) = match parse(&args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("could not start tokio runtime");
+    match runtime.block_on(run(&device, command)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Blocking front-end for firmware without an executor, using the
+//! [`embedded_io`] and [`embedded_hal::delay`] blocking traits.
+//!
+//! [`BlockingMhz`] is generated from the async [`MHZ`]: the blocking UART is
+//! wrapped so its futures finish on the first poll, each method then runs
+//! the async implementation to completion. Parsing, commands and errors are
+//! the same for both.
+//!
+//! ```no_run
+//! # fn run<Tx, Rx>(tx: Tx, rx: Rx)
+//! # where
+//! #     Tx: embedded_io::Write,
+//! #     Rx: embedded_io::Read,
+//! # {
+//! use mhzx::blocking::BlockingMhz;
+//!
+//! let mut sensor = BlockingMhz::new(tx, rx);
+//! let measurement = sensor.read_co2().unwrap();
+//! # }
+//! ```
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use embedded_hal::delay::DelayNs as BlockingDelayNs;
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{ErrorType, Read, ReadReady, Write};
+
+use crate::model::{
+    Configurable, Mhz19B, Model, SupportsAbc, SupportsFirmwareVersion, SupportsRange,
+    SupportsTemperature,
+};
+use crate::{Error, FirmwareVersion, Measurement, RawMeasurement, ZeroCalibration, MHZ};
+
+/// Adapts a blocking [`embedded_io`] reader or writer, or a blocking
+/// [`embedded_hal`] delay, to the async traits the driver uses. The futures
+/// never return [`Poll::Pending`].
+pub struct FromBlocking<T>(pub T);
+
+impl<T: embedded_io::ErrorType> ErrorType for FromBlocking<T> {
+    type Error = T::Error;
+}
+
+impl<T: embedded_io::Read> Read for FromBlocking<T> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.0.read(buf)
+    }
+}
+
+impl<T: embedded_io::ReadReady> ReadReady for FromBlocking<T> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        self.0.read_ready()
+    }
+}
+
+impl<T: embedded_io::Write> Write for FromBlocking<T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0.write(buf)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.0.flush()
+    }
+}
+
+impl<T: BlockingDelayNs> DelayNs for FromBlocking<T> {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.0.delay_ns(ns)
+    }
+}
+
+/// Run a future that never waits to completion.
+fn run<F: Future>(future: F) -> F::Output {
+    let future = pin!(future);
+    match future.poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("blocking IO never returns Pending"),
+    }
+}
+
+/// The async sensor the blocking methods forward to.
+type Inner<Tx, Rx, M> = MHZ<FromBlocking<Tx>, FromBlocking<Rx>, M>;
+
+/// Blocking version of [`MHZ`], see the [module docs](self).
+pub struct BlockingMhz<Tx, Rx, M = Mhz19B> {
+    sensor: Inner<Tx, Rx, M>,
+}
+
+impl<Tx, Rx> BlockingMhz<Tx, Rx>
+where
+    Tx: embedded_io::Write,
+    Rx: embedded_io::Read,
+{
+    /// See [`MHZ::from_tx_rx`] for the required UART settings.
+    pub fn new(uart_tx: Tx, uart_rx: Rx) -> Self {
+        BlockingMhz::with_model(uart_tx, uart_rx, Mhz19B)
+    }
+}
+
+/// Generates blocking methods forwarding to the async method of the same
+/// name.
+macro_rules! forward {
+    ($($(#[$attr:meta])* fn $name:ident(&mut self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name(&mut self $(, $arg: $ty)*) -> Result<$ret, Error<Tx::Error, Rx::Error>> {
+                run(self.sensor.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+impl<Tx, Rx, M> BlockingMhz<Tx, Rx, M>
+where
+    Tx: embedded_io::Write,
+    Rx: embedded_io::Read,
+    M: Model,
+{
+    /// Like [`new`](BlockingMhz::new) but for a specific [`Model`].
+    pub fn with_model(uart_tx: Tx, uart_rx: Rx, model: M) -> Self {
+        BlockingMhz {
+            sensor: MHZ::from_tx_rx_with_model(FromBlocking(uart_tx), FromBlocking(uart_rx), model),
+        }
+    }
+
+    /// The async sensor, for settings such as
+    /// [`set_resync_limit`](MHZ::set_resync_limit).
+    pub fn inner(&mut self) -> &mut Inner<Tx, Rx, M> {
+        &mut self.sensor
+    }
+
+    forward! {
+        /// See [`MHZ::read_co2`].
+        fn read_co2(&mut self) -> Measurement;
+        /// See [`MHZ::read_co2_raw`].
+        fn read_co2_raw(&mut self) -> RawMeasurement;
+    }
+
+    /// See [`MHZ::read_both`].
+    pub fn read_both(
+        &mut self,
+        delay: &mut impl BlockingDelayNs,
+    ) -> Result<(Measurement, RawMeasurement), Error<Tx::Error, Rx::Error>> {
+        run(self.sensor.read_both(&mut FromBlocking(delay)))
+    }
+}
+
+impl<Tx, Rx, M> BlockingMhz<Tx, Rx, M>
+where
+    Tx: embedded_io::Write,
+    Rx: embedded_io::Read,
+    M: Configurable,
+{
+    forward! {
+        /// See [`MHZ::calibrate_zero`].
+        fn calibrate_zero(&mut self, fresh_air: ZeroCalibration) -> ();
+    }
+}
+
+impl<Tx, Rx, M> BlockingMhz<Tx, Rx, M>
+where
+    Tx: embedded_io::Write,
+    Rx: embedded_io::Read,
+    M: SupportsRange,
+{
+    forward! {
+        /// See [`MHZ::set_range`].
+        fn set_range(&mut self, range: M::Range) -> ();
+        /// See [`MHZ::read_range_ppm`].
+        fn read_range_ppm(&mut self) -> u16;
+    }
+}
+
+impl<Tx, Rx, M> BlockingMhz<Tx, Rx, M>
+where
+    Tx: embedded_io::Write,
+    Rx: embedded_io::Read,
+    M: SupportsAbc,
+{
+    forward! {
+        /// See [`MHZ::set_abc`].
+        fn set_abc(&mut self, enabled: bool) -> ();
+        /// See [`MHZ::read_abc`].
+        fn read_abc(&mut self) -> bool;
+    }
+}
+
+impl<Tx, Rx, M> BlockingMhz<Tx, Rx, M>
+where
+    Tx: embedded_io::Write,
+    Rx: embedded_io::Read,
+    M: SupportsFirmwareVersion,
+{
+    forward! {
+        /// See [`MHZ::read_firmware_version`].
+        fn read_firmware_version(&mut self) -> FirmwareVersion;
+    }
+}
+
+impl<Tx, Rx, M> BlockingMhz<Tx, Rx, M>
+where
+    Tx: embedded_io::Write,
+    Rx: embedded_io::Read,
+    M: SupportsTemperature,
+{
+    forward! {
+        /// See [`MHZ::read_temperature`].
+        fn read_temperature(&mut self) -> i16;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Command;
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    struct Tx<'a>(&'a Cell<Option<[u8; 9]>>);
+    struct Rx<'a>(&'a Cell<Option<[u8; 9]>>, &'a [u8]);
+
+    impl embedded_io::ErrorType for Tx<'_> {
+        type Error = Infallible;
+    }
+
+    impl embedded_io::ErrorType for Rx<'_> {
+        type Error = Infallible;
+    }
+
+    impl embedded_io::Write for Tx<'_> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+            self.0.set(buf.try_into().ok());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    impl embedded_io::Read for Rx<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+            let n = self.1.len().min(buf.len());
+            buf[..n].copy_from_slice(&self.1[..n]);
+            self.1 = &self.1[n..];
+            Ok(n)
+        }
+    }
+
+    impl embedded_io::ReadReady for Rx<'_> {
+        fn read_ready(&mut self) -> Result<bool, Infallible> {
+            // the response only arrives once a command was send
+            Ok(self.0.get().is_some() && !self.1.is_empty())
+        }
+    }
+
+    #[test]
+    fn same_results_as_async() {
+        let sent = Cell::new(None);
+        let response = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97];
+        let mut sensor = BlockingMhz::new(Tx(&sent), Rx(&sent, &response));
+
+        let measurement = sensor.read_co2().unwrap();
+        assert_eq!(sent.get(), Some(Command::ReadCo2.to_frame().into_bytes()));
+        assert_eq!(measurement.co2, 420);
+        assert_eq!(measurement.temp_celsius(), 22);
+        assert_eq!(sensor.read_co2().unwrap_err(), Error::ReadingEOF);
+    }
+}
@@ -0,0 +1,41 @@
+use embedded_io_async::{BufRead, Read, Write};
+
+use crate::model::Model;
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::read_package::read_response_buffered;
+use crate::{Command, Error, Frame, Measurement, MHZ};
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read + BufRead,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Like [`read_co2`](MHZ::read_co2) but parses the response straight
+    /// from the receive buffer of the UART, for example a DMA ring buffer
+    /// behind embassy's `BufferedUarte`. Skips the copy into the
+    /// `RX_BUF` scratch buffer.
+    pub async fn read_co2_buffered(&mut self) -> Result<Measurement, Error<Tx::Error, Rx::Error>> {
+        let mut protocol = self.protocol(Command::ReadCo2.to_frame());
+        if let Some(frame) = protocol.next_frame_to_send() {
+            self.write_command(&frame).await?;
+        }
+
+        trace!("reading uart buffer");
+        let package = read_response_buffered::<Tx::Error, Rx>(
+            &mut self.uart_rx,
+            &mut protocol,
+            &mut (&mut self.observer, &mut self.stats),
+        )
+        .await?;
+        trace_frame!(Rx, package);
+        self.observe().frame_received(&Frame::from_bytes(package));
+
+        trace!("checking packet checksum");
+        self.verify_checksum(&package)?;
+        self.parse_measurement(package)
+    }
+}
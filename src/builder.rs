@@ -0,0 +1,153 @@
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+
+use crate::model::{Mhz19B, Model, SupportsAbc, SupportsRange};
+use crate::verify::Setting;
+use crate::{Error, MHZ};
+
+/// Times a configuration command is send again by default when the sensor
+/// ignores it, see [`MhzBuilder::retries`].
+pub const DEFAULT_CONFIG_RETRIES: u8 = 2;
+
+/// Constructs an [`MHZ`] and brings the sensor into a known configuration,
+/// so every firmware does not have to repeat the same init sequence.
+///
+/// ```no_run
+/// # async fn run<Tx, Rx>(tx: Tx, rx: Rx, mut delay: impl embedded_hal_async::delay::DelayNs)
+/// # where
+/// #     Tx: embedded_io_async::Write,
+/// #     Rx: embedded_io_async::Read,
+/// # {
+/// use mhzx::model::Range;
+/// use mhzx::MhzBuilder;
+///
+/// let sensor = MhzBuilder::new()
+///     .abc(false)
+///     .range(Range::Ppm5000)
+///     .build(tx, rx, &mut delay)
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct MhzBuilder<M = Mhz19B> {
+    abc: Option<Setting>,
+    range: Option<Setting>,
+    retries: u8,
+    model: M,
+}
+
+impl MhzBuilder {
+    pub fn new() -> Self {
+        MhzBuilder::for_model(Mhz19B)
+    }
+}
+
+impl Default for MhzBuilder {
+    fn default() -> Self {
+        MhzBuilder::new()
+    }
+}
+
+impl<M: Model> MhzBuilder<M> {
+    /// Like [`new`](MhzBuilder::new) but for a specific [`Model`].
+    pub fn for_model(model: M) -> Self {
+        MhzBuilder {
+            abc: None,
+            range: None,
+            retries: DEFAULT_CONFIG_RETRIES,
+            model,
+        }
+    }
+
+    /// Send a configuration command again up to `retries` times when
+    /// reading it back shows the sensor ignored it. Defaults to
+    /// [`DEFAULT_CONFIG_RETRIES`].
+    pub fn retries(mut self, retries: u8) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Construct the sensor and apply the configuration like
+    /// [`set_abc_verified`](MHZ::set_abc_verified) and
+    /// [`set_range_verified`](MHZ::set_range_verified) do: each setting is
+    /// read back after waiting
+    /// [`config_settle_ms`](crate::timing::Timing::config_settle_ms). Then
+    /// reads the CO2 concentration to verify the sensor answers.
+    pub async fn build<Tx, Rx>(
+        self,
+        uart_tx: Tx,
+        uart_rx: Rx,
+        delay: &mut impl DelayNs,
+    ) -> Result<MHZ<Tx, Rx, M>, Error<Tx::Error, Rx::Error>>
+    where
+        Tx: Write,
+        Rx: Read,
+    {
+        let mut sensor = MHZ::from_tx_rx_with_model(uart_tx, uart_rx, self.model);
+        for setting in [self.abc, self.range].into_iter().flatten() {
+            sensor.apply_verified(setting, self.retries, delay).await?;
+        }
+        sensor.read_co2().await?;
+        Ok(sensor)
+    }
+}
+
+impl<M: SupportsAbc> MhzBuilder<M> {
+    /// Turn automatic baseline correction on or off during
+    /// [`build`](MhzBuilder::build).
+    pub fn abc(mut self, enabled: bool) -> Self {
+        self.abc = Some(Setting::Abc(enabled));
+        self
+    }
+}
+
+impl<M: SupportsRange> MhzBuilder<M> {
+    /// Set the detection range during [`build`](MhzBuilder::build).
+    pub fn range(mut self, range: M::Range) -> Self {
+        self.range = Some(Setting::Range(range.into()));
+        self
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::model::Range;
+    use crate::sim::{FakeMhz, NoDelay};
+    use futures::executor::block_on;
+
+    #[test]
+    fn applies_config() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = block_on(MhzBuilder::new().abc(false).range(Range::Ppm10000).build(
+            tx,
+            rx,
+            &mut NoDelay,
+        ))
+        .unwrap();
+
+        let state = sim.state();
+        assert_eq!(state.abc, Some(false));
+        assert_eq!(state.range, Some(10000));
+        block_on(sensor.read_co2()).unwrap();
+    }
+
+    #[test]
+    fn retries_ignored_config() {
+        let sim = FakeMhz::new();
+        sim.with(|s| s.ignore_config = 1);
+        let (tx, rx) = sim.split();
+        block_on(MhzBuilder::new().abc(false).build(tx, rx, &mut NoDelay)).unwrap();
+        assert_eq!(sim.state().abc, Some(false));
+
+        sim.with(|s| s.ignore_config = 1);
+        let (tx, rx) = sim.split();
+        let build = MhzBuilder::new().abc(true).retries(0);
+        assert!(matches!(
+            block_on(build.build(tx, rx, &mut NoDelay)),
+            Err(Error::ConfigNotApplied)
+        ));
+    }
+}
@@ -0,0 +1,160 @@
+//! Keep showing the last good measurement while the sensor misbehaves.
+//!
+//! ```
+//! # futures::executor::block_on(async {
+//! use mhzx::cache::CachedMhz;
+//! use mhzx::sim::FakeMhz;
+//! use mhzx::MHZ;
+//!
+//! let sim = FakeMhz::new();
+//! let (tx, rx) = sim.split();
+//! let mut sensor = CachedMhz::new(MHZ::from_tx_rx(tx, rx), || 0u64);
+//!
+//! sensor.read_co2().await.unwrap();
+//! sim.corrupt_next(1);
+//! let cached = sensor.read_co2().await.unwrap();
+//! assert!(cached.is_stale());
+//! # });
+//! ```
+
+use embedded_io_async::{Read, Write};
+
+use crate::model::{Mhz19B, Model};
+use crate::observer::{NoObserver, Observer};
+use crate::pacing::{NoPacing, Pacing};
+use crate::{Clock, Error, Measurement, DEFAULT_RX_BUFFER, MHZ};
+
+/// The most recent successful measurement.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy)]
+pub struct Cached {
+    pub measurement: Measurement,
+    /// Time since the measurement was read.
+    pub age_ms: u64,
+    /// Reads that failed since the measurement was read.
+    pub failed_attempts: u32,
+}
+
+impl Cached {
+    /// True if the latest read failed and this is an older measurement.
+    pub fn is_stale(&self) -> bool {
+        self.failed_attempts > 0
+    }
+}
+
+/// Returns the last good measurement when a read fails, see the
+/// [module docs](self).
+pub struct CachedMhz<
+    Tx,
+    Rx,
+    C,
+    M = Mhz19B,
+    const RX_BUF: usize = DEFAULT_RX_BUFFER,
+    P = NoPacing,
+    O = NoObserver,
+> {
+    sensor: MHZ<Tx, Rx, M, RX_BUF, P, O>,
+    clock: C,
+    last_good: Option<(Measurement, u64)>,
+    failed_attempts: u32,
+}
+
+impl<Tx, Rx, C, M, const RX_BUF: usize, P, O> CachedMhz<Tx, Rx, C, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    C: Clock,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    pub fn new(sensor: MHZ<Tx, Rx, M, RX_BUF, P, O>, clock: C) -> Self {
+        CachedMhz {
+            sensor,
+            clock,
+            last_good: None,
+            failed_attempts: 0,
+        }
+    }
+
+    /// Read the sensor, on failure fall back to the last good measurement.
+    /// Only fails if no read ever succeeded.
+    pub async fn read_co2(&mut self) -> Result<Cached, Error<Tx::Error, Rx::Error>> {
+        match self.sensor.read_co2().await {
+            Ok(measurement) => {
+                self.last_good = Some((measurement, self.clock.now_ms()));
+                self.failed_attempts = 0;
+            }
+            Err(e) => {
+                self.failed_attempts = self.failed_attempts.saturating_add(1);
+                debug!("read failed, {} in a row", self.failed_attempts);
+                if self.last_good.is_none() {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(self.cached().expect("set above"))
+    }
+
+    /// The last good measurement without reading the sensor.
+    pub fn cached(&self) -> Option<Cached> {
+        self.last_good.map(|(measurement, read_at)| Cached {
+            measurement,
+            age_ms: self.clock.now_ms().saturating_sub(read_at),
+            failed_attempts: self.failed_attempts,
+        })
+    }
+
+    /// Forget the last good measurement.
+    pub fn clear(&mut self) {
+        self.last_good = None;
+        self.failed_attempts = 0;
+    }
+
+    /// Access the sensor for other commands.
+    pub fn sensor(&mut self) -> &mut MHZ<Tx, Rx, M, RX_BUF, P, O> {
+        &mut self.sensor
+    }
+
+    /// Give back the sensor and clock.
+    pub fn release(self) -> (MHZ<Tx, Rx, M, RX_BUF, P, O>, C) {
+        (self.sensor, self.clock)
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::sim::FakeMhz;
+    use core::cell::Cell;
+    use futures::executor::block_on;
+
+    #[test]
+    fn falls_back_to_last_good() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let now = Cell::new(0u64);
+        let mut sensor = CachedMhz::new(MHZ::from_tx_rx(tx, rx), || now.get());
+
+        block_on(async {
+            sim.corrupt_next(1);
+            assert!(sensor.read_co2().await.is_err());
+
+            sim.set_co2(700);
+            let cached = sensor.read_co2().await.unwrap();
+            assert_eq!((cached.measurement.co2.get(), cached.age_ms), (700, 0));
+            assert!(!cached.is_stale());
+
+            now.set(5000);
+            sim.set_co2(900);
+            sim.corrupt_next(2);
+            sensor.read_co2().await.unwrap();
+            let cached = sensor.read_co2().await.unwrap();
+            assert_eq!(cached.measurement.co2.get(), 700);
+            assert_eq!((cached.age_ms, cached.failed_attempts), (5000, 2));
+
+            let fresh = sensor.read_co2().await.unwrap();
+            assert_eq!((fresh.measurement.co2.get(), fresh.age_ms), (900, 0));
+        });
+    }
+}
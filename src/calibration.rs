@@ -0,0 +1,316 @@
+use core::fmt;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+
+use crate::model::Configurable;
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Error, IntoMillis, Ppm, MHZ};
+
+/// Minimum time the sensor needs to spend in fresh air before a zero point
+/// calibration, according to the datasheet: 20 minutes.
+pub const MIN_FRESH_AIR_EXPOSURE_MS: u32 = 20 * 60 * 1000;
+
+/// Proof that the sensor is sitting in fresh (~400ppm) air. Required by
+/// [`MHZ::calibrate_zero`](crate::MHZ::calibrate_zero).
+///
+/// Zero point calibration in polluted air permanently ruins the accuracy of
+/// the sensor. This type can not be constructed by accident, you have to
+/// explicitly confirm the sensor is in fresh air.
+#[derive(Debug)]
+pub struct ZeroCalibration {
+    _private: (),
+}
+
+impl ZeroCalibration {
+    /// Confirm the sensor has been in fresh outdoor air (~400ppm) for at least
+    /// [`MIN_FRESH_AIR_EXPOSURE_MS`].
+    pub fn confirm_fresh_air() -> Self {
+        ZeroCalibration { _private: () }
+    }
+
+    /// Confirm the sensor has just been placed in fresh outdoor air (~400ppm)
+    /// then wait `exposure` before handing out the guard. Exposures shorter
+    /// then [`MIN_FRESH_AIR_EXPOSURE_MS`] are extended to it.
+    pub async fn confirm_fresh_air_after(
+        delay: &mut impl DelayNs,
+        exposure: impl IntoMillis,
+    ) -> Self {
+        delay
+            .delay_ms(exposure.into_millis().max(MIN_FRESH_AIR_EXPOSURE_MS))
+            .await;
+        Self::confirm_fresh_air()
+    }
+}
+
+/// Time the HD pin must be held low to trigger a zero point calibration,
+/// the datasheet asks for at least 7 seconds.
+pub const HD_HOLD_MS: u32 = 7_500;
+
+/// How long [`MHZ::calibrate_zero_hd`] waits for the reading to settle
+/// near 400ppm.
+pub const HD_SETTLE_MS: u32 = 60_000;
+
+/// How close to 400ppm the reading must get after a HD pin calibration.
+pub const HD_TOLERANCE_PPM: u16 = 50;
+
+/// How often [`MHZ::calibrate_zero_guided`] reads the sensor while waiting
+/// in fresh air.
+pub const GUIDED_READ_INTERVAL_MS: u32 = 60_000;
+
+/// How far from 400ppm a reading may be during
+/// [`MHZ::calibrate_zero_guided`]. An uncalibrated sensor is not expected
+/// to read exactly 400ppm, much further off and it is not in fresh air.
+pub const GUIDED_TOLERANCE_PPM: u16 = 100;
+
+/// How far apart the readings during [`MHZ::calibrate_zero_guided`] may
+/// be, people walking by or a closing window make them drift more.
+pub const GUIDED_MAX_SPREAD_PPM: u16 = 50;
+
+/// Why [`MHZ::calibrate_zero_guided`] did not calibrate.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuidedCalibrationError<TxError, RxError> {
+    Sensor(Error<TxError, RxError>),
+    /// A reading was further then [`GUIDED_TOLERANCE_PPM`] from 400ppm.
+    NotFreshAir {
+        co2: Ppm,
+    },
+    /// The readings were further then [`GUIDED_MAX_SPREAD_PPM`] apart.
+    Unstable {
+        min: Ppm,
+        max: Ppm,
+    },
+}
+
+impl<TxError, RxError> fmt::Display for GuidedCalibrationError<TxError, RxError>
+where
+    TxError: fmt::Display,
+    RxError: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GuidedCalibrationError::Sensor(e) => write!(f, "{e}"),
+            GuidedCalibrationError::NotFreshAir { co2 } => {
+                write!(f, "Sensor is not in fresh air, it reads {co2}")
+            }
+            GuidedCalibrationError::Unstable { min, max } => {
+                write!(
+                    f,
+                    "Readings are not stable, they ranged from {min} to {max}"
+                )
+            }
+        }
+    }
+}
+
+/// Error during a zero point calibration using the HD pin.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HdCalibrationError<PinError, TxError, RxError> {
+    /// Driving the HD pin failed.
+    Pin(PinError),
+    Sensor(Error<TxError, RxError>),
+    /// The reading did not settle near 400ppm within [`HD_SETTLE_MS`].
+    NotSettled {
+        co2: Ppm,
+    },
+}
+
+impl<PinError, TxError, RxError> fmt::Display for HdCalibrationError<PinError, TxError, RxError>
+where
+    PinError: fmt::Debug,
+    TxError: fmt::Display,
+    RxError: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HdCalibrationError::Pin(e) => write!(f, "Could not drive the HD pin: {e:?}"),
+            HdCalibrationError::Sensor(e) => write!(f, "{e}"),
+            HdCalibrationError::NotSettled { co2 } => {
+                write!(
+                    f,
+                    "Reading did not settle near 400 ppm after calibration, got {co2}"
+                )
+            }
+        }
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Configurable,
+    P: Pacing,
+    O: Observer,
+{
+    /// Zero point calibration by holding the HD pin low for [`HD_HOLD_MS`],
+    /// for installations that wire HD. Then reads the sensor every second
+    /// until it reports 400ppm ± [`HD_TOLERANCE_PPM`], for at most
+    /// [`HD_SETTLE_MS`]. Returns the settled reading.
+    ///
+    /// `hd` must idle high, the pin is left high.
+    pub async fn calibrate_zero_hd<Hd: OutputPin>(
+        &mut self,
+        hd: &mut Hd,
+        delay: &mut impl DelayNs,
+        _fresh_air: ZeroCalibration,
+    ) -> Result<Ppm, HdCalibrationError<Hd::Error, Tx::Error, Rx::Error>> {
+        debug!("performing zero point calibration using the HD pin");
+        hd.set_low().map_err(HdCalibrationError::Pin)?;
+        delay.delay_ms(HD_HOLD_MS).await;
+        hd.set_high().map_err(HdCalibrationError::Pin)?;
+
+        let mut co2 = Ppm(0);
+        for _ in 0..HD_SETTLE_MS / 1000 {
+            delay.delay_ms(1000).await;
+            co2 = self
+                .read_co2()
+                .await
+                .map_err(HdCalibrationError::Sensor)?
+                .co2;
+            if co2.abs_diff(Ppm(400)) <= HD_TOLERANCE_PPM {
+                return Ok(co2);
+            }
+        }
+        Err(HdCalibrationError::NotSettled { co2 })
+    }
+
+    /// Zero point calibration for a sensor that was just placed in fresh
+    /// outdoor air. Waits `dwell` first, at least
+    /// [`MIN_FRESH_AIR_EXPOSURE_MS`], reading the sensor every
+    /// [`GUIDED_READ_INTERVAL_MS`]. Only calibrates if every reading is
+    /// within [`GUIDED_TOLERANCE_PPM`] of 400ppm and all of them are within
+    /// [`GUIDED_MAX_SPREAD_PPM`] of each other.
+    pub async fn calibrate_zero_guided(
+        &mut self,
+        delay: &mut impl DelayNs,
+        dwell: impl IntoMillis,
+    ) -> Result<(), GuidedCalibrationError<Tx::Error, Rx::Error>> {
+        let dwell_ms = dwell.into_millis().max(MIN_FRESH_AIR_EXPOSURE_MS);
+        debug!("waiting {} ms in fresh air before calibrating", dwell_ms);
+
+        let mut min = Ppm::MAX;
+        let mut max = Ppm::ZERO;
+        let mut waited = 0;
+        loop {
+            let co2 = self
+                .read_co2()
+                .await
+                .map_err(GuidedCalibrationError::Sensor)?
+                .co2;
+            if co2.abs_diff(Ppm(400)) > GUIDED_TOLERANCE_PPM {
+                return Err(GuidedCalibrationError::NotFreshAir { co2 });
+            }
+            min = min.min(co2);
+            max = max.max(co2);
+            if max.abs_diff(min) > GUIDED_MAX_SPREAD_PPM {
+                return Err(GuidedCalibrationError::Unstable { min, max });
+            }
+
+            if waited >= dwell_ms {
+                break;
+            }
+            let step = GUIDED_READ_INTERVAL_MS.min(dwell_ms - waited);
+            delay.delay_ms(step).await;
+            waited += step;
+        }
+
+        self.calibrate_zero(ZeroCalibration::confirm_fresh_air())
+            .await
+            .map_err(GuidedCalibrationError::Sensor)
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::sim::{CountingDelay, FakeMhz};
+    use core::convert::Infallible;
+    use embedded_hal::digital::ErrorType;
+    use futures::executor::block_on;
+
+    /// Calibrates the simulated sensor on release.
+    struct HdPin<'a> {
+        sim: &'a FakeMhz,
+        pulled_low: bool,
+        works: bool,
+    }
+
+    impl ErrorType for HdPin<'_> {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for HdPin<'_> {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.pulled_low = true;
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            if self.works {
+                self.sim.set_co2(410);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn hd_pin() {
+        let sim = FakeMhz::new();
+        sim.set_co2(900);
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        let mut hd = HdPin {
+            sim: &sim,
+            pulled_low: false,
+            works: true,
+        };
+        let mut delay = CountingDelay::default();
+
+        let co2 = block_on(sensor.calibrate_zero_hd(
+            &mut hd,
+            &mut delay,
+            ZeroCalibration::confirm_fresh_air(),
+        ))
+        .unwrap();
+        assert_eq!(co2, 410);
+        assert!(hd.pulled_low);
+        assert_eq!(delay.0, HD_HOLD_MS + 1000);
+
+        sim.set_co2(900);
+        hd.works = false;
+        let err = block_on(sensor.calibrate_zero_hd(
+            &mut hd,
+            &mut delay,
+            ZeroCalibration::confirm_fresh_air(),
+        ))
+        .unwrap_err();
+        assert_eq!(err, HdCalibrationError::NotSettled { co2: Ppm(900) });
+    }
+
+    #[test]
+    fn guided() {
+        let sim = FakeMhz::new();
+        sim.set_co2(430);
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        let mut delay = CountingDelay::default();
+
+        // too short a dwell is extended to the minimum exposure
+        block_on(sensor.calibrate_zero_guided(&mut delay, 1000u32)).unwrap();
+        assert_eq!(delay.0, MIN_FRESH_AIR_EXPOSURE_MS);
+        let state = sim.state();
+        assert_eq!(state.zero_calibrations, 1);
+        let reads = MIN_FRESH_AIR_EXPOSURE_MS / GUIDED_READ_INTERVAL_MS + 1;
+        assert_eq!(state.commands_received, reads as usize + 1);
+
+        sim.set_co2(900);
+        let err = block_on(sensor.calibrate_zero_guided(&mut delay, 0u32)).unwrap_err();
+        assert_eq!(err, GuidedCalibrationError::NotFreshAir { co2: Ppm(900) });
+        assert_eq!(sim.state().zero_calibrations, 1);
+    }
+}
@@ -0,0 +1,258 @@
+//! Record UART traffic in the field and replay it at your desk.
+//!
+//! Wrap the UART halves using [`Capture::wrap`] to have every byte sent and
+//! received passed to a [`CaptureSink`] together with a timestamp. Store
+//! those records however suits you. Later feed them to [`Replay`] to run
+//! the received bytes through the same package reader the driver uses.
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+
+use embedded_io_async::{ErrorType, Read, ReadReady, Write};
+
+use crate::read_package::read_package;
+use crate::time::Clock;
+use crate::{Error, Frame, DEFAULT_RESYNC_LIMIT, DEFAULT_RX_BUFFER};
+
+/// Whether bytes were send to or received from the sensor.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Tx,
+    Rx,
+}
+
+/// The bytes moved by a single read or write.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record<'a> {
+    pub direction: Direction,
+    /// Time of the read or write in milliseconds.
+    pub timestamp_ms: u64,
+    pub bytes: &'a [u8],
+}
+
+/// Receives the captured traffic.
+pub trait CaptureSink {
+    fn record(&mut self, record: Record<'_>);
+}
+
+/// Shared between the two halves created by [`wrap`](Capture::wrap).
+pub struct Capture<S, C> {
+    sink: RefCell<S>,
+    clock: C,
+}
+
+impl<S: CaptureSink, C: Clock> Capture<S, C> {
+    pub fn new(sink: S, clock: C) -> Self {
+        Capture {
+            sink: RefCell::new(sink),
+            clock,
+        }
+    }
+
+    /// Wrap the UART halves, use the returned halves to construct
+    /// [`MHZ`](crate::MHZ).
+    pub fn wrap<Tx, Rx>(
+        &self,
+        tx: Tx,
+        rx: Rx,
+    ) -> (CaptureTx<'_, Tx, S, C>, CaptureRx<'_, Rx, S, C>) {
+        (
+            CaptureTx {
+                inner: tx,
+                capture: self,
+            },
+            CaptureRx {
+                inner: rx,
+                capture: self,
+            },
+        )
+    }
+
+    pub fn into_sink(self) -> S {
+        self.sink.into_inner()
+    }
+
+    fn record(&self, direction: Direction, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        self.sink.borrow_mut().record(Record {
+            direction,
+            timestamp_ms: self.clock.now_ms(),
+            bytes,
+        });
+    }
+}
+
+/// Transmit half passing everything written to a [`CaptureSink`].
+pub struct CaptureTx<'a, Tx, S, C> {
+    inner: Tx,
+    capture: &'a Capture<S, C>,
+}
+
+/// Receive half passing everything read to a [`CaptureSink`].
+pub struct CaptureRx<'a, Rx, S, C> {
+    inner: Rx,
+    capture: &'a Capture<S, C>,
+}
+
+impl<Tx: ErrorType, S, C> ErrorType for CaptureTx<'_, Tx, S, C> {
+    type Error = Tx::Error;
+}
+
+impl<Rx: ErrorType, S, C> ErrorType for CaptureRx<'_, Rx, S, C> {
+    type Error = Rx::Error;
+}
+
+impl<Tx: Write, S: CaptureSink, C: Clock> Write for CaptureTx<'_, Tx, S, C> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let n = self.inner.write(buf).await?;
+        self.capture.record(Direction::Tx, &buf[..n]);
+        Ok(n)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.inner.flush().await
+    }
+}
+
+impl<Rx: Read, S: CaptureSink, C: Clock> Read for CaptureRx<'_, Rx, S, C> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.inner.read(buf).await?;
+        self.capture.record(Direction::Rx, &buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<Rx: ReadReady, S, C> ReadReady for CaptureRx<'_, Rx, S, C> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        self.inner.read_ready()
+    }
+}
+
+/// Plays back the received bytes in a capture, one record per read so the
+/// package reader sees the same chunks it saw in the field. Reads past the
+/// end of the capture return EOF.
+pub struct Replay<'a> {
+    records: &'a [Record<'a>],
+    /// Bytes of the first record already read.
+    offset: usize,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(records: &'a [Record<'a>]) -> Self {
+        Replay { records, offset: 0 }
+    }
+
+    /// Run the captured bytes through the package reader the driver uses.
+    pub async fn next_package(
+        &mut self,
+        expected_command: u8,
+    ) -> Result<Frame, Error<Infallible, Infallible>> {
+        read_package::<Infallible, _, DEFAULT_RX_BUFFER>(
+            self,
+            expected_command,
+            DEFAULT_RESYNC_LIMIT,
+        )
+        .await
+        .map(Frame::from_bytes)
+    }
+
+    fn skip_tx(&mut self) {
+        while let [first, rest @ ..] = self.records {
+            if first.direction == Direction::Rx {
+                break;
+            }
+            self.records = rest;
+        }
+    }
+}
+
+impl ErrorType for Replay<'_> {
+    type Error = Infallible;
+}
+
+impl Read for Replay<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.skip_tx();
+        let [record, rest @ ..] = self.records else {
+            return Ok(0);
+        };
+
+        // a record only does not fit if the capture was made with a larger
+        // read buffer, the rest is returned by the next read
+        let remaining = &record.bytes[self.offset..];
+        let n = remaining.len().min(buf.len());
+        buf[..n].copy_from_slice(&remaining[..n]);
+        if n == remaining.len() {
+            self.records = rest;
+            self.offset = 0;
+        } else {
+            self.offset += n;
+        }
+        Ok(n)
+    }
+}
+
+impl ReadReady for Replay<'_> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        self.skip_tx();
+        Ok(!self.records.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::FakeMhz;
+    use crate::MHZ;
+    use futures::executor::block_on;
+    use std::vec::Vec;
+
+    #[derive(Default)]
+    struct VecSink(Vec<(Direction, u64, Vec<u8>)>);
+
+    impl CaptureSink for VecSink {
+        fn record(&mut self, record: Record<'_>) {
+            self.0
+                .push((record.direction, record.timestamp_ms, record.bytes.to_vec()));
+        }
+    }
+
+    #[test]
+    fn capture_and_replay() {
+        let sim = FakeMhz::new();
+        sim.set_co2(900);
+        sim.prefix_next_response(&[0x01, 0x02]);
+        let (tx, rx) = sim.split();
+
+        let capture = Capture::new(VecSink::default(), || 42);
+        {
+            let (tx, rx) = capture.wrap(tx, rx);
+            let mut sensor = MHZ::from_tx_rx(tx, rx);
+            block_on(sensor.read_co2()).unwrap();
+        }
+
+        let captured = capture.into_sink().0;
+        assert_eq!(captured[0].0, Direction::Tx);
+        assert_eq!(captured[0].2, crate::Command::ReadCo2.to_frame().as_bytes());
+        assert!(captured.iter().all(|(_, timestamp, _)| *timestamp == 42));
+
+        let records: Vec<_> = captured
+            .iter()
+            .map(|(direction, timestamp_ms, bytes)| Record {
+                direction: *direction,
+                timestamp_ms: *timestamp_ms,
+                bytes,
+            })
+            .collect();
+        let mut replay = Replay::new(&records);
+        let package = block_on(replay.next_package(0x86)).unwrap();
+        assert_eq!(
+            u16::from_be_bytes([package.payload()[0], package.payload()[1]]),
+            900
+        );
+    }
+}
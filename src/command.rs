@@ -0,0 +1,168 @@
+use crate::model::{DetectionRange, Range};
+use crate::Frame;
+
+/// Frames as listed in the MH-Z19B datasheet. A typo in any of them fails
+/// the build.
+const READ_CO2: Frame = Frame::checked([0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79]);
+const READ_RAW_CO2: Frame = Frame::checked([0xFF, 0x01, 0x85, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7a]);
+const CALIBRATE_ZERO: Frame =
+    Frame::checked([0xFF, 0x01, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00, 0x78]);
+const READ_FIRMWARE_VERSION: Frame =
+    Frame::checked([0xFF, 0x01, 0xA0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5F]);
+const READ_ABC: Frame = Frame::checked([0xFF, 0x01, 0x7D, 0x00, 0x00, 0x00, 0x00, 0x00, 0x82]);
+const READ_RANGE: Frame = Frame::checked([0xFF, 0x01, 0x9B, 0x00, 0x00, 0x00, 0x00, 0x00, 0x64]);
+
+/// Every documented command, turn one into the bytes to send using
+/// [`to_frame`](Command::to_frame).
+///
+/// ```
+/// use mhzx::Command;
+///
+/// let frame = Command::SetAbc(false).to_frame();
+/// assert_eq!(frame, [0xFF, 0x01, 0x79, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86]);
+/// ```
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Read "final" CO2 concentration.
+    ReadCo2,
+    /// Read raw CO2 concentration.
+    ReadRawCo2,
+    /// Zero point calibration, the sensor will take its current reading as 400ppm.
+    CalibrateZero,
+    /// Read the firmware version.
+    ReadFirmwareVersion,
+    /// Read whether automatic baseline correction is on.
+    ReadAbc,
+    /// Read the detection range.
+    ReadRange,
+    /// Set the detection range.
+    SetRange(Range),
+    /// Turn automatic baseline correction on or off.
+    SetAbc(bool),
+}
+
+impl Command {
+    /// The command byte, the sensor repeats it in its response.
+    pub const fn code(self) -> u8 {
+        match self {
+            Command::ReadCo2 => 0x86,
+            Command::ReadRawCo2 => 0x85,
+            Command::CalibrateZero => 0x87,
+            Command::ReadFirmwareVersion => 0xA0,
+            Command::ReadAbc => 0x7D,
+            Command::ReadRange => 0x9B,
+            Command::SetRange(_) => 0x99,
+            Command::SetAbc(_) => 0x79,
+        }
+    }
+
+    /// The command `frame` sends. None for responses, unknown commands and
+    /// frames [`to_frame`](Command::to_frame) would not produce, for
+    /// example because the checksum is wrong.
+    pub fn from_frame(frame: &Frame) -> Option<Self> {
+        if !frame.is_request() {
+            return None;
+        }
+        let args = frame.payload();
+        let command = match frame.command() {
+            0x86 => Command::ReadCo2,
+            0x85 => Command::ReadRawCo2,
+            0x87 => Command::CalibrateZero,
+            0xA0 => Command::ReadFirmwareVersion,
+            0x7D => Command::ReadAbc,
+            0x9B => Command::ReadRange,
+            0x99 => Command::SetRange(Range::from_max_ppm(u16::from_be_bytes([args[3], args[4]]))?),
+            0x79 => Command::SetAbc(args[0] == 0xA0),
+            _ => return None,
+        };
+        (command.to_frame() == *frame).then_some(command)
+    }
+
+    /// The frame to send to the sensor, including the checksum.
+    pub const fn to_frame(self) -> Frame {
+        match self {
+            Command::ReadCo2 => READ_CO2,
+            Command::ReadRawCo2 => READ_RAW_CO2,
+            Command::CalibrateZero => CALIBRATE_ZERO,
+            Command::ReadFirmwareVersion => READ_FIRMWARE_VERSION,
+            Command::ReadAbc => READ_ABC,
+            Command::ReadRange => READ_RANGE,
+            Command::SetRange(range) => {
+                let [high, low] = range.max_ppm().to_be_bytes();
+                Frame::request(self.code(), [0x00, 0x00, 0x00, high, low])
+            }
+            Command::SetAbc(enabled) => {
+                let state = if enabled { 0xA0 } else { 0x00 };
+                Frame::request(self.code(), [state, 0x00, 0x00, 0x00, 0x00])
+            }
+        }
+    }
+}
+
+// the frames written out by hand must carry their own command byte
+const _: () = {
+    let fixed = [
+        Command::ReadCo2,
+        Command::ReadRawCo2,
+        Command::CalibrateZero,
+        Command::ReadFirmwareVersion,
+        Command::ReadAbc,
+        Command::ReadRange,
+    ];
+    let mut i = 0;
+    while i < fixed.len() {
+        assert!(fixed[i].to_frame().command() == fixed[i].code());
+        i += 1;
+    }
+};
+
+impl From<Command> for Frame {
+    fn from(command: Command) -> Self {
+        command.to_frame()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        let all = [
+            Command::ReadCo2,
+            Command::ReadRawCo2,
+            Command::CalibrateZero,
+            Command::ReadFirmwareVersion,
+            Command::ReadAbc,
+            Command::ReadRange,
+            Command::SetRange(Range::Ppm5000),
+            Command::SetAbc(true),
+        ];
+        for command in all {
+            let frame = command.to_frame();
+            assert!(frame.checksum_ok(), "{command:?}");
+            assert_eq!(frame.command(), command.code());
+            assert_eq!(Command::from_frame(&frame), Some(command));
+        }
+
+        // examples from the MH-Z19B datasheet
+        assert_eq!(
+            Command::SetRange(Range::Ppm2000).to_frame(),
+            [0xFF, 0x01, 0x99, 0x00, 0x00, 0x00, 0x07, 0xD0, 0x8F]
+        );
+        assert_eq!(
+            Command::SetAbc(true).to_frame(),
+            [0xFF, 0x01, 0x79, 0xA0, 0x00, 0x00, 0x00, 0x00, 0xE6]
+        );
+        assert_eq!(
+            Command::SetAbc(false).to_frame(),
+            [0xFF, 0x01, 0x79, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86]
+        );
+
+        // a range none of the models support
+        let mut frame = [0xFF, 0x01, 0x99, 0x00, 0x00, 0x00, 0x0B, 0xB8, 0x00];
+        frame[8] = crate::measurement::checksum(&frame);
+        assert_eq!(Command::from_frame(&Frame::from_bytes(frame)), None);
+    }
+}
@@ -0,0 +1,80 @@
+//! Barometric pressure compensation.
+//!
+//! NDIR sensors count CO2 molecules in their optical path, at lower pressure
+//! there are fewer of them and the sensor under reports. The sensor is
+//! calibrated at sea level pressure, readings are corrected by scaling with
+//! the ratio between that and the actual pressure. Integer math only.
+
+use crate::{Measurement, Ppm};
+
+/// Standard sea level pressure the sensor is calibrated at in Pascal.
+pub const REFERENCE_PRESSURE_PA: u32 = 101_325;
+
+/// Standard atmosphere pressure every 500m starting at sea level.
+const PRESSURE_BY_ALTITUDE: [u32; 11] = [
+    101_325, 95_461, 89_875, 84_556, 79_495, 74_682, 70_108, 65_764, 61_640, 57_728, 54_019,
+];
+const ALTITUDE_STEP_M: u32 = 500;
+
+/// Correct a CO2 concentration measured at `pressure_pa`. Saturates at
+/// `u16::MAX`.
+pub fn compensate_pressure(co2: u16, pressure_pa: u32) -> u16 {
+    let pressure_pa = u64::from(pressure_pa.max(1));
+    let corrected = u64::from(co2) * u64::from(REFERENCE_PRESSURE_PA) / pressure_pa;
+    corrected.min(u64::from(u16::MAX)) as u16
+}
+
+/// Approximate pressure at `altitude_m` above sea level according to the
+/// standard atmosphere. Altitudes above 5000m are treated as 5000m.
+pub fn pressure_at_altitude(altitude_m: u16) -> u32 {
+    let altitude_m = u32::from(altitude_m);
+    let idx = (altitude_m / ALTITUDE_STEP_M) as usize;
+    let Some(above) = PRESSURE_BY_ALTITUDE.get(idx + 1) else {
+        return PRESSURE_BY_ALTITUDE[PRESSURE_BY_ALTITUDE.len() - 1];
+    };
+    let below = PRESSURE_BY_ALTITUDE[idx];
+    let into_step = altitude_m % ALTITUDE_STEP_M;
+    below - (below - above) * into_step / ALTITUDE_STEP_M
+}
+
+/// Correct a CO2 concentration measured at `altitude_m` above sea level.
+pub fn compensate_altitude(co2: u16, altitude_m: u16) -> u16 {
+    compensate_pressure(co2, pressure_at_altitude(altitude_m))
+}
+
+impl Measurement {
+    /// This measurement corrected for the barometric pressure it was taken
+    /// at.
+    pub fn pressure_compensated(&self, pressure_pa: u32) -> Measurement {
+        Measurement {
+            co2: Ppm(compensate_pressure(self.co2.get(), pressure_pa)),
+            ..*self
+        }
+    }
+
+    /// This measurement corrected for the altitude it was taken at.
+    pub fn altitude_compensated(&self, altitude_m: u16) -> Measurement {
+        self.pressure_compensated(pressure_at_altitude(altitude_m))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pressure() {
+        assert_eq!(compensate_pressure(400, REFERENCE_PRESSURE_PA), 400);
+        assert_eq!(compensate_pressure(400, 90_000), 450);
+        assert_eq!(compensate_pressure(u16::MAX, 50_000), u16::MAX);
+    }
+
+    #[test]
+    fn altitude() {
+        assert_eq!(pressure_at_altitude(0), REFERENCE_PRESSURE_PA);
+        assert_eq!(pressure_at_altitude(1000), 89_875);
+        assert_eq!(pressure_at_altitude(250), 98_393);
+        assert_eq!(pressure_at_altitude(9000), 54_019);
+        assert_eq!(compensate_altitude(400, 1000), 450);
+    }
+}
@@ -0,0 +1,353 @@
+//! Sensor configuration that survives a reboot. The sensor keeps its range
+//! and ABC state but a replaced sensor does not, and the software
+//! [`Correction`] only lives in the firmware. Store a [`SensorConfig`] in
+//! flash and re-apply it at boot using [`MHZ::apply_config`].
+//!
+//! With the `embedded-storage` feature `store` and `load` put it in NOR
+//! flash. Otherwise use [`SensorConfig::to_bytes`] and
+//! [`SensorConfig::from_bytes`] with any storage.
+//!
+//! ```no_run
+//! # async fn run<Tx, Rx>(
+//! #     mut sensor: mhzx::MHZ<Tx, Rx>,
+//! #     mut delay: impl embedded_hal_async::delay::DelayNs,
+//! # ) where
+//! #     Tx: embedded_io_async::Write,
+//! #     Rx: embedded_io_async::Read,
+//! # {
+//! use mhzx::config::SensorConfig;
+//! use mhzx::correction::Correction;
+//! use mhzx::model::Range;
+//!
+//! let config = SensorConfig {
+//!     range: Range::Ppm5000,
+//!     abc: false,
+//!     correction: Correction::from_ratio(1, 1, -20),
+//! };
+//! let stored = config.to_bytes();
+//! // after a reboot
+//! let config = SensorConfig::<Range>::from_bytes(&stored).unwrap();
+//! sensor.apply_config(&config, &mut delay).await.unwrap();
+//! # }
+//! ```
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+
+use crate::correction::Correction;
+use crate::model::{DetectionRange, SupportsAbc, SupportsRange};
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Error, Ppm, MHZ};
+
+/// Size of [`SensorConfig::to_bytes`].
+pub const CONFIG_SIZE: usize = 12;
+
+const MAGIC: u8 = b'M';
+const VERSION: u8 = 2;
+/// Size of a version 1 config, which only had an offset.
+const V1_SIZE: usize = 8;
+
+/// Settings to re-apply after a reboot.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SensorConfig<R> {
+    pub range: R,
+    /// Automatic baseline correction.
+    pub abc: bool,
+    /// Applied to every reading by the driver, the sensor does not know
+    /// about it.
+    pub correction: Correction,
+}
+
+impl<R: DetectionRange> SensorConfig<R> {
+    /// `co2` with the [`correction`](SensorConfig::correction) applied.
+    pub fn corrected(&self, co2: Ppm) -> Ppm {
+        self.correction.apply(co2)
+    }
+
+    /// Serialize, versioned and with a checksum.
+    pub fn to_bytes(&self) -> [u8; CONFIG_SIZE] {
+        let [range_high, range_low] = self.range.max_ppm().to_be_bytes();
+        let [g0, g1, g2, g3] = self.correction.gain.to_be_bytes();
+        let [offset_high, offset_low] = self.correction.offset_ppm.to_be_bytes();
+        let mut bytes = [
+            MAGIC,
+            VERSION,
+            range_high,
+            range_low,
+            self.abc.into(),
+            g0,
+            g1,
+            g2,
+            g3,
+            offset_high,
+            offset_low,
+            0,
+        ];
+        bytes[CONFIG_SIZE - 1] = checksum(&bytes);
+        bytes
+    }
+
+    /// None if `bytes` does not hold a config, for example erased flash, or
+    /// one with a range `R` does not have. Configs stored by older
+    /// versions, with only an offset, are still read.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.get(1) == Some(&1) {
+            return Self::from_v1_bytes(bytes);
+        }
+        let bytes: [u8; CONFIG_SIZE] = bytes.get(..CONFIG_SIZE)?.try_into().ok()?;
+        let [magic, version, range_high, range_low, abc, g0, g1, g2, g3, offset_high, offset_low, sum] =
+            bytes;
+        if magic != MAGIC || version != VERSION || sum != checksum(&bytes) || abc > 1 {
+            return None;
+        }
+        Some(SensorConfig {
+            range: R::from_max_ppm(u16::from_be_bytes([range_high, range_low]))?,
+            abc: abc == 1,
+            correction: Correction {
+                gain: u32::from_be_bytes([g0, g1, g2, g3]),
+                offset_ppm: i16::from_be_bytes([offset_high, offset_low]),
+            },
+        })
+    }
+
+    fn from_v1_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; V1_SIZE] = bytes.get(..V1_SIZE)?.try_into().ok()?;
+        let [magic, _, range_high, range_low, abc, offset_high, offset_low, sum] = bytes;
+        if magic != MAGIC || sum != checksum(&bytes) || abc > 1 {
+            return None;
+        }
+        Some(SensorConfig {
+            range: R::from_max_ppm(u16::from_be_bytes([range_high, range_low]))?,
+            abc: abc == 1,
+            correction: Correction {
+                offset_ppm: i16::from_be_bytes([offset_high, offset_low]),
+                ..Correction::NONE
+            },
+        })
+    }
+}
+
+/// Checksum over all but the last byte of a stored record, which holds it.
+pub(crate) fn checksum(bytes: &[u8]) -> u8 {
+    bytes[..bytes.len() - 1]
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+        .wrapping_neg()
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: SupportsRange + SupportsAbc,
+    P: Pacing,
+    O: Observer,
+{
+    /// Set the range and ABC state of `config`. After each command waits
+    /// [`config_settle_ms`](crate::timing::Timing::config_settle_ms) for
+    /// the sensor to apply it. Then applies its [`Correction`] using
+    /// [`set_correction`](MHZ::set_correction).
+    pub async fn apply_config(
+        &mut self,
+        config: &SensorConfig<M::Range>,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        self.set_abc(config.abc).await?;
+        delay.delay_ms(M::TIMING.config_settle_ms).await;
+        self.set_range(config.range).await?;
+        delay.delay_ms(M::TIMING.config_settle_ms).await;
+        self.set_correction(config.correction);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-storage")]
+pub(crate) mod storage {
+    use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+
+    use super::SensorConfig;
+    use crate::model::DetectionRange;
+
+    /// Largest flash read or write size supported.
+    const MAX_ACCESS_SIZE: usize = 64;
+
+    const fn padded(size: usize, access_size: usize) -> usize {
+        assert!(
+            access_size <= MAX_ACCESS_SIZE,
+            "flash access size too large"
+        );
+        size.div_ceil(access_size) * access_size
+    }
+
+    /// Erase the page at `offset` and write `bytes` padded to the write
+    /// size.
+    pub(crate) fn store_bytes<F: NorFlash, const N: usize>(
+        flash: &mut F,
+        offset: u32,
+        bytes: &[u8; N],
+    ) -> Result<(), F::Error> {
+        let len = const { padded(N, F::WRITE_SIZE) };
+        let mut buf = [0xFF; MAX_ACCESS_SIZE];
+        buf[..N].copy_from_slice(bytes);
+        flash.erase(offset, offset + F::ERASE_SIZE as u32)?;
+        flash.write(offset, &buf[..len])
+    }
+
+    /// Read back what [`store_bytes`] wrote at `offset`.
+    pub(crate) fn load_bytes<F: ReadNorFlash, const N: usize>(
+        flash: &mut F,
+        offset: u32,
+    ) -> Result<[u8; N], F::Error> {
+        let len = const { padded(N, F::READ_SIZE) };
+        let mut buf = [0u8; MAX_ACCESS_SIZE];
+        flash.read(offset, &mut buf[..len])?;
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(&buf[..N]);
+        Ok(bytes)
+    }
+
+    /// Erase the page at `offset` and write `config` to it. `offset` must
+    /// be the start of an erase page reserved for the config.
+    pub fn store<F: NorFlash, R: DetectionRange>(
+        flash: &mut F,
+        offset: u32,
+        config: &SensorConfig<R>,
+    ) -> Result<(), F::Error> {
+        store_bytes(flash, offset, &config.to_bytes())
+    }
+
+    /// Read the config [`store`] wrote at `offset`, None if there is none.
+    pub fn load<F: ReadNorFlash, R: DetectionRange>(
+        flash: &mut F,
+        offset: u32,
+    ) -> Result<Option<SensorConfig<R>>, F::Error> {
+        let bytes: [u8; super::CONFIG_SIZE] = load_bytes(flash, offset)?;
+        Ok(SensorConfig::from_bytes(&bytes))
+    }
+}
+#[cfg(feature = "embedded-storage")]
+pub use storage::{load, store};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Mhz19cRange, Range};
+
+    #[test]
+    fn round_trip() {
+        let config = SensorConfig {
+            range: Range::Ppm10000,
+            abc: true,
+            correction: Correction::from_ratio(11, 10, -20),
+        };
+        let bytes = config.to_bytes();
+        assert_eq!(SensorConfig::from_bytes(&bytes), Some(config));
+        assert_eq!(config.corrected(Ppm(400)), Ppm(420));
+        assert_eq!(config.corrected(Ppm(10)), Ppm(0));
+
+        // the MH-Z19C has no 10000ppm range
+        assert_eq!(SensorConfig::<Mhz19cRange>::from_bytes(&bytes), None);
+        assert_eq!(
+            SensorConfig::<Range>::from_bytes(&[0xFF; CONFIG_SIZE]),
+            None
+        );
+        let mut corrupt = bytes;
+        corrupt[3] ^= 1;
+        assert_eq!(SensorConfig::<Range>::from_bytes(&corrupt), None);
+    }
+
+    #[test]
+    fn reads_version_1() {
+        let stored = [b'M', 1, 0x13, 0x88, 0, 0xFF, 0xEC, 0];
+        let mut stored = stored;
+        stored[V1_SIZE - 1] = checksum(&stored);
+        assert_eq!(
+            SensorConfig::from_bytes(&stored),
+            Some(SensorConfig {
+                range: Range::Ppm5000,
+                abc: false,
+                correction: Correction {
+                    offset_ppm: -20,
+                    ..Correction::NONE
+                },
+            })
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn apply() {
+        use crate::sim::{FakeMhz, NoDelay};
+        use futures::executor::block_on;
+
+        let sim = FakeMhz::new();
+        sim.set_co2(1000);
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        let config = SensorConfig {
+            range: Range::Ppm2000,
+            abc: false,
+            correction: Correction::from_ratio(11, 10, 0),
+        };
+        block_on(sensor.apply_config(&config, &mut NoDelay)).unwrap();
+        assert_eq!(sim.state().range, Some(2000));
+        assert_eq!(sim.state().abc, Some(false));
+        assert_eq!(block_on(sensor.read_co2()).unwrap().co2, 1100);
+    }
+
+    #[cfg(feature = "embedded-storage")]
+    #[test]
+    fn flash() {
+        use core::convert::Infallible;
+        use embedded_storage::nor_flash::{ErrorType, NorFlash, ReadNorFlash};
+
+        struct Ram([u8; 256]);
+
+        impl ErrorType for Ram {
+            type Error = Infallible;
+        }
+
+        impl ReadNorFlash for Ram {
+            const READ_SIZE: usize = 4;
+
+            fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Infallible> {
+                let offset = offset as usize;
+                bytes.copy_from_slice(&self.0[offset..offset + bytes.len()]);
+                Ok(())
+            }
+
+            fn capacity(&self) -> usize {
+                self.0.len()
+            }
+        }
+
+        impl NorFlash for Ram {
+            const WRITE_SIZE: usize = 16;
+            const ERASE_SIZE: usize = 128;
+
+            fn erase(&mut self, from: u32, to: u32) -> Result<(), Infallible> {
+                self.0[from as usize..to as usize].fill(0xFF);
+                Ok(())
+            }
+
+            fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Infallible> {
+                assert_eq!(bytes.len() % Self::WRITE_SIZE, 0);
+                let offset = offset as usize;
+                self.0[offset..offset + bytes.len()].copy_from_slice(bytes);
+                Ok(())
+            }
+        }
+
+        let mut flash = Ram([0xFF; 256]);
+        assert_eq!(load::<_, Range>(&mut flash, 128), Ok(None));
+        let config = SensorConfig {
+            range: Range::Ppm2000,
+            abc: false,
+            correction: Correction::from_ratio(1, 1, 15),
+        };
+        store(&mut flash, 128, &config).unwrap();
+        assert_eq!(load(&mut flash, 128), Ok(Some(config)));
+    }
+}
@@ -0,0 +1,322 @@
+//! Software span and offset correction. When a sensor is co-located with a
+//! reference instrument its readings can be corrected in the firmware
+//! instead of recalibrating the sensor itself:
+//! `corrected = co2 * gain + offset_ppm`. The gain is fixed point, integer
+//! math only.
+//!
+//! Set it with [`MHZ::set_correction`], every [`Measurement`] the driver
+//! returns is then corrected. The sensor does not know about it, store it
+//! as part of a [`SensorConfig`](crate::config::SensorConfig), using
+//! [`Correction::to_bytes`] or, with the `embedded-storage` feature,
+//! `store` and re-apply it at boot.
+//!
+//! Feed paired readings of the sensor and a reference into
+//! [`CrossCalibration`] to find the correction.
+//!
+//! ```no_run
+//! # async fn run<Tx, Rx>(mut sensor: mhzx::MHZ<Tx, Rx>)
+//! # where
+//! #     Tx: embedded_io_async::Write,
+//! #     Rx: embedded_io_async::Read,
+//! # {
+//! use mhzx::correction::Correction;
+//!
+//! // the reference read 1030ppm where the sensor read 1000ppm, both
+//! // agree in fresh air
+//! sensor.set_correction(Correction::from_ratio(1030, 1000, -12));
+//! let stored = sensor.correction().to_bytes();
+//! // after a reboot
+//! sensor.set_correction(Correction::from_bytes(&stored).unwrap_or_default());
+//! # }
+//! ```
+
+use crate::config::checksum;
+use crate::{Measurement, Ppm, MHZ};
+
+/// Size of [`Correction::to_bytes`].
+pub const CORRECTION_SIZE: usize = 9;
+
+const MAGIC: u8 = b'C';
+const VERSION: u8 = 1;
+
+/// Correction applied to the CO2 concentration, the identity by default.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Correction {
+    /// Multiplier in units of 1/[`GAIN_ONE`](Correction::GAIN_ONE).
+    pub gain: u32,
+    /// Added after applying the gain.
+    pub offset_ppm: i16,
+}
+
+impl Default for Correction {
+    fn default() -> Self {
+        Correction::NONE
+    }
+}
+
+impl Correction {
+    /// A gain of one, 16 fractional bits.
+    pub const GAIN_ONE: u32 = 1 << 16;
+
+    /// Leaves readings as they are.
+    pub const NONE: Correction = Correction {
+        gain: Self::GAIN_ONE,
+        offset_ppm: 0,
+    };
+
+    /// Correction with a gain of `numerator / denominator`, rounded to the
+    /// nearest step. A zero `denominator` is treated as one.
+    pub const fn from_ratio(numerator: u32, denominator: u32, offset_ppm: i16) -> Self {
+        let denominator = if denominator == 0 {
+            1
+        } else {
+            denominator as u64
+        };
+        let gain = (numerator as u64 * Self::GAIN_ONE as u64 + denominator / 2) / denominator;
+        Correction {
+            gain: if gain > u32::MAX as u64 {
+                u32::MAX
+            } else {
+                gain as u32
+            },
+            offset_ppm,
+        }
+    }
+
+    /// `co2` corrected, rounded to the nearest ppm and saturating at the
+    /// bounds of [`Ppm`].
+    pub fn apply(&self, co2: Ppm) -> Ppm {
+        let scaled = (u64::from(co2.get()) * u64::from(self.gain) + u64::from(Self::GAIN_ONE / 2))
+            >> Self::GAIN_ONE.trailing_zeros();
+        let corrected = scaled as i64 + i64::from(self.offset_ppm);
+        Ppm(corrected.clamp(0, i64::from(u16::MAX)) as u16)
+    }
+
+    /// Serialize, versioned and with a checksum.
+    pub fn to_bytes(&self) -> [u8; CORRECTION_SIZE] {
+        let [g0, g1, g2, g3] = self.gain.to_be_bytes();
+        let [offset_high, offset_low] = self.offset_ppm.to_be_bytes();
+        let mut bytes = [MAGIC, VERSION, g0, g1, g2, g3, offset_high, offset_low, 0];
+        bytes[CORRECTION_SIZE - 1] = checksum(&bytes);
+        bytes
+    }
+
+    /// None if `bytes` does not hold a correction, for example erased
+    /// flash.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; CORRECTION_SIZE] = bytes.get(..CORRECTION_SIZE)?.try_into().ok()?;
+        let [magic, version, g0, g1, g2, g3, offset_high, offset_low, sum] = bytes;
+        if magic != MAGIC || version != VERSION || sum != checksum(&bytes) {
+            return None;
+        }
+        Some(Correction {
+            gain: u32::from_be_bytes([g0, g1, g2, g3]),
+            offset_ppm: i16::from_be_bytes([offset_high, offset_low]),
+        })
+    }
+}
+
+impl Measurement {
+    /// This measurement with `correction` applied to the CO2 concentration.
+    pub fn corrected(&self, correction: &Correction) -> Measurement {
+        Measurement {
+            co2: correction.apply(self.co2),
+            ..*self
+        }
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O> {
+    /// Correct every [`Measurement`] read from now on, see the
+    /// [module docs](self). Raw readings are not corrected.
+    pub fn set_correction(&mut self, correction: Correction) {
+        self.correction = correction;
+    }
+
+    /// The correction applied to every [`Measurement`], persist it if it
+    /// should survive a reboot.
+    pub fn correction(&self) -> Correction {
+        self.correction
+    }
+}
+
+/// Least squares fit of a [`Correction`] from paired readings of this
+/// sensor and a reference instrument. Keeps running sums only, any number
+/// of pairs fits. Spread the readings over the range the sensor is used
+/// in, pairs that all read about the same only determine the offset.
+#[derive(Debug, Clone, Default)]
+pub struct CrossCalibration {
+    count: u32,
+    sum_sensor: u64,
+    sum_reference: u64,
+    sum_sensor_sq: u64,
+    sum_product: u64,
+}
+
+impl CrossCalibration {
+    pub const fn new() -> Self {
+        CrossCalibration {
+            count: 0,
+            sum_sensor: 0,
+            sum_reference: 0,
+            sum_sensor_sq: 0,
+            sum_product: 0,
+        }
+    }
+
+    /// Add a pair of readings taken at the same time. Pairs beyond
+    /// `u32::MAX` are ignored.
+    pub fn add(&mut self, sensor: Ppm, reference: Ppm) {
+        let Some(count) = self.count.checked_add(1) else {
+            return;
+        };
+        let (x, y) = (u64::from(sensor.get()), u64::from(reference.get()));
+        self.count = count;
+        self.sum_sensor += x;
+        self.sum_reference += y;
+        self.sum_sensor_sq += x * x;
+        self.sum_product += x * y;
+    }
+
+    /// Number of pairs added.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// The correction that maps the sensor readings closest to the
+    /// reference. None with fewer then two distinct sensor readings or if
+    /// the fit does not fit a [`Correction`]: a negative gain or an offset
+    /// beyond `i16`.
+    pub fn fit(&self) -> Option<Correction> {
+        let n = i128::from(self.count);
+        let sum_x = i128::from(self.sum_sensor);
+        let sum_y = i128::from(self.sum_reference);
+        let one = i128::from(Correction::GAIN_ONE);
+
+        let denominator = n * i128::from(self.sum_sensor_sq) - sum_x * sum_x;
+        if denominator <= 0 {
+            return None;
+        }
+        let numerator = n * i128::from(self.sum_product) - sum_x * sum_y;
+        let gain = div_round(numerator * one, denominator);
+        let offset = div_round(sum_y * one - gain * sum_x, n * one);
+        Some(Correction {
+            gain: u32::try_from(gain).ok()?,
+            offset_ppm: i16::try_from(offset).ok()?,
+        })
+    }
+}
+
+/// `numerator / denominator` rounded to the nearest integer, `denominator`
+/// must be positive.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    (2 * numerator + denominator).div_euclid(2 * denominator)
+}
+
+#[cfg(feature = "embedded-storage")]
+mod storage {
+    use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+
+    use super::{Correction, CORRECTION_SIZE};
+    use crate::config::storage::{load_bytes, store_bytes};
+
+    /// Erase the page at `offset` and write `correction` to it. `offset`
+    /// must be the start of an erase page reserved for the correction.
+    pub fn store<F: NorFlash>(
+        flash: &mut F,
+        offset: u32,
+        correction: &Correction,
+    ) -> Result<(), F::Error> {
+        store_bytes(flash, offset, &correction.to_bytes())
+    }
+
+    /// Read the correction [`store`] wrote at `offset`, None if there is
+    /// none.
+    pub fn load<F: ReadNorFlash>(
+        flash: &mut F,
+        offset: u32,
+    ) -> Result<Option<Correction>, F::Error> {
+        let bytes: [u8; CORRECTION_SIZE] = load_bytes(flash, offset)?;
+        Ok(Correction::from_bytes(&bytes))
+    }
+}
+#[cfg(feature = "embedded-storage")]
+pub use storage::{load, store};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply() {
+        assert_eq!(Correction::NONE.apply(Ppm(1234)), Ppm(1234));
+        let correction = Correction::from_ratio(103, 100, -12);
+        assert_eq!(correction.apply(Ppm(1000)), Ppm(1018));
+        assert_eq!(correction.apply(Ppm(5)), Ppm(0));
+        assert_eq!(
+            Correction::from_ratio(2, 1, 0).apply(Ppm(u16::MAX)),
+            Ppm::MAX
+        );
+        assert_eq!(
+            Correction::from_ratio(1, 0, 0),
+            Correction::from_ratio(1, 1, 0)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let correction = Correction::from_ratio(97, 100, 25);
+        let bytes = correction.to_bytes();
+        assert_eq!(Correction::from_bytes(&bytes), Some(correction));
+        assert_eq!(Correction::from_bytes(&[0xFF; CORRECTION_SIZE]), None);
+        let mut corrupt = bytes;
+        corrupt[4] ^= 1;
+        assert_eq!(Correction::from_bytes(&corrupt), None);
+    }
+
+    #[test]
+    fn cross_calibration() {
+        let mut cal = CrossCalibration::new();
+        assert_eq!(cal.fit(), None);
+        // reference = 1.1 * sensor - 20
+        for x in [400, 600, 1000, 1500, 2000] {
+            cal.add(Ppm(x), Ppm(x + x / 10 - 20));
+        }
+        assert_eq!(cal.count(), 5);
+        let correction = cal.fit().unwrap();
+        assert_eq!(correction, Correction::from_ratio(11, 10, -20));
+        assert_eq!(correction.apply(Ppm(800)), Ppm(860));
+
+        // noisy pairs around the identity
+        let mut cal = CrossCalibration::new();
+        for (x, y) in [(400, 402), (800, 797), (1200, 1203), (1600, 1598)] {
+            cal.add(Ppm(x), Ppm(y));
+        }
+        let correction = cal.fit().unwrap();
+        assert_eq!(correction.apply(Ppm(1000)), Ppm(1000));
+
+        // a single distinct sensor reading can not determine the gain
+        let mut cal = CrossCalibration::new();
+        cal.add(Ppm(500), Ppm(510));
+        cal.add(Ppm(500), Ppm(530));
+        assert_eq!(cal.fit(), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn driver() {
+        use crate::sim::FakeMhz;
+        use futures::executor::block_on;
+
+        let sim = FakeMhz::new();
+        sim.set_co2(1000);
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        sensor.set_correction(Correction::from_ratio(11, 10, 0));
+
+        assert_eq!(block_on(sensor.read_co2()).unwrap().co2, 1100);
+        sim.with(|s| s.raw_co2 = 1000);
+        assert_eq!(block_on(sensor.read_co2_raw()).unwrap().co2, 1000);
+    }
+}
@@ -0,0 +1,210 @@
+//! Decode a hex dump of UART traffic without a sensor, for example in a
+//! web page where people paste what their logic analyzer or serial monitor
+//! captured. Needs neither std nor an allocator and builds for
+//! `wasm32-unknown-unknown`.
+//!
+//! ```
+//! use mhzx::decode::{decode_hex, Decoded};
+//! use mhzx::Command;
+//!
+//! let dump = "ff 01 86 00 00 00 00 00 79\nFF:86:01:A4:3E:00:00:00:97";
+//! let mut decoded = decode_hex(dump);
+//! assert!(matches!(decoded.next(), Some(Ok(Decoded::Command(Command::ReadCo2)))));
+//! match decoded.next() {
+//!     Some(Ok(Decoded::Measurement(m))) => assert_eq!(m.co2, 420),
+//!     _ => unreachable!(),
+//! }
+//! assert!(decoded.next().is_none());
+//! ```
+
+use core::fmt;
+use core::iter::Peekable;
+use core::str::CharIndices;
+
+use crate::{Command, Deframer, FirmwareVersion, Frame, Measurement, RawMeasurement};
+
+/// What a single frame contains.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy)]
+pub enum Decoded {
+    /// A command send to the sensor.
+    Command(Command),
+    Measurement(Measurement),
+    RawMeasurement(RawMeasurement),
+    FirmwareVersion(FirmwareVersion),
+    /// Whether automatic baseline correction is on.
+    Abc(bool),
+    /// The upper bound of the detection range in ppm.
+    Range(u16),
+    /// The checksum is right but the command is not known.
+    Unknown(Frame),
+    /// The checksum is wrong.
+    Corrupt(Frame),
+}
+
+/// Decode a single frame.
+pub fn decode(frame: Frame) -> Decoded {
+    if !frame.checksum_ok() {
+        return Decoded::Corrupt(frame);
+    }
+    if frame.is_request() {
+        return Command::from_frame(&frame)
+            .map(Decoded::Command)
+            .unwrap_or(Decoded::Unknown(frame));
+    }
+
+    let bytes = frame.into_bytes();
+    let parsed = match frame.command() {
+        0x86 => Measurement::parse_response::<(), ()>(bytes).map(Decoded::Measurement),
+        0x85 => RawMeasurement::parse_response::<(), ()>(bytes).map(Decoded::RawMeasurement),
+        0xA0 => FirmwareVersion::parse_response::<(), ()>(bytes).map(Decoded::FirmwareVersion),
+        0x7D => Ok(Decoded::Abc(bytes[7] == 1)),
+        0x9B => Ok(Decoded::Range(u16::from_be_bytes([bytes[4], bytes[5]]))),
+        _ => Ok(Decoded::Unknown(frame)),
+    };
+    parsed.unwrap_or(Decoded::Unknown(frame))
+}
+
+/// A character in the dump that is neither a hex digit nor a separator.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidHex {
+    /// Byte offset of the character in the dump.
+    pub position: usize,
+}
+
+impl fmt::Display for InvalidHex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid hex at position {}", self.position)
+    }
+}
+
+impl core::error::Error for InvalidHex {}
+
+/// The bytes in a hex dump. Bytes are two hex digits, optionally prefixed
+/// with `0x` and separated by whitespace, `,`, `:` or `-`. Stops after the
+/// first error.
+pub fn parse_hex(dump: &str) -> HexBytes<'_> {
+    HexBytes {
+        chars: dump.char_indices().peekable(),
+        len: dump.len(),
+        failed: false,
+    }
+}
+
+/// Iterator returned by [`parse_hex`].
+pub struct HexBytes<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    len: usize,
+    failed: bool,
+}
+
+fn hex_digit((position, c): (usize, char)) -> Result<u8, InvalidHex> {
+    c.to_digit(16)
+        .map(|d| d as u8)
+        .ok_or(InvalidHex { position })
+}
+
+impl HexBytes<'_> {
+    fn next_digit(&mut self) -> Result<u8, InvalidHex> {
+        let position = self.len;
+        self.chars
+            .next()
+            .map_or(Err(InvalidHex { position }), hex_digit)
+    }
+}
+
+impl Iterator for HexBytes<'_> {
+    type Item = Result<u8, InvalidHex>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let is_separator =
+            |(_, c): &(usize, char)| c.is_whitespace() || matches!(c, ',' | ':' | '-');
+        while self.chars.next_if(is_separator).is_some() {}
+
+        let first = self.chars.next()?;
+        let prefixed = first.1 == '0'
+            && self
+                .chars
+                .next_if(|(_, c)| matches!(c, 'x' | 'X'))
+                .is_some();
+        let high = if prefixed {
+            self.next_digit()
+        } else {
+            hex_digit(first)
+        };
+        let byte = high.and_then(|high| Ok(high << 4 | self.next_digit()?));
+        self.failed = byte.is_err();
+        Some(byte)
+    }
+}
+
+/// Decode every frame in a hex dump. Bytes before a 0xFF start byte and
+/// an incomplete frame at the end are skipped. A corrupt frame is reported
+/// and the search resumes from the next 0xFF inside it, like
+/// [`Deframer`] does.
+pub fn decode_hex(dump: &str) -> impl Iterator<Item = Result<Decoded, InvalidHex>> + '_ {
+    let mut bytes = parse_hex(dump);
+    let mut deframer = Deframer::new();
+    core::iter::from_fn(move || loop {
+        match bytes.next()? {
+            Ok(byte) => {
+                if let Some(frame) = deframer.push_any(byte) {
+                    return Some(Ok(decode(frame.unwrap_or_else(|corrupt| corrupt))));
+                }
+            }
+            Err(e) => return Some(Err(e)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_formats() {
+        let parsed = |dump| parse_hex(dump).collect::<Result<heapless::Vec<u8, 16>, _>>();
+
+        assert_eq!(
+            parsed("ff 01, 0x86:a0-7D").unwrap(),
+            [0xFF, 0x01, 0x86, 0xA0, 0x7D]
+        );
+        assert_eq!(parsed("ff01 86").unwrap(), [0xFF, 0x01, 0x86]);
+        assert_eq!(parsed("ff 0g"), Err(InvalidHex { position: 4 }));
+        assert_eq!(parsed("ff f"), Err(InvalidHex { position: 4 }));
+        assert_eq!(parsed("zz"), Err(InvalidHex { position: 0 }));
+    }
+
+    #[test]
+    fn frames_in_dump() {
+        let dump = "12 ff 79 00 00 00 00 00 00 87 ff 9b 00 00 13 88 00 00 ca ff 86";
+        let decoded: heapless::Vec<_, 4> = decode_hex(dump).map(Result::unwrap).collect();
+        assert!(matches!(decoded[0], Decoded::Unknown(_)));
+        assert!(matches!(decoded[1], Decoded::Range(5000)));
+        assert_eq!(decoded.len(), 2);
+
+        let mut corrupt = Command::ReadCo2.to_frame().into_bytes();
+        corrupt[8] = 0;
+        assert!(matches!(
+            decode(Frame::from_bytes(corrupt)),
+            Decoded::Corrupt(_)
+        ));
+    }
+
+    #[test]
+    fn resyncs_after_corrupt_frame() {
+        let decoded: heapless::Vec<_, 4> = decode_hex("FF FF 86 01 A4 3E 00 00 00 97")
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(decoded.len(), 2);
+        assert!(matches!(decoded[0], Decoded::Corrupt(_)));
+        match decoded[1] {
+            Decoded::Measurement(m) => assert_eq!(m.co2, 420),
+            other => panic!("{other:?}"),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::measurement::checksum_valid;
+use crate::{Frame, PAYLOAD_SIZE};
+
+/// Reassembles frames from single bytes, for use in an RX interrupt or
+/// when draining a DMA buffer.
+///
+/// Searches for the 0xFF start byte and only hands out frames with a valid
+/// checksum. When a candidate frame turns out corrupt the search resumes
+/// from the next start byte inside it, so no valid frame is lost. Never
+/// panics, whatever the bytes.
+///
+/// ```
+/// use mhzx::Deframer;
+///
+/// let mut deframer = Deframer::new();
+/// let bytes = [0x12, 0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97];
+/// let frames: Vec<_> = bytes.iter().filter_map(|b| deframer.push(*b)).collect();
+/// assert_eq!(frames, [[0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97]]);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Deframer {
+    buf: [u8; PAYLOAD_SIZE],
+    len: usize,
+}
+
+impl Deframer {
+    pub const fn new() -> Self {
+        Deframer {
+            buf: [0u8; PAYLOAD_SIZE],
+            len: 0,
+        }
+    }
+
+    /// Feed the next received byte, returns a frame once one is complete
+    /// and its checksum is valid.
+    pub fn push(&mut self, byte: u8) -> Option<Frame> {
+        self.push_any(byte).and_then(Result::ok)
+    }
+
+    /// Like [`push`](Deframer::push) but also hands out corrupt frames, as
+    /// `Err`. The search then resumes from the next start byte inside the
+    /// corrupt frame.
+    pub(crate) fn push_any(&mut self, byte: u8) -> Option<Result<Frame, Frame>> {
+        if self.len == 0 && byte != 0xFF {
+            return None;
+        }
+
+        let Some(slot) = self.buf.get_mut(self.len) else {
+            self.len = 0;
+            return None;
+        };
+        *slot = byte;
+        self.len += 1;
+        if self.len < PAYLOAD_SIZE {
+            return None;
+        }
+
+        let frame = Frame::from_bytes(self.buf);
+        if checksum_valid(&self.buf) {
+            self.len = 0;
+            return Some(Ok(frame));
+        }
+
+        trace!("corrupt frame, resyncing");
+        match self.buf.iter().skip(1).position(|byte| *byte == 0xFF) {
+            Some(pos) => {
+                let start = pos + 1;
+                self.buf.rotate_left(start % PAYLOAD_SIZE);
+                self.len = PAYLOAD_SIZE.saturating_sub(start);
+            }
+            None => self.len = 0,
+        }
+        Some(Err(frame))
+    }
+
+    /// Drop any partially received frame.
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: [u8; 9] = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97];
+
+    fn push_all(deframer: &mut Deframer, bytes: &[u8]) -> Option<Frame> {
+        let mut found = None;
+        for byte in bytes {
+            if let Some(frame) = deframer.push(*byte) {
+                assert!(found.is_none(), "only expected a single frame");
+                found = Some(frame);
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn skips_leading_garbage() {
+        let mut deframer = Deframer::new();
+        assert_eq!(push_all(&mut deframer, &[1, 2, 3]), None);
+        assert_eq!(
+            push_all(&mut deframer, &FRAME),
+            Some(Frame::from_bytes(FRAME))
+        );
+    }
+
+    #[test]
+    fn resyncs_on_start_byte_in_corrupt_frame() {
+        let mut deframer = Deframer::new();
+        // a truncated frame directly followed by a complete one
+        let mut bytes = [0u8; 13];
+        bytes[..4].copy_from_slice(&FRAME[..4]);
+        bytes[4..].copy_from_slice(&FRAME);
+        assert_eq!(
+            push_all(&mut deframer, &bytes),
+            Some(Frame::from_bytes(FRAME))
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_frame() {
+        let mut deframer = Deframer::new();
+        let mut corrupt = FRAME;
+        corrupt[2] = 0x02;
+        assert_eq!(push_all(&mut deframer, &corrupt), None);
+        assert_eq!(
+            push_all(&mut deframer, &FRAME),
+            Some(Frame::from_bytes(FRAME))
+        );
+    }
+}
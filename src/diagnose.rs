@@ -0,0 +1,256 @@
+use core::fmt;
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, ReadReady, Write};
+
+use crate::measurement::checksum_valid;
+use crate::model::Model;
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Command, Error, MHZ, MIN_COMMAND_INTERVAL_MS, PAYLOAD_SIZE};
+
+/// Likely cause of communication problems, see [`MHZ::diagnose`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// Every read succeeded.
+    Healthy,
+    /// The receiver reported end of file, it is not connected to anything
+    /// or the sensor has no power.
+    NoPower,
+    /// Nothing was received. Most likely TX and RX are swapped, otherwise
+    /// the sensor is not powered.
+    SwappedTxRx,
+    /// Only bytes that are not part of a response, or only 0x00 and 0xFF
+    /// which a line at the wrong baudrate produces. The sensor uses 9600
+    /// baud 8N1.
+    WrongBaud,
+    /// Our own command came back, TX is connected to RX. Enable
+    /// [`set_echo_cancel`](MHZ::set_echo_cancel) if that is intended.
+    Loopback,
+    /// Responses arrive but some are corrupt, check for long or unshielded
+    /// wires and a stable supply.
+    NoiseOnLine,
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Diagnosis::Healthy => "sensor communication is healthy",
+            Diagnosis::NoPower => "receiver is closed, is the sensor powered?",
+            Diagnosis::SwappedTxRx => "nothing received, are TX and RX swapped?",
+            Diagnosis::WrongBaud => "only garbage received, is the UART at 9600 baud 8N1?",
+            Diagnosis::Loopback => "our own command came back, TX is connected to RX",
+            Diagnosis::NoiseOnLine => "responses are corrupted, is there noise on the line?",
+        };
+        f.write_str(text)
+    }
+}
+
+/// What happened to a single command.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Response,
+    Eof,
+    Silent,
+    Echo,
+    ChecksumFailure,
+    OnlyZerosAndOnes,
+    Garbage,
+}
+
+fn classify(received: &[u8], eof: bool) -> Outcome {
+    let command = Command::ReadCo2.to_frame();
+    if received.starts_with(command.as_bytes()) {
+        return Outcome::Echo;
+    }
+    let mut frames = received
+        .windows(PAYLOAD_SIZE)
+        .filter(|w| w[0] == 0xFF && w[1] == command.command());
+    let frame_checksum = |w: &[u8]| checksum_valid(w.try_into().expect("window is a frame"));
+    if let Some(frame) = frames.next() {
+        if frame_checksum(frame) || frames.any(frame_checksum) {
+            return Outcome::Response;
+        }
+        return Outcome::ChecksumFailure;
+    }
+
+    if received.is_empty() {
+        if eof {
+            Outcome::Eof
+        } else {
+            Outcome::Silent
+        }
+    } else if received.iter().all(|b| *b == 0x00 || *b == 0xFF) {
+        Outcome::OnlyZerosAndOnes
+    } else {
+        Outcome::Garbage
+    }
+}
+
+fn conclude(outcomes: &[Outcome]) -> Diagnosis {
+    let all = |expected: &[Outcome]| outcomes.iter().all(|o| expected.contains(o));
+    let any = |expected: Outcome| outcomes.contains(&expected);
+
+    if all(&[Outcome::Response]) {
+        Diagnosis::Healthy
+    } else if any(Outcome::Echo) {
+        Diagnosis::Loopback
+    } else if any(Outcome::Response) || any(Outcome::ChecksumFailure) {
+        Diagnosis::NoiseOnLine
+    } else if any(Outcome::Eof) {
+        Diagnosis::NoPower
+    } else if all(&[Outcome::Silent]) {
+        Diagnosis::SwappedTxRx
+    } else {
+        Diagnosis::WrongBaud
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read + ReadReady,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Send `attempts` read commands and interpret what comes back, to find
+    /// wiring or configuration problems while bringing up a board.
+    ///
+    /// Waits [`MIN_COMMAND_INTERVAL_MS`] for each response using `delay`
+    /// and then only reads what is available, at most the resync limit, so
+    /// this returns even if nothing is connected or the line never goes
+    /// quiet. Only errors if the UART itself fails.
+    pub async fn diagnose(
+        &mut self,
+        attempts: u8,
+        delay: &mut impl DelayNs,
+    ) -> Result<Diagnosis, Error<Tx::Error, Rx::Error>> {
+        let attempts = usize::from(attempts.max(1));
+        let mut outcomes = [Outcome::Silent; u8::MAX as usize];
+        for outcome in &mut outcomes[..attempts] {
+            self.drain_rx().await?;
+            self.write_command(&Command::ReadCo2.to_frame()).await?;
+            delay.delay_ms(MIN_COMMAND_INTERVAL_MS).await;
+
+            let mut received = [0u8; 4 * PAYLOAD_SIZE];
+            let mut len = 0;
+            let mut eof = false;
+            let mut buf = [0u8; PAYLOAD_SIZE];
+            let mut read = 0usize;
+            // a line that never stops sending must not hang the diagnosis
+            while read <= self.resync_limit && self.uart_rx.read_ready().map_err(Error::Reading)? {
+                let n = self.uart_rx.read(&mut buf).await.map_err(Error::Reading)?;
+                if n == 0 {
+                    eof = true;
+                    break;
+                }
+                read = read.saturating_add(n);
+                let n = n.min(received.len() - len);
+                received[len..len + n].copy_from_slice(&buf[..n]);
+                len += n;
+            }
+            trace!("diagnose received {} bytes", len);
+
+            *outcome = classify(&received[..len], eof);
+            debug!("diagnose outcome: {:?}", outcome);
+        }
+        Ok(conclude(&outcomes[..attempts]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_outcomes() {
+        let response = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97];
+        let mut corrupt = response;
+        corrupt[8] = 0;
+
+        assert_eq!(classify(&response, false), Outcome::Response);
+        assert_eq!(classify(&[0x12, 0xFF, 0x00], false), Outcome::Garbage);
+        assert_eq!(classify(&corrupt, false), Outcome::ChecksumFailure);
+        assert_eq!(classify(&[], true), Outcome::Eof);
+        assert_eq!(classify(&[], false), Outcome::Silent);
+        assert_eq!(
+            classify(&[0xFF, 0x00, 0xFF], false),
+            Outcome::OnlyZerosAndOnes
+        );
+        assert_eq!(
+            classify(Command::ReadCo2.to_frame().as_bytes(), false),
+            Outcome::Echo
+        );
+
+        use Outcome::*;
+        assert_eq!(conclude(&[Response, Response]), Diagnosis::Healthy);
+        assert_eq!(
+            conclude(&[Response, ChecksumFailure]),
+            Diagnosis::NoiseOnLine
+        );
+        assert_eq!(conclude(&[Silent, Silent]), Diagnosis::SwappedTxRx);
+        assert_eq!(conclude(&[Eof]), Diagnosis::NoPower);
+        assert_eq!(conclude(&[OnlyZerosAndOnes, Silent]), Diagnosis::WrongBaud);
+        assert_eq!(conclude(&[Echo]), Diagnosis::Loopback);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn diagnose_simulated_sensor() {
+        use crate::sim::{FakeMhz, NoDelay};
+        use futures::executor::block_on;
+
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        let diagnose = |sensor: &mut MHZ<_, _>| block_on(sensor.diagnose(3, &mut NoDelay));
+
+        assert_eq!(diagnose(&mut sensor), Ok(Diagnosis::Healthy));
+        sim.corrupt_next(1);
+        assert_eq!(diagnose(&mut sensor), Ok(Diagnosis::NoiseOnLine));
+        sim.with(|s| s.echo = true);
+        assert_eq!(diagnose(&mut sensor), Ok(Diagnosis::Loopback));
+    }
+
+    #[test]
+    fn babbling_line() {
+        use crate::sim::NoDelay;
+        use core::convert::Infallible;
+        use embedded_io_async::ErrorType;
+        use futures::executor::block_on;
+
+        struct Sink;
+        impl ErrorType for Sink {
+            type Error = Infallible;
+        }
+        impl Write for Sink {
+            async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+                Ok(buf.len())
+            }
+        }
+
+        /// Always has more noise to read.
+        struct Babble;
+        impl ErrorType for Babble {
+            type Error = Infallible;
+        }
+        impl Read for Babble {
+            async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+                buf.fill(0x55);
+                Ok(buf.len())
+            }
+        }
+        impl ReadReady for Babble {
+            fn read_ready(&mut self) -> Result<bool, Infallible> {
+                Ok(true)
+            }
+        }
+
+        let mut sensor = MHZ::from_tx_rx(Sink, Babble);
+        let diagnosis = block_on(sensor.diagnose(2, &mut NoDelay));
+        assert_eq!(diagnosis, Ok(Diagnosis::WrongBaud));
+    }
+}
@@ -0,0 +1,39 @@
+use embassy_time::{with_deadline, with_timeout, Duration, Instant};
+use embedded_io_async::{Read, Write};
+
+use crate::model::Model;
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Error, Measurement, MHZ};
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Like [`read_co2`](MHZ::read_co2) but gives up with [`Error::Timeout`]
+    /// if the sensor does not respond within `timeout`. A late response can
+    /// be discarded using [`with_rx_drain`](MHZ::with_rx_drain).
+    pub async fn read_co2_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Measurement, Error<Tx::Error, Rx::Error>> {
+        with_timeout(timeout, self.read_co2())
+            .await
+            .unwrap_or_else(|_| Err(self.timed_out()))
+    }
+
+    /// Like [`read_co2_before`](MHZ::read_co2_before) using the embassy
+    /// clock.
+    pub async fn read_co2_before_instant(
+        &mut self,
+        deadline: Instant,
+    ) -> Result<Measurement, Error<Tx::Error, Rx::Error>> {
+        with_deadline(deadline, self.read_co2())
+            .await
+            .unwrap_or_else(|_| Err(self.timed_out()))
+    }
+}
@@ -1,74 +1,219 @@
 use core::fmt;
 
+use crate::validate::Implausibility;
+
+/// Errors talking to the sensor. `RxError` defaults to `TxError` as both
+/// halves of a UART usually share an error type, `Error<E>` then suffices.
 #[derive(Debug)]
-#[cfg_attr(feature = "thiserror", derive(thiserror::Error))]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
-#[derive(defmt::Format)]
-pub enum Error<TxError, RxError>
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "minicbor", derive(minicbor::Encode, minicbor::Decode))]
+pub enum Error<TxError, RxError = TxError> {
+    #[cfg_attr(feature = "minicbor", n(0))]
+    InvalidChecksum {
+        /// The packet as received.
+        #[cfg_attr(feature = "minicbor", cbor(n(0), with = "minicbor::bytes"))]
+        packet: [u8; 9],
+        /// Checksum computed over the packet.
+        #[cfg_attr(feature = "minicbor", n(1))]
+        computed: u8,
+        /// Checksum the packet ended with.
+        #[cfg_attr(feature = "minicbor", n(2))]
+        received: u8,
+    },
+    #[cfg_attr(feature = "minicbor", n(1))]
+    WrongStartByte(#[cfg_attr(feature = "minicbor", n(0))] u8),
+    #[cfg_attr(feature = "minicbor", n(2))]
+    UnexpectedCommand {
+        #[cfg_attr(feature = "minicbor", n(0))]
+        expected: u8,
+        #[cfg_attr(feature = "minicbor", n(1))]
+        got: u8,
+    },
+    #[cfg_attr(feature = "minicbor", n(3))]
+    WritingToUart(#[cfg_attr(feature = "minicbor", n(0))] TxError),
+    #[cfg_attr(feature = "minicbor", n(4))]
+    FlushingUart(#[cfg_attr(feature = "minicbor", n(0))] TxError),
+    #[cfg_attr(feature = "minicbor", n(5))]
+    ReadingEOF,
+    #[cfg_attr(feature = "minicbor", n(6))]
+    Reading(#[cfg_attr(feature = "minicbor", n(0))] RxError),
+    #[cfg_attr(feature = "minicbor", n(7))]
+    Desynchronized {
+        #[cfg_attr(feature = "minicbor", n(0))]
+        discarded: usize,
+    },
+    /// The sensor did not respond in time.
+    #[cfg_attr(feature = "minicbor", n(8))]
+    Timeout,
+    /// Reading back a configuration change showed the sensor ignored it.
+    #[cfg_attr(feature = "minicbor", n(9))]
+    ConfigNotApplied,
+    /// A [`Validator`](crate::validate::Validator) rejected the measurement.
+    #[cfg_attr(feature = "minicbor", n(10))]
+    Implausible(#[cfg_attr(feature = "minicbor", n(0))] Implausibility),
+}
+
+impl<TxError, RxError> Error<TxError, RxError> {
+    /// Convert both UART errors into the same type `E`.
+    pub fn unify<E>(self) -> Error<E>
+    where
+        TxError: Into<E>,
+        RxError: Into<E>,
+    {
+        match self {
+            Error::InvalidChecksum {
+                packet,
+                computed,
+                received,
+            } => Error::InvalidChecksum {
+                packet,
+                computed,
+                received,
+            },
+            Error::WrongStartByte(byte) => Error::WrongStartByte(byte),
+            Error::UnexpectedCommand { expected, got } => {
+                Error::UnexpectedCommand { expected, got }
+            }
+            Error::WritingToUart(e) => Error::WritingToUart(e.into()),
+            Error::FlushingUart(e) => Error::FlushingUart(e.into()),
+            Error::ReadingEOF => Error::ReadingEOF,
+            Error::Reading(e) => Error::Reading(e.into()),
+            Error::Desynchronized { discarded } => Error::Desynchronized { discarded },
+            Error::Timeout => Error::Timeout,
+            Error::ConfigNotApplied => Error::ConfigNotApplied,
+            Error::Implausible(details) => Error::Implausible(details),
+        }
+    }
+}
+
+impl<TxError, RxError> fmt::Display for Error<TxError, RxError>
 where
-    TxError: defmt::Format + fmt::Debug,
-    RxError: defmt::Format + fmt::Debug,
+    TxError: fmt::Display,
+    RxError: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidChecksum {
+                packet,
+                computed,
+                received,
+            } => write!(
+                f,
+                "The sensor send back a packet however it is corrupt, checksum should be {computed:#04x} but is {received:#04x}, packet: {packet:02x?}"
+            ),
+            Error::WrongStartByte(byte) => {
+                write!(f, "Response does not start with 0xFF but with: {byte:#04x}")
+            }
+            Error::UnexpectedCommand { expected, got } => write!(
+                f,
+                "Expected a response to command {expected:#04x} got one to: {got:#04x}"
+            ),
+            Error::WritingToUart(e) => write!(f, "Writing data to sensor failed: {e}"),
+            Error::FlushingUart(e) => write!(f, "Flushing data to sensor failed: {e}"),
+            Error::ReadingEOF => write!(f, "Unexpected EOF while reading from sensor"),
+            Error::Reading(e) => write!(f, "Could not read from sensor: {e}"),
+            Error::Desynchronized { discarded } => {
+                write!(f, "No valid packet found, discarded {discarded} bytes")
+            }
+            Error::Timeout => write!(f, "Sensor did not respond in time"),
+            Error::ConfigNotApplied => {
+                write!(f, "Sensor did not apply the configuration change")
+            }
+            Error::Implausible(details) => write!(f, "Implausible measurement: {details}"),
+        }
+    }
+}
+
+impl<TxError, RxError> core::error::Error for Error<TxError, RxError>
+where
+    TxError: fmt::Debug + fmt::Display,
+    RxError: fmt::Debug + fmt::Display,
 {
-    #[cfg_attr(
-        feature = "thiserror",
-        error("The sensor send back a packet however it is corrupt")
-    )]
-    InvalidChecksum,
-    #[cfg_attr(
-        feature = "thiserror",
-        error("Response header is not correct for the made request")
-    )]
-    InvalidPacket,
-    #[cfg_attr(feature = "thiserror", error("Writing data to sensor failed: {0}"))]
-    WritingToUart(TxError),
-    #[cfg_attr(feature = "thiserror", error("Flushing data to sensor failed: {0}"))]
-    FlushingUart(TxError),
-    #[cfg_attr(
-        feature = "thiserror",
-        error("Unexpected EOF while reading from sensor")
-    )]
-    ReadingEOF,
-    #[cfg_attr(feature = "thiserror", error("Could not read from sensor: {0}"))]
-    Reading(RxError),
 }
 
 impl<TxError, RxError> Clone for Error<TxError, RxError>
 where
-    TxError: defmt::Format + fmt::Debug + Clone,
-    RxError: defmt::Format + fmt::Debug + Clone,
+    TxError: Clone,
+    RxError: Clone,
 {
     fn clone(&self) -> Self {
         match self {
-            Error::InvalidChecksum => Error::InvalidChecksum,
-            Error::InvalidPacket => Error::InvalidPacket,
+            Error::InvalidChecksum {
+                packet,
+                computed,
+                received,
+            } => Error::InvalidChecksum {
+                packet: *packet,
+                computed: *computed,
+                received: *received,
+            },
+            Error::WrongStartByte(byte) => Error::WrongStartByte(*byte),
+            Error::UnexpectedCommand { expected, got } => Error::UnexpectedCommand {
+                expected: *expected,
+                got: *got,
+            },
             Error::WritingToUart(e) => Error::WritingToUart(e.clone()),
             Error::FlushingUart(e) => Error::FlushingUart(e.clone()),
             Error::ReadingEOF => Error::ReadingEOF,
+            Error::Timeout => Error::Timeout,
+            Error::ConfigNotApplied => Error::ConfigNotApplied,
+            Error::Implausible(details) => Error::Implausible(*details),
             Error::Reading(e) => Error::Reading(e.clone()),
+            Error::Desynchronized { discarded } => Error::Desynchronized {
+                discarded: *discarded,
+            },
         }
     }
 }
 
 impl<TxError, RxError> Eq for Error<TxError, RxError>
 where
-    TxError: defmt::Format + fmt::Debug + Eq,
-    RxError: defmt::Format + fmt::Debug + Eq,
+    TxError: Eq,
+    RxError: Eq,
 {
 }
 
 impl<TxError, RxError> PartialEq for Error<TxError, RxError>
 where
-    TxError: defmt::Format + fmt::Debug + PartialEq,
-    RxError: defmt::Format + fmt::Debug + PartialEq,
+    TxError: PartialEq,
+    RxError: PartialEq,
 {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Error::ReadingEOF, Error::ReadingEOF)
-            | (Error::InvalidChecksum, Error::InvalidChecksum)
-            | (Error::InvalidPacket, Error::InvalidPacket) => true,
+            | (Error::Timeout, Error::Timeout)
+            | (Error::ConfigNotApplied, Error::ConfigNotApplied) => true,
+            (
+                Error::InvalidChecksum {
+                    packet,
+                    computed,
+                    received,
+                },
+                Error::InvalidChecksum {
+                    packet: packet2,
+                    computed: computed2,
+                    received: received2,
+                },
+            ) => packet == packet2 && computed == computed2 && received == received2,
+            (Error::WrongStartByte(b), Error::WrongStartByte(b2)) => b == b2,
+            (Error::Implausible(details), Error::Implausible(details2)) => details == details2,
+            (
+                Error::UnexpectedCommand { expected, got },
+                Error::UnexpectedCommand {
+                    expected: expected2,
+                    got: got2,
+                },
+            ) => expected == expected2 && got == got2,
             (Error::WritingToUart(e), Error::WritingToUart(e2))
             | (Error::FlushingUart(e), Error::FlushingUart(e2)) => e == e2,
             (Error::Reading(e), Error::Reading(e2)) => e == e2,
+            (
+                Error::Desynchronized { discarded },
+                Error::Desynchronized {
+                    discarded: discarded2,
+                },
+            ) => discarded == discarded2,
             (_, _) => false,
         }
     }
@@ -86,9 +231,258 @@ const fn max(a: usize, b: usize) -> usize {
 #[cfg(feature = "postcard")]
 impl<TxError, RxError> postcard::experimental::max_size::MaxSize for Error<TxError, RxError>
 where
-    TxError: postcard::experimental::max_size::MaxSize + core::fmt::Debug + defmt::Format,
-    RxError: postcard::experimental::max_size::MaxSize + core::fmt::Debug + defmt::Format,
+    TxError: postcard::experimental::max_size::MaxSize,
+    RxError: postcard::experimental::max_size::MaxSize,
 {
-    const POSTCARD_MAX_SIZE: usize =
-        1 + max(TxError::POSTCARD_MAX_SIZE, RxError::POSTCARD_MAX_SIZE);
+    const POSTCARD_MAX_SIZE: usize = 1 + max(
+        max(TxError::POSTCARD_MAX_SIZE, RxError::POSTCARD_MAX_SIZE),
+        max(
+            max(usize::POSTCARD_MAX_SIZE, Implausibility::POSTCARD_MAX_SIZE),
+            <[u8; 9]>::POSTCARD_MAX_SIZE + u8::POSTCARD_MAX_SIZE + u8::POSTCARD_MAX_SIZE,
+        ),
+    );
+}
+
+#[cfg(feature = "postcard-schema")]
+mod schema {
+    use postcard_schema::schema::NamedType;
+    use postcard_schema::Schema;
+
+    use crate::validate::Implausibility;
+
+    /// Mirrors [`Error`](super::Error). Postcard has no schema for `usize`,
+    /// it encodes it as the same varint as a `u64`.
+    #[allow(dead_code)]
+    #[derive(Schema)]
+    #[cfg_attr(test, derive(serde::Serialize))]
+    enum Error<TxError, RxError> {
+        InvalidChecksum {
+            packet: [u8; 9],
+            computed: u8,
+            received: u8,
+        },
+        WrongStartByte(u8),
+        UnexpectedCommand {
+            expected: u8,
+            got: u8,
+        },
+        WritingToUart(TxError),
+        FlushingUart(TxError),
+        ReadingEOF,
+        Reading(RxError),
+        Desynchronized {
+            discarded: u64,
+        },
+        Timeout,
+        ConfigNotApplied,
+        Implausible(Implausibility),
+    }
+
+    impl<TxError: Schema, RxError: Schema> Schema for super::Error<TxError, RxError> {
+        const SCHEMA: &'static NamedType = Error::<TxError, RxError>::SCHEMA;
+    }
+
+    /// Keeps the mirror in step: a variant or field added to the real
+    /// error fails to compile here. The test below checks the order.
+    impl<TxError, RxError> From<super::Error<TxError, RxError>> for Error<TxError, RxError> {
+        fn from(e: super::Error<TxError, RxError>) -> Self {
+            use super::Error as E;
+            match e {
+                E::InvalidChecksum {
+                    packet,
+                    computed,
+                    received,
+                } => Error::InvalidChecksum {
+                    packet,
+                    computed,
+                    received,
+                },
+                E::WrongStartByte(byte) => Error::WrongStartByte(byte),
+                E::UnexpectedCommand { expected, got } => {
+                    Error::UnexpectedCommand { expected, got }
+                }
+                E::WritingToUart(e) => Error::WritingToUart(e),
+                E::FlushingUart(e) => Error::FlushingUart(e),
+                E::ReadingEOF => Error::ReadingEOF,
+                E::Reading(e) => Error::Reading(e),
+                E::Desynchronized { discarded } => Error::Desynchronized {
+                    discarded: discarded as u64,
+                },
+                E::Timeout => Error::Timeout,
+                E::ConfigNotApplied => Error::ConfigNotApplied,
+                E::Implausible(reason) => Error::Implausible(reason),
+            }
+        }
+    }
+
+    #[cfg(all(test, feature = "postcard"))]
+    mod tests {
+        use super::*;
+        use crate::Error as E;
+
+        #[test]
+        fn mirror_encodes_like_error() {
+            let all: [E<u8, u8>; 11] = [
+                E::InvalidChecksum {
+                    packet: [0xFF; 9],
+                    computed: 1,
+                    received: 2,
+                },
+                E::WrongStartByte(3),
+                E::UnexpectedCommand {
+                    expected: 4,
+                    got: 5,
+                },
+                E::WritingToUart(6),
+                E::FlushingUart(7),
+                E::ReadingEOF,
+                E::Reading(8),
+                E::Desynchronized { discarded: 300 },
+                E::Timeout,
+                E::ConfigNotApplied,
+                E::Implausible(Implausibility::Temperature { celsius: -40 }),
+            ];
+            for error in all {
+                let mut expected = [0u8; 32];
+                let mut got = [0u8; 32];
+                let expected = postcard::to_slice(&error, &mut expected).unwrap();
+                let got = postcard::to_slice(&Error::from(error.clone()), &mut got).unwrap();
+                assert_eq!(got, expected, "{error:?}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+mod std_io {
+    extern crate std;
+
+    use super::Error;
+    use core::fmt;
+    use embedded_io_async::ErrorKind;
+    use std::io;
+
+    fn io_kind(kind: ErrorKind) -> io::ErrorKind {
+        match kind {
+            ErrorKind::NotFound => io::ErrorKind::NotFound,
+            ErrorKind::PermissionDenied => io::ErrorKind::PermissionDenied,
+            ErrorKind::ConnectionRefused => io::ErrorKind::ConnectionRefused,
+            ErrorKind::ConnectionReset => io::ErrorKind::ConnectionReset,
+            ErrorKind::ConnectionAborted => io::ErrorKind::ConnectionAborted,
+            ErrorKind::NotConnected => io::ErrorKind::NotConnected,
+            ErrorKind::AddrInUse => io::ErrorKind::AddrInUse,
+            ErrorKind::AddrNotAvailable => io::ErrorKind::AddrNotAvailable,
+            ErrorKind::BrokenPipe => io::ErrorKind::BrokenPipe,
+            ErrorKind::AlreadyExists => io::ErrorKind::AlreadyExists,
+            ErrorKind::InvalidInput => io::ErrorKind::InvalidInput,
+            ErrorKind::InvalidData => io::ErrorKind::InvalidData,
+            ErrorKind::TimedOut => io::ErrorKind::TimedOut,
+            ErrorKind::Interrupted => io::ErrorKind::Interrupted,
+            ErrorKind::Unsupported => io::ErrorKind::Unsupported,
+            ErrorKind::OutOfMemory => io::ErrorKind::OutOfMemory,
+            ErrorKind::WriteZero => io::ErrorKind::WriteZero,
+            _ => io::ErrorKind::Other,
+        }
+    }
+
+    /// Corrupt or unexpected responses become [`io::ErrorKind::InvalidData`],
+    /// UART errors keep their [`ErrorKind`]. The original error is the
+    /// source of the [`io::Error`].
+    impl<TxError, RxError> From<Error<TxError, RxError>> for io::Error
+    where
+        TxError: embedded_io_async::Error + fmt::Display + Send + Sync + 'static,
+        RxError: embedded_io_async::Error + fmt::Display + Send + Sync + 'static,
+    {
+        fn from(e: Error<TxError, RxError>) -> Self {
+            let kind = match &e {
+                Error::InvalidChecksum { .. }
+                | Error::WrongStartByte(_)
+                | Error::UnexpectedCommand { .. }
+                | Error::Desynchronized { .. }
+                | Error::Implausible(_) => io::ErrorKind::InvalidData,
+                Error::ReadingEOF => io::ErrorKind::UnexpectedEof,
+                Error::Timeout => io::ErrorKind::TimedOut,
+                Error::ConfigNotApplied => io::ErrorKind::Other,
+                Error::WritingToUart(e) | Error::FlushingUart(e) => io_kind(e.kind()),
+                Error::Reading(e) => io_kind(e.kind()),
+            };
+            io::Error::new(kind, e)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use core::convert::Infallible;
+        use std::string::ToString;
+
+        #[test]
+        fn kinds() {
+            let e: io::Error = Error::<Infallible, Infallible>::ReadingEOF.into();
+            assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+
+            let e: io::Error = Error::<Infallible, io::Error>::Reading(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "unplugged",
+            ))
+            .into();
+            assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+            assert_eq!(e.to_string(), "Could not read from sensor: unplugged");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use core::convert::Infallible;
+
+    #[derive(Debug, PartialEq)]
+    struct AppError(u8);
+
+    impl From<Infallible> for AppError {
+        fn from(e: Infallible) -> Self {
+            match e {}
+        }
+    }
+
+    impl From<u8> for AppError {
+        fn from(code: u8) -> Self {
+            AppError(code)
+        }
+    }
+
+    #[test]
+    fn unify() {
+        let e: Error<AppError> = Error::<Infallible, u8>::Reading(3).unify();
+        assert_eq!(e, Error::Reading(AppError(3)));
+        let e: Error<AppError> = Error::<Infallible, u8>::Timeout.unify();
+        assert_eq!(e, Error::Timeout);
+    }
+
+    #[cfg(feature = "postcard-schema")]
+    #[test]
+    fn schema() {
+        use postcard_schema::schema::DataModelType;
+        use postcard_schema::Schema;
+
+        let schema = Error::<u8>::SCHEMA;
+        assert_eq!(schema.name, "Error");
+        let DataModelType::Enum(variants) = schema.ty else {
+            panic!("Error is an enum");
+        };
+        assert!(variants.iter().map(|v| v.name).eq([
+            "InvalidChecksum",
+            "WrongStartByte",
+            "UnexpectedCommand",
+            "WritingToUart",
+            "FlushingUart",
+            "ReadingEOF",
+            "Reading",
+            "Desynchronized",
+            "Timeout",
+            "ConfigNotApplied",
+            "Implausible",
+        ]));
+    }
 }
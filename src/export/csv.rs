@@ -0,0 +1,85 @@
+//! CSV lines for data loggers, for example writing to an SD card using
+//! `embedded-sdmmc`. Write [`HEADER`] once when creating the file, then one
+//! [`write_record`] per measurement.
+//!
+//! ```
+//! use heapless::String;
+//! use mhzx::export::csv;
+//! use mhzx::{Measurement, Ppm};
+//!
+//! let measurement = Measurement {
+//!     co2: Ppm(612),
+//!     temp: 61,
+//!     calib_ticks: 3,
+//!     calib_cycles: 1,
+//!     undocumented_u: 0,
+//! };
+//! let mut line: String<64> = String::new();
+//! csv::write_record(&mut line, 1_700_000_000, &measurement).unwrap();
+//! assert_eq!(line, "1700000000,612,21,3,1\n");
+//! ```
+
+use core::fmt::{self, Write};
+
+#[cfg(feature = "heapless")]
+use heapless::String;
+
+use crate::Measurement;
+
+/// Column names, ends with a newline.
+pub const HEADER: &str = "timestamp,co2_ppm,temperature_celsius,calib_ticks,calib_cycles\n";
+
+/// Like [`write_record`] but returns the line. Fails if it does not fit
+/// in `N` bytes.
+#[cfg(feature = "heapless")]
+pub fn record<const N: usize>(
+    timestamp: impl fmt::Display,
+    measurement: &Measurement,
+) -> Result<String<N>, fmt::Error> {
+    let mut out = String::new();
+    write_record(&mut out, timestamp, measurement)?;
+    Ok(out)
+}
+
+/// One line for `measurement`, written to `out`, for example a file
+/// implementing [`fmt::Write`]. The `timestamp` column is written as is,
+/// pass an empty string if there is no clock.
+pub fn write_record(
+    out: &mut impl Write,
+    timestamp: impl fmt::Display,
+    measurement: &Measurement,
+) -> fmt::Result {
+    writeln!(
+        out,
+        "{},{},{},{},{}",
+        timestamp,
+        measurement.co2.get(),
+        measurement.temp_celsius(),
+        measurement.calib_ticks,
+        measurement.calib_cycles
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ppm;
+
+    #[test]
+    fn record_matches_header() {
+        let measurement = Measurement {
+            co2: Ppm(400),
+            temp: 35,
+            calib_ticks: 0,
+            calib_cycles: 0,
+            undocumented_u: 0,
+        };
+        let mut line = heapless::String::<64>::new();
+        write_record(&mut line, "", &measurement).unwrap();
+        assert_eq!(line, ",400,-5,0,0\n");
+        assert_eq!(line.split(',').count(), HEADER.split(',').count());
+
+        #[cfg(feature = "heapless")]
+        assert!(record::<8>(12, &measurement).is_err());
+    }
+}
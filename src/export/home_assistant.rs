@@ -0,0 +1,218 @@
+//! Home Assistant MQTT discovery. Publish the
+//! [config](Discovery::write_config) (retained) to its
+//! [topic](Discovery::write_config_topic) for each [`Entity`] once, then
+//! the [state](write_state) to the state topic after every measurement.
+//! With the `heapless` feature `config`, `config_topic` and `state` return
+//! them as strings.
+//!
+//! ```
+//! use heapless::String;
+//! use mhzx::export::home_assistant::{write_state, Discovery, Entity};
+//! use mhzx::model::Mhz19B;
+//! use mhzx::{Measurement, Ppm};
+//!
+//! let discovery = Discovery::new("livingroom_co2", "Living room", "sensors/livingroom/co2");
+//! let mut topic: String<64> = String::new();
+//! discovery.write_config_topic(&mut topic, Entity::Co2).unwrap();
+//! assert_eq!(topic, "homeassistant/sensor/livingroom_co2/co2/config");
+//! let mut config: String<512> = String::new();
+//! discovery.write_config::<Mhz19B>(&mut config, Entity::Co2).unwrap();
+//!
+//! let measurement = Measurement {
+//!     co2: Ppm(612),
+//!     temp: 61,
+//!     calib_ticks: 0,
+//!     calib_cycles: 0,
+//!     undocumented_u: 0,
+//! };
+//! let mut payload: String<48> = String::new();
+//! write_state::<Mhz19B>(&mut payload, &measurement).unwrap();
+//! assert_eq!(payload, r#"{"co2":612,"temperature":21}"#);
+//! ```
+
+use core::fmt::{self, Write};
+
+#[cfg(feature = "heapless")]
+use heapless::String;
+
+use crate::model::{reports_temperature, Model};
+use crate::Measurement;
+
+/// The topic prefix Home Assistant listens on by default.
+pub const DEFAULT_DISCOVERY_PREFIX: &str = "homeassistant";
+
+/// The values the sensor reports.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    Co2,
+    /// Only meaningful if the model reports temperature, see
+    /// [`Model::Temperature`].
+    Temperature,
+}
+
+impl Entity {
+    fn object_id(self) -> &'static str {
+        match self {
+            Entity::Co2 => "co2",
+            Entity::Temperature => "temperature",
+        }
+    }
+}
+
+/// Describes one sensor to Home Assistant.
+#[derive(Debug, Clone, Copy)]
+pub struct Discovery<'a> {
+    /// Unique id of the sensor, only `[a-zA-Z0-9_-]` is allowed.
+    pub node_id: &'a str,
+    /// Name of the device shown in Home Assistant.
+    pub device_name: &'a str,
+    /// Topic [`write_state`] payloads are published to.
+    pub state_topic: &'a str,
+    pub discovery_prefix: &'a str,
+}
+
+impl<'a> Discovery<'a> {
+    pub fn new(node_id: &'a str, device_name: &'a str, state_topic: &'a str) -> Self {
+        Discovery {
+            node_id,
+            device_name,
+            state_topic,
+            discovery_prefix: DEFAULT_DISCOVERY_PREFIX,
+        }
+    }
+
+    /// Like [`write_config_topic`](Self::write_config_topic) but returns
+    /// the topic. Fails if it does not fit in `N` bytes.
+    #[cfg(feature = "heapless")]
+    pub fn config_topic<const N: usize>(&self, entity: Entity) -> Result<String<N>, fmt::Error> {
+        let mut topic = String::new();
+        self.write_config_topic(&mut topic, entity)?;
+        Ok(topic)
+    }
+
+    /// Write the topic to publish the config of `entity` to.
+    pub fn write_config_topic(&self, out: &mut impl Write, entity: Entity) -> fmt::Result {
+        write!(
+            out,
+            "{}/sensor/{}/{}/config",
+            self.discovery_prefix,
+            self.node_id,
+            entity.object_id()
+        )
+    }
+
+    /// Like [`write_config`](Self::write_config) but returns the config.
+    /// Fails if it does not fit in `N` bytes.
+    #[cfg(feature = "heapless")]
+    pub fn config<M: Model, const N: usize>(
+        &self,
+        entity: Entity,
+    ) -> Result<String<N>, fmt::Error> {
+        let mut out = String::new();
+        self.write_config::<M>(&mut out, entity)?;
+        Ok(out)
+    }
+
+    /// Write the discovery config of `entity`.
+    pub fn write_config<M: Model>(&self, out: &mut impl Write, entity: Entity) -> fmt::Result {
+        let (name, device_class, unit) = match entity {
+            Entity::Co2 => ("CO2", "carbon_dioxide", "ppm"),
+            Entity::Temperature => ("Temperature", "temperature", "°C"),
+        };
+        let object_id = entity.object_id();
+
+        write!(out, r#"{{"name":"{name}","unique_id":""#)?;
+        write_escaped(out, self.node_id)?;
+        write!(
+            out,
+            r#"_{object_id}","device_class":"{device_class}","state_class":"measurement","unit_of_measurement":"{unit}","state_topic":""#
+        )?;
+        write_escaped(out, self.state_topic)?;
+        write!(
+            out,
+            r#"","value_template":"{{{{ value_json.{object_id} }}}}","device":{{"identifiers":[""#
+        )?;
+        write_escaped(out, self.node_id)?;
+        write!(out, r#""],"name":""#)?;
+        write_escaped(out, self.device_name)?;
+        write!(
+            out,
+            r#"","manufacturer":"Winsen","model":"{}"}}}}"#,
+            M::NAME
+        )
+    }
+}
+
+/// Like [`write_state`] but returns the payload. Fails if it does not fit
+/// in `N` bytes.
+#[cfg(feature = "heapless")]
+pub fn state<M: Model, const N: usize>(measurement: &Measurement) -> Result<String<N>, fmt::Error> {
+    let mut out = String::new();
+    write_state::<M>(&mut out, measurement)?;
+    Ok(out)
+}
+
+/// Write the state payload for all entities of one measurement. The
+/// temperature is left out if `M` does not report it.
+pub fn write_state<M: Model>(out: &mut impl Write, measurement: &Measurement) -> fmt::Result {
+    write!(out, r#"{{"co2":{}"#, measurement.co2.get())?;
+    if reports_temperature::<M>() {
+        write!(out, r#","temperature":{}"#, measurement.temp_celsius())?;
+    }
+    out.write_char('}')
+}
+
+fn write_escaped(out: &mut impl Write, value: &str) -> fmt::Result {
+    for c in value.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Mhz16;
+
+    #[test]
+    fn config() {
+        let discovery = Discovery::new("office", "Office \"CO2\"", "office/co2");
+        let mut config = heapless::String::<512>::new();
+        discovery
+            .write_config::<Mhz16>(&mut config, Entity::Co2)
+            .unwrap();
+        assert_eq!(
+            config,
+            concat!(
+                r#"{"name":"CO2","unique_id":"office_co2","device_class":"carbon_dioxide","#,
+                r#""state_class":"measurement","unit_of_measurement":"ppm","#,
+                r#""state_topic":"office/co2","value_template":"{{ value_json.co2 }}","#,
+                r#""device":{"identifiers":["office"],"name":"Office \"CO2\"","#,
+                r#""manufacturer":"Winsen","model":"MH-Z16"}}"#,
+            )
+        );
+
+        #[cfg(feature = "heapless")]
+        assert!(discovery.config::<Mhz16, 64>(Entity::Co2).is_err());
+    }
+
+    #[test]
+    fn state_without_temperature() {
+        let measurement = Measurement {
+            co2: crate::Ppm(812),
+            temp: 0,
+            calib_ticks: 0,
+            calib_cycles: 0,
+            undocumented_u: 0,
+        };
+        let mut payload = heapless::String::<48>::new();
+        write_state::<Mhz16>(&mut payload, &measurement).unwrap();
+        assert_eq!(payload, r#"{"co2":812}"#);
+    }
+}
@@ -0,0 +1,6 @@
+//! Encoders turning measurements into the formats of other systems.
+
+pub mod csv;
+pub mod home_assistant;
+#[cfg(feature = "std")]
+pub mod prometheus;
@@ -0,0 +1,112 @@
+//! The Prometheus text exposition format.
+//!
+//! ```
+//! use mhzx::export::prometheus;
+//! use mhzx::{Measurement, Ppm};
+//!
+//! let measurement = Measurement {
+//!     co2: Ppm(612),
+//!     temp: 61,
+//!     calib_ticks: 0,
+//!     calib_cycles: 0,
+//!     undocumented_u: 0,
+//! };
+//! let mut body = String::new();
+//! prometheus::encode(&measurement, &mut body);
+//! assert!(body.contains("mhz_co2_ppm 612\n"));
+//! ```
+
+extern crate std;
+
+use core::fmt::Write;
+use std::string::String;
+
+use crate::Measurement;
+
+/// Name of the CO2 concentration gauge.
+pub const CO2_METRIC: &str = "mhz_co2_ppm";
+/// Name of the temperature gauge.
+pub const TEMPERATURE_METRIC: &str = "mhz_temperature_celsius";
+
+/// Append gauges for the CO2 concentration and temperature to `out`.
+pub fn encode(measurement: &Measurement, out: &mut String) {
+    encode_labeled(measurement, &[], out)
+}
+
+/// Like [`encode`] but with `labels`, for example to tell sensors apart.
+/// Label values are escaped.
+pub fn encode_labeled(measurement: &Measurement, labels: &[(&str, &str)], out: &mut String) {
+    gauge(
+        out,
+        CO2_METRIC,
+        "CO2 concentration in parts per million.",
+        labels,
+        i32::from(measurement.co2.get()),
+    );
+    gauge(
+        out,
+        TEMPERATURE_METRIC,
+        "Temperature reported by the sensor in degrees Celsius.",
+        labels,
+        measurement.temp_celsius() as i32,
+    );
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, labels: &[(&str, &str)], value: i32) {
+    // writing to a String can not fail
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    out.push_str(name);
+    if !labels.is_empty() {
+        out.push('{');
+        for (i, (key, value)) in labels.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(key);
+            out.push_str("=\"");
+            escape(value, out);
+            out.push('"');
+        }
+        out.push('}');
+    }
+    let _ = writeln!(out, " {value}");
+}
+
+fn escape(value: &str, out: &mut String) {
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_escaped() {
+        let measurement = Measurement {
+            co2: crate::Ppm(420),
+            temp: 62,
+            calib_ticks: 0,
+            calib_cycles: 0,
+            undocumented_u: 0,
+        };
+        let mut out = String::new();
+        encode_labeled(&measurement, &[("room", "a \"b\"")], &mut out);
+        assert_eq!(
+            out,
+            "# HELP mhz_co2_ppm CO2 concentration in parts per million.\n\
+             # TYPE mhz_co2_ppm gauge\n\
+             mhz_co2_ppm{room=\"a \\\"b\\\"\"} 420\n\
+             # HELP mhz_temperature_celsius Temperature reported by the sensor in degrees Celsius.\n\
+             # TYPE mhz_temperature_celsius gauge\n\
+             mhz_temperature_celsius{room=\"a \\\"b\\\"\"} 22\n"
+        );
+    }
+}
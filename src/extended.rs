@@ -0,0 +1,151 @@
+//! Commands that are not in any datasheet but were found by the community,
+//! mostly documented at <https://revspace.nl/MH-Z19B> and in the WifWaf
+//! MH-Z19 Arduino library.
+//!
+//! **Use at your own risk.** Their meaning was reverse engineered, they may
+//! behave differently between firmware versions and some can leave the
+//! sensor in a state only a power cycle recovers from. Every method needs
+//! an [`AtOwnRisk`] token to make that explicit.
+//!
+//! Only commands whose effect and response are consistently described get
+//! a method. Others, such as changing the ABC period, are reported with
+//! conflicting encodings, send those using
+//! [`send_extended`](MHZ::send_extended) or
+//! [`transact_extended`](MHZ::transact_extended).
+//!
+//! ```no_run
+//! # async fn run<Tx, Rx>(mut sensor: mhzx::MHZ<Tx, Rx>)
+//! # where
+//! #     Tx: embedded_io_async::Write,
+//! #     Rx: embedded_io_async::Read,
+//! # {
+//! use mhzx::extended::AtOwnRisk;
+//!
+//! let risk = AtOwnRisk::accept();
+//! let background = sensor.read_background_co2(risk).await.unwrap();
+//! # }
+//! ```
+
+use embedded_io_async::{Read, Write};
+
+use crate::measurement::check_header;
+use crate::model::Configurable;
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Error, Frame, Ppm, MHZ};
+
+/// Undocumented command bytes.
+pub mod command {
+    /// Restart the sensor.
+    pub const RECOVERY_RESET: u8 = 0x78;
+    /// Reset the sensor, what it resets is unclear.
+    pub const RESET: u8 = 0x8D;
+    /// Read the concentration ABC assumes as fresh air.
+    pub const READ_BACKGROUND_CO2: u8 = 0x9C;
+}
+
+/// Acknowledges that undocumented commands might misbehave, see the
+/// [module docs](self).
+#[derive(Debug, Clone, Copy)]
+pub struct AtOwnRisk {
+    _private: (),
+}
+
+impl AtOwnRisk {
+    /// Accept that the sensor might misbehave or need a power cycle.
+    pub fn accept() -> Self {
+        AtOwnRisk { _private: () }
+    }
+}
+
+/// Frame for undocumented `command` with arguments `args` for bytes 3 to 7,
+/// including the checksum.
+pub const fn frame(command: u8, args: [u8; 5]) -> Frame {
+    Frame::request(command, args)
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Configurable,
+    P: Pacing,
+    O: Observer,
+{
+    /// Send any command the sensor does not respond to.
+    pub async fn send_extended(
+        &mut self,
+        command: u8,
+        args: [u8; 5],
+        _risk: AtOwnRisk,
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        debug!("sending undocumented command {}", command);
+        self.write_command(&frame(command, args)).await
+    }
+
+    /// Send any command and return the response to it, its checksum is
+    /// verified.
+    pub async fn transact_extended(
+        &mut self,
+        command: u8,
+        args: [u8; 5],
+        _risk: AtOwnRisk,
+    ) -> Result<Frame, Error<Tx::Error, Rx::Error>> {
+        debug!("sending undocumented command {}", command);
+        let response = self.transact(&frame(command, args)).await?;
+        check_header(&response, command)?;
+        Ok(Frame::from_bytes(response))
+    }
+
+    /// Restart the sensor (command 0x78). It does not respond.
+    pub async fn recovery_reset(
+        &mut self,
+        risk: AtOwnRisk,
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        self.send_extended(command::RECOVERY_RESET, [0; 5], risk)
+            .await
+    }
+
+    /// Reset the sensor (command 0x8D). It does not respond.
+    pub async fn reset(&mut self, risk: AtOwnRisk) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        self.send_extended(command::RESET, [0; 5], risk).await
+    }
+
+    /// The concentration automatic baseline correction takes as fresh air
+    /// (command 0x9C), usually 400ppm.
+    pub async fn read_background_co2(
+        &mut self,
+        risk: AtOwnRisk,
+    ) -> Result<Ppm, Error<Tx::Error, Rx::Error>> {
+        let response = self
+            .transact_extended(command::READ_BACKGROUND_CO2, [0; 5], risk)
+            .await?;
+        let payload = response.payload();
+        Ok(Ppm(u16::from_be_bytes([payload[2], payload[3]])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_have_valid_checksum() {
+        assert_eq!(frame(0x86, [0; 5]), crate::Command::ReadCo2.to_frame());
+        let frame = frame(command::READ_BACKGROUND_CO2, [1, 2, 3, 4, 5]);
+        assert!(frame.checksum_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn background_co2() {
+        use crate::sim::FakeMhz;
+        use futures::executor::block_on;
+
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        let co2 = block_on(sensor.read_background_co2(AtOwnRisk::accept())).unwrap();
+        assert_eq!(co2, 400);
+    }
+}
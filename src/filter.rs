@@ -0,0 +1,291 @@
+//! Smoothing for consecutive CO2 readings. The sensor output is noisy
+//! (±50ppm), these filters work on the ppm values and need no allocation.
+
+/// Average of the last `N` readings.
+#[derive(Debug, Clone)]
+pub struct MovingAverage<const N: usize> {
+    samples: [u16; N],
+    len: usize,
+    next: usize,
+    /// Can not overflow, that would take more than 2^48 samples.
+    sum: u64,
+}
+
+impl<const N: usize> MovingAverage<N> {
+    pub const fn new() -> Self {
+        const { assert!(N > 0, "need room for at least one sample") };
+        MovingAverage {
+            samples: [0; N],
+            len: 0,
+            next: 0,
+            sum: 0,
+        }
+    }
+
+    /// Add a reading, returns the new average.
+    pub fn push(&mut self, co2: u16) -> u16 {
+        if self.len == N {
+            self.sum -= u64::from(self.samples[self.next]);
+        } else {
+            self.len += 1;
+        }
+        self.samples[self.next] = co2;
+        self.sum += u64::from(co2);
+        self.next = (self.next + 1) % N;
+        self.current()
+    }
+
+    /// The average of the readings so far, None if there are none.
+    pub fn average(&self) -> Option<u16> {
+        (self.len > 0).then(|| self.current())
+    }
+
+    /// True once `N` readings have been pushed.
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Forget all readings.
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+        self.sum = 0;
+    }
+
+    fn current(&self) -> u16 {
+        let len = self.len as u64;
+        ((self.sum + len / 2) / len) as u16
+    }
+}
+
+impl<const N: usize> Default for MovingAverage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Drops readings that deviate from the median of the last `N` accepted
+/// readings by more then `max_ppm_per_s` times the seconds since the last
+/// accepted reading. The MH-Z19 occasionally reports a single wild value
+/// after a brown-out, this keeps those out.
+///
+/// The allowed deviation grows the longer readings are rejected, a real step
+/// in concentration is therefore accepted after a while.
+#[derive(Debug, Clone)]
+pub struct SpikeReject<const N: usize> {
+    recent: [u16; N],
+    len: usize,
+    next: usize,
+    last_accepted_ms: u64,
+    max_ppm_per_s: u32,
+}
+
+impl<const N: usize> SpikeReject<N> {
+    pub const fn new(max_ppm_per_s: u32) -> Self {
+        const { assert!(N > 0, "need room for at least one sample") };
+        SpikeReject {
+            recent: [0; N],
+            len: 0,
+            next: 0,
+            last_accepted_ms: 0,
+            max_ppm_per_s,
+        }
+    }
+
+    /// Offer a reading taken at `now_ms`, a monotonic timestamp in
+    /// milliseconds. Returns the reading if it is accepted.
+    pub fn push(&mut self, co2: u16, now_ms: u64) -> Option<u16> {
+        if let Some(median) = self.median() {
+            let elapsed_ms = now_ms.saturating_sub(self.last_accepted_ms);
+            let allowed = u64::from(self.max_ppm_per_s) * elapsed_ms / 1000;
+            let deviation = u64::from(co2.abs_diff(median));
+            if deviation > allowed {
+                debug!("rejecting spike: {}ppm, median: {}ppm", co2, median);
+                return None;
+            }
+        }
+
+        self.recent[self.next] = co2;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+        self.last_accepted_ms = now_ms;
+        Some(co2)
+    }
+
+    /// Median of the recently accepted readings.
+    pub fn median(&self) -> Option<u16> {
+        if self.len == 0 {
+            return None;
+        }
+        let mut sorted = self.recent;
+        let sorted = &mut sorted[..self.len];
+        sorted.sort_unstable();
+        Some(sorted[self.len / 2])
+    }
+
+    /// Forget all readings.
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+}
+
+/// Measurement noise of the MH-Z19 in ppm², its datasheet specifies an
+/// accuracy of ±50ppm, a standard deviation of about 25ppm.
+pub const MHZ19_MEASUREMENT_NOISE: u32 = 625;
+
+/// How fast the real concentration is expected to change, in ppm² per
+/// second. Indoors it rarely changes more than a few ppm per second.
+pub const DEFAULT_PROCESS_NOISE: u32 = 4;
+
+/// One dimensional Kalman filter in fixed point. Smoother than a
+/// [`MovingAverage`] of similar lag: it weighs each reading by how much it
+/// trusts it compared to the current estimate.
+///
+/// Raise `process_noise` to follow changes faster, raise
+/// `measurement_noise` for smoother output.
+#[derive(Debug, Clone)]
+pub struct Kalman {
+    /// Estimate in 1/256 ppm.
+    estimate: i64,
+    /// Variance of the estimate in 1/256 ppm².
+    variance: u64,
+    last_ms: Option<u64>,
+    process_noise: u32,
+    measurement_noise: u32,
+}
+
+impl Kalman {
+    /// Both noises are variances, `process_noise` in ppm² per second and
+    /// `measurement_noise` in ppm².
+    pub const fn new(process_noise: u32, measurement_noise: u32) -> Self {
+        Kalman {
+            estimate: 0,
+            variance: 0,
+            last_ms: None,
+            process_noise,
+            measurement_noise,
+        }
+    }
+
+    /// Add a reading taken at `now_ms`, returns the new estimate.
+    pub fn push(&mut self, co2: u16, now_ms: u64) -> u16 {
+        let measurement = i64::from(co2) << 8;
+        let measurement_noise = u64::from(self.measurement_noise.max(1)) << 8;
+        let Some(last_ms) = self.last_ms else {
+            self.estimate = measurement;
+            self.variance = measurement_noise;
+            self.last_ms = Some(now_ms);
+            return co2;
+        };
+
+        let elapsed_ms = now_ms.saturating_sub(last_ms);
+        self.variance = self
+            .variance
+            .saturating_add((u64::from(self.process_noise) << 8).saturating_mul(elapsed_ms) / 1000);
+        // gain in 1/65536
+        let gain = ((u128::from(self.variance) << 16)
+            / u128::from(self.variance.saturating_add(measurement_noise)))
+            as i64;
+        self.estimate += (gain * (measurement - self.estimate)) >> 16;
+        self.variance -= ((u128::from(self.variance) * gain as u128) >> 16) as u64;
+        self.last_ms = Some(now_ms);
+        self.current()
+    }
+
+    /// The current estimate, None before the first reading.
+    pub fn estimate(&self) -> Option<u16> {
+        self.last_ms.map(|_| self.current())
+    }
+
+    /// Forget all readings.
+    pub fn reset(&mut self) {
+        self.last_ms = None;
+    }
+
+    fn current(&self) -> u16 {
+        ((self.estimate + 128) >> 8).clamp(0, i64::from(u16::MAX)) as u16
+    }
+}
+
+impl Default for Kalman {
+    fn default() -> Self {
+        Kalman::new(DEFAULT_PROCESS_NOISE, MHZ19_MEASUREMENT_NOISE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_average() {
+        let mut avg = MovingAverage::<3>::new();
+        assert_eq!(avg.average(), None);
+        assert_eq!(avg.push(400), 400);
+        assert_eq!(avg.push(500), 450);
+        assert_eq!(avg.push(600), 500);
+        assert!(avg.is_full());
+        // 400 drops out
+        assert_eq!(avg.push(700), 600);
+
+        avg.reset();
+        assert_eq!(avg.average(), None);
+        assert_eq!(avg.push(u16::MAX), u16::MAX);
+
+        // the sum of this many readings does not fit a u32
+        let mut avg = MovingAverage::<70_000>::new();
+        for _ in 0..70_000 {
+            avg.push(u16::MAX);
+        }
+        assert_eq!(avg.average(), Some(u16::MAX));
+    }
+
+    #[test]
+    fn spike_reject() {
+        let mut filter = SpikeReject::<5>::new(10);
+        assert_eq!(filter.push(600, 0), Some(600));
+        assert_eq!(filter.push(610, 1000), Some(610));
+        assert_eq!(filter.push(5000, 2000), None);
+        assert_eq!(filter.push(620, 3000), Some(620));
+        assert_eq!(filter.median(), Some(610));
+
+        // a real step is accepted once enough time has passed
+        assert_eq!(filter.push(1000, 4000), None);
+        assert_eq!(filter.push(1000, 60_000), Some(1000));
+    }
+
+    #[test]
+    fn kalman() {
+        let mut filter = Kalman::default();
+        assert_eq!(filter.estimate(), None);
+        assert_eq!(filter.push(600, 0), 600);
+
+        // noise of ±50ppm is mostly smoothed away once the filter settled
+        for i in 1..100u64 {
+            let co2 = if i % 2 == 0 { 650 } else { 550 };
+            let estimate = filter.push(co2, i * 2000);
+            if i > 10 {
+                assert!(estimate.abs_diff(600) < 10, "{estimate}");
+            }
+        }
+
+        // a real step is followed
+        for i in 100..150u64 {
+            filter.push(1000, i * 2000);
+        }
+        assert!(filter.estimate().unwrap() > 950);
+
+        filter.reset();
+        assert_eq!(filter.estimate(), None);
+    }
+
+    #[test]
+    fn kalman_long_gap() {
+        let mut filter = Kalman::new(u32::MAX, u32::MAX);
+        filter.push(600, 0);
+        // the variance saturates, the new reading is all that counts
+        assert_eq!(filter.push(900, u64::MAX), 900);
+        assert_eq!(filter.push(900, u64::MAX), 900);
+    }
+}
@@ -0,0 +1,49 @@
+//! Logging macros forwarding to `defmt` and/or `log` when the corresponding
+//! feature is enabled and compiling to nothing otherwise.
+
+macro_rules! trace {
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(feature = "defmt")]
+            ::defmt::trace!($s $(, $x)*);
+            #[cfg(feature = "log")]
+            ::log::trace!($s $(, $x)*);
+            #[cfg(not(any(feature = "defmt", feature = "log")))]
+            let _ = ($( & $x ),*);
+        }
+    };
+}
+
+macro_rules! debug {
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(feature = "defmt")]
+            ::defmt::debug!($s $(, $x)*);
+            #[cfg(feature = "log")]
+            ::log::debug!($s $(, $x)*);
+            #[cfg(not(any(feature = "defmt", feature = "log")))]
+            let _ = ($( & $x ),*);
+        }
+    };
+}
+
+/// Logs a whole frame as hex at trace level, only with the `trace-frames`
+/// feature. `Tx` marks frames send to the sensor, `Rx` those received.
+macro_rules! trace_frame {
+    (Tx, $frame:expr) => {
+        trace_frame!(@ "TX > {=[u8]:02x}", "TX > {:02x?}", $frame)
+    };
+    (Rx, $frame:expr) => {
+        trace_frame!(@ "RX < {=[u8]:02x}", "RX < {:02x?}", $frame)
+    };
+    (@ $defmt:literal, $log:literal, $frame:expr) => {
+        {
+            #[cfg(all(feature = "trace-frames", feature = "defmt"))]
+            ::defmt::trace!($defmt, &$frame[..]);
+            #[cfg(all(feature = "trace-frames", feature = "log"))]
+            ::log::trace!($log, $frame);
+            #[cfg(not(all(feature = "trace-frames", any(feature = "defmt", feature = "log"))))]
+            let _ = &$frame;
+        }
+    };
+}
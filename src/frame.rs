@@ -0,0 +1,224 @@
+use core::fmt;
+
+use crate::decode::{decode, Decoded};
+use crate::measurement::{checksum, checksum_valid};
+use crate::PAYLOAD_SIZE;
+
+/// Number the sensor answers to, every command carries it.
+const SENSOR_NUMBER: u8 = 0x01;
+
+/// The nine bytes of a single command or response.
+///
+/// Commands start with `0xFF 0x01 <command>`, responses with
+/// `0xFF <command>`. Both end in a checksum over the bytes in between.
+///
+/// ```
+/// use mhzx::Frame;
+///
+/// let response = Frame::from_bytes([0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97]);
+/// assert_eq!(response.command(), 0x86);
+/// assert_eq!(response.payload(), [0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00]);
+/// assert!(response.checksum_ok());
+/// ```
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Frame([u8; PAYLOAD_SIZE]);
+
+impl Frame {
+    /// Wrap received or captured bytes, the checksum is not checked.
+    pub const fn from_bytes(bytes: [u8; PAYLOAD_SIZE]) -> Self {
+        Frame(bytes)
+    }
+
+    /// For frames written out by hand. Panics if the checksum is wrong,
+    /// used in a constant that fails the build instead.
+    pub(crate) const fn checked(bytes: [u8; PAYLOAD_SIZE]) -> Self {
+        assert!(checksum_valid(&bytes), "frame checksum is wrong");
+        Frame(bytes)
+    }
+
+    /// Command frame for `command` with arguments `args` for bytes 3 to 7,
+    /// the checksum is filled in.
+    pub(crate) const fn request(command: u8, args: [u8; 5]) -> Self {
+        let [a, b, c, d, e] = args;
+        let mut bytes = [0xFF, SENSOR_NUMBER, command, a, b, c, d, e, 0x00];
+        bytes[8] = checksum(&bytes);
+        Frame(bytes)
+    }
+
+    /// True for frames sent to the sensor, false for its responses. No
+    /// command is numbered 0x01 so the two can not be confused.
+    pub const fn is_request(&self) -> bool {
+        self.0[1] == SENSOR_NUMBER
+    }
+
+    /// The command this frame sends or responds to.
+    pub const fn command(&self) -> u8 {
+        if self.is_request() {
+            self.0[2]
+        } else {
+            self.0[1]
+        }
+    }
+
+    /// The bytes between the command and the checksum, five for requests
+    /// and six for responses.
+    pub fn payload(&self) -> &[u8] {
+        if self.is_request() {
+            &self.0[3..8]
+        } else {
+            &self.0[2..8]
+        }
+    }
+
+    /// Whether the last byte matches the checksum over the others.
+    pub const fn checksum_ok(&self) -> bool {
+        checksum_valid(&self.0)
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; PAYLOAD_SIZE] {
+        &self.0
+    }
+
+    pub const fn into_bytes(self) -> [u8; PAYLOAD_SIZE] {
+        self.0
+    }
+
+    /// Display as hex followed by what the frame contains, for logs and
+    /// command line output. Also implements `defmt::Format` with the
+    /// `defmt` feature.
+    ///
+    /// ```
+    /// use mhzx::Frame;
+    ///
+    /// let frame = Frame::from_bytes([0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97]);
+    /// assert_eq!(
+    ///     frame.annotated().to_string(),
+    ///     "FF 86 01 A4 3E 00 00 00 97 [co2=420 t=22 ck OK]"
+    /// );
+    /// ```
+    pub fn annotated(self) -> AnnotatedFrame {
+        AnnotatedFrame(self)
+    }
+}
+
+/// See [`Frame::annotated`].
+#[derive(Debug, Clone, Copy)]
+pub struct AnnotatedFrame(Frame);
+
+impl fmt::Display for AnnotatedFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.as_bytes() {
+            write!(f, "{byte:02X} ")?;
+        }
+        f.write_str("[")?;
+        match decode(self.0) {
+            Decoded::Command(command) => write!(f, "cmd={command:?} ")?,
+            Decoded::Measurement(m) => write!(f, "co2={} t={} ", m.co2.get(), m.temp_celsius())?,
+            Decoded::RawMeasurement(m) => write!(f, "raw_co2={} adc_temp={} ", m.co2, m.adc_temp)?,
+            Decoded::FirmwareVersion(v) => write!(f, "fw={} ", v.as_str().unwrap_or("?"))?,
+            Decoded::Abc(enabled) => write!(f, "abc={} ", if enabled { "on" } else { "off" })?,
+            Decoded::Range(max_ppm) => write!(f, "range={max_ppm} ")?,
+            Decoded::Unknown(_) => write!(f, "unknown ")?,
+            Decoded::Corrupt(_) => (),
+        }
+        let checksum = if self.0.checksum_ok() { "OK" } else { "BAD" };
+        write!(f, "ck {checksum}]")
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for AnnotatedFrame {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=[u8]:02X} [", &self.0.as_bytes()[..]);
+        match decode(self.0) {
+            Decoded::Command(command) => defmt::write!(f, "cmd={} ", command),
+            Decoded::Measurement(m) => {
+                defmt::write!(f, "co2={} t={} ", m.co2.get(), m.temp_celsius())
+            }
+            Decoded::RawMeasurement(m) => {
+                defmt::write!(f, "raw_co2={} adc_temp={} ", m.co2, m.adc_temp)
+            }
+            Decoded::FirmwareVersion(v) => {
+                defmt::write!(f, "fw={} ", v.as_str().unwrap_or("?"))
+            }
+            Decoded::Abc(enabled) => defmt::write!(f, "abc={} ", enabled),
+            Decoded::Range(max_ppm) => defmt::write!(f, "range={} ", max_ppm),
+            Decoded::Unknown(_) => defmt::write!(f, "unknown "),
+            Decoded::Corrupt(_) => (),
+        }
+        let checksum = if self.0.checksum_ok() { "OK" } else { "BAD" };
+        defmt::write!(f, "ck {=str}]", checksum);
+    }
+}
+
+impl fmt::Debug for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Frame({:02x?})", self.0)
+    }
+}
+
+impl From<[u8; PAYLOAD_SIZE]> for Frame {
+    fn from(bytes: [u8; PAYLOAD_SIZE]) -> Self {
+        Frame(bytes)
+    }
+}
+
+impl From<Frame> for [u8; PAYLOAD_SIZE] {
+    fn from(frame: Frame) -> Self {
+        frame.0
+    }
+}
+
+impl AsRef<[u8]> for Frame {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq<[u8; PAYLOAD_SIZE]> for Frame {
+    fn eq(&self, other: &[u8; PAYLOAD_SIZE]) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Command;
+
+    #[test]
+    fn request_and_response() {
+        let request = Command::SetRange(crate::model::Range::Ppm2000).to_frame();
+        assert!(request.is_request());
+        assert_eq!(request.command(), 0x99);
+        assert_eq!(request.payload(), [0x00, 0x00, 0x00, 0x07, 0xD0]);
+        assert!(request.checksum_ok());
+
+        let mut response = [0xFF, 0x99, 0, 0, 0, 0, 0, 0, 0x00];
+        response[8] = checksum(&response);
+        let response = Frame::from_bytes(response);
+        assert!(!response.is_request());
+        assert_eq!(response.command(), 0x99);
+        assert_eq!(response.payload().len(), 6);
+
+        let corrupt = Frame::from_bytes([0xFF, 0x99, 0, 0, 0, 0, 0, 0, 0x00]);
+        assert!(!corrupt.checksum_ok());
+    }
+
+    #[test]
+    fn annotated() {
+        extern crate std;
+        use std::string::ToString;
+
+        assert_eq!(
+            Command::SetAbc(true).to_frame().annotated().to_string(),
+            "FF 01 79 A0 00 00 00 00 E6 [cmd=SetAbc(true) ck OK]"
+        );
+        let corrupt = Frame::from_bytes([0xFF, 0x9B, 0, 0, 0x13, 0x88, 0, 0, 0]);
+        assert_eq!(
+            corrupt.annotated().to_string(),
+            "FF 9B 00 00 13 88 00 00 00 [ck BAD]"
+        );
+    }
+}
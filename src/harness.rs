@@ -0,0 +1,321 @@
+//! Scripted UART for driving [`MHZ`](crate::MHZ) end-to-end in tests.
+//! Unlike [`FakeMhz`](crate::sim::FakeMhz) nothing is simulated, every
+//! write is checked against the script and every read hands out exactly
+//! the bytes scripted for it, errors included.
+
+extern crate std;
+
+use core::cell::RefCell;
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+use embedded_io_async::{ErrorKind, ErrorType, Read, ReadReady, Write};
+
+use crate::{Frame, PAYLOAD_SIZE};
+
+/// Error injected by the script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MockError;
+
+impl embedded_io_async::Error for MockError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+#[derive(Debug)]
+enum Step {
+    /// The next frame written must be this one.
+    Write(Frame),
+    WriteError,
+    FlushError,
+    /// Bytes waiting before the command is written, `drain_rx` discards them.
+    Stale(Vec<u8>),
+    /// Bytes handed out by one read, split over more reads if they do not
+    /// fit the buffer.
+    Read(Vec<u8>),
+    ReadError,
+}
+
+/// Steps are taken in order. Reading when the next step is not a read
+/// returns EOF.
+pub(crate) struct ScriptedUart {
+    steps: RefCell<VecDeque<Step>>,
+    written: RefCell<Vec<u8>>,
+}
+
+impl ScriptedUart {
+    pub(crate) fn new() -> Self {
+        ScriptedUart {
+            steps: RefCell::new(VecDeque::new()),
+            written: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn push(&self, step: Step) -> &Self {
+        self.steps.borrow_mut().push_back(step);
+        self
+    }
+
+    pub(crate) fn expect_write(&self, frame: impl Into<Frame>) -> &Self {
+        self.push(Step::Write(frame.into()))
+    }
+
+    pub(crate) fn fail_write(&self) -> &Self {
+        self.push(Step::WriteError)
+    }
+
+    pub(crate) fn fail_flush(&self) -> &Self {
+        self.push(Step::FlushError)
+    }
+
+    pub(crate) fn stale(&self, bytes: &[u8]) -> &Self {
+        self.push(Step::Stale(bytes.to_vec()))
+    }
+
+    pub(crate) fn read(&self, bytes: &[u8]) -> &Self {
+        self.push(Step::Read(bytes.to_vec()))
+    }
+
+    pub(crate) fn fail_read(&self) -> &Self {
+        self.push(Step::ReadError)
+    }
+
+    pub(crate) fn split(&self) -> (ScriptedTx<'_>, ScriptedRx<'_>) {
+        (ScriptedTx { uart: self }, ScriptedRx { uart: self })
+    }
+
+    /// Panics if part of the script was not used.
+    pub(crate) fn assert_done(&self) {
+        let steps = self.steps.borrow();
+        assert!(steps.is_empty(), "steps left in script: {steps:?}");
+    }
+}
+
+pub(crate) struct ScriptedTx<'a> {
+    uart: &'a ScriptedUart,
+}
+
+pub(crate) struct ScriptedRx<'a> {
+    uart: &'a ScriptedUart,
+}
+
+impl ErrorType for ScriptedTx<'_> {
+    type Error = MockError;
+}
+
+impl ErrorType for ScriptedRx<'_> {
+    type Error = MockError;
+}
+
+impl Write for ScriptedTx<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let mut steps = self.uart.steps.borrow_mut();
+        let expected = match steps.front() {
+            Some(Step::Write(expected)) => *expected,
+            Some(Step::WriteError) => {
+                steps.pop_front();
+                return Err(MockError);
+            }
+            other => panic!("unexpected write of {buf:02x?}, next step: {other:?}"),
+        };
+
+        let mut written = self.uart.written.borrow_mut();
+        let n = buf.len().min(PAYLOAD_SIZE - written.len());
+        written.extend_from_slice(&buf[..n]);
+        if written.len() == PAYLOAD_SIZE {
+            assert_eq!(expected.as_bytes()[..], written[..], "wrong frame written");
+            written.clear();
+            steps.pop_front();
+        }
+        Ok(n)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        let mut steps = self.uart.steps.borrow_mut();
+        if let Some(Step::FlushError) = steps.front() {
+            steps.pop_front();
+            return Err(MockError);
+        }
+        Ok(())
+    }
+}
+
+impl Read for ScriptedRx<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut steps = self.uart.steps.borrow_mut();
+        let bytes = match steps.front_mut() {
+            Some(Step::Read(bytes) | Step::Stale(bytes)) => bytes,
+            Some(Step::ReadError) => {
+                steps.pop_front();
+                return Err(MockError);
+            }
+            _ => return Ok(0),
+        };
+
+        let n = buf.len().min(bytes.len());
+        buf[..n].copy_from_slice(&bytes[..n]);
+        bytes.drain(..n);
+        if bytes.is_empty() {
+            steps.pop_front();
+        }
+        Ok(n)
+    }
+}
+
+impl ReadReady for ScriptedRx<'_> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(matches!(
+            self.uart.steps.borrow().front(),
+            Some(Step::Stale(_))
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measurement::checksum;
+    use crate::{Command, Error, MHZ};
+    use futures::executor::block_on;
+
+    fn response(payload: [u8; 7]) -> [u8; PAYLOAD_SIZE] {
+        let mut frame = [0xFF, 0, 0, 0, 0, 0, 0, 0, 0];
+        frame[1..8].copy_from_slice(&payload);
+        frame[8] = checksum(&frame);
+        frame
+    }
+
+    const CO2_420: [u8; PAYLOAD_SIZE] = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97];
+
+    #[test]
+    fn read_co2() {
+        let uart = ScriptedUart::new();
+        uart.stale(&[0x00, 0x12])
+            .expect_write(Command::ReadCo2)
+            .read(&CO2_420[..3])
+            .read(&CO2_420[3..]);
+        let (tx, rx) = uart.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        block_on(sensor.drain_rx()).unwrap();
+        let m = block_on(sensor.read_co2()).unwrap();
+        assert_eq!((m.co2.get(), m.temp_celsius()), (420, 22));
+        uart.assert_done();
+    }
+
+    #[test]
+    fn rx_drain() {
+        let uart = ScriptedUart::new();
+        uart.stale(&CO2_420)
+            .expect_write(Command::SetAbc(false))
+            .stale(&[0x12])
+            .expect_write(Command::ReadCo2)
+            .read(&CO2_420);
+        let (tx, rx) = uart.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx).with_rx_drain();
+
+        block_on(sensor.set_abc(false)).unwrap();
+        assert_eq!(block_on(sensor.read_co2()).unwrap().co2, 420);
+        uart.assert_done();
+    }
+
+    #[test]
+    fn read_co2_raw() {
+        let uart = ScriptedUart::new();
+        uart.expect_write(Command::ReadRawCo2)
+            .read(&response([0x85, 0x01, 0x02, 0x05, 0x14, 0x00, 0x07]));
+        let (tx, rx) = uart.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        let m = block_on(sensor.read_co2_raw()).unwrap();
+        assert_eq!((m.adc_temp, m.co2, m.adc_min_light), (0x0102, 1300, 7));
+        uart.assert_done();
+    }
+
+    #[test]
+    fn wrong_command() {
+        let other = response([0x85, 0, 0, 0, 0, 0, 0]);
+
+        // the response to another command is skipped
+        let uart = ScriptedUart::new();
+        uart.expect_write(Command::ReadCo2)
+            .read(&other)
+            .read(&CO2_420);
+        let (tx, rx) = uart.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        assert_eq!(block_on(sensor.read_co2()).unwrap().co2, 420);
+        assert_eq!(sensor.stats().stale_frames, 1);
+        uart.assert_done();
+
+        // only the response to another command arrives
+        uart.expect_write(Command::ReadRawCo2).read(&CO2_420);
+        assert_eq!(
+            block_on(sensor.read_co2_raw()).unwrap_err(),
+            Error::ReadingEOF
+        );
+        uart.assert_done();
+    }
+
+    #[test]
+    fn desynchronized() {
+        let uart = ScriptedUart::new();
+        uart.expect_write(Command::ReadCo2)
+            .read(&[0x00; 20])
+            .read(&[0x00; 20]);
+        let (tx, rx) = uart.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        sensor.set_resync_limit(16);
+
+        assert_eq!(
+            block_on(sensor.read_co2()).unwrap_err(),
+            Error::Desynchronized { discarded: 31 }
+        );
+        uart.assert_done();
+    }
+
+    #[test]
+    fn eof() {
+        let uart = ScriptedUart::new();
+        uart.expect_write(Command::ReadCo2).read(&CO2_420[..5]);
+        let (tx, rx) = uart.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        assert_eq!(block_on(sensor.read_co2()).unwrap_err(), Error::ReadingEOF);
+
+        uart.expect_write(Command::ReadRawCo2);
+        assert_eq!(
+            block_on(sensor.read_co2_raw()).unwrap_err(),
+            Error::ReadingEOF
+        );
+        uart.assert_done();
+    }
+
+    #[test]
+    fn uart_errors() {
+        let uart = ScriptedUart::new();
+        let (tx, rx) = uart.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        uart.fail_write();
+        assert_eq!(
+            block_on(sensor.read_co2()).unwrap_err(),
+            Error::WritingToUart(MockError)
+        );
+        uart.expect_write(Command::ReadCo2).fail_flush();
+        assert_eq!(
+            block_on(sensor.read_co2()).unwrap_err(),
+            Error::FlushingUart(MockError)
+        );
+        uart.expect_write(Command::ReadRawCo2).fail_read();
+        assert_eq!(
+            block_on(sensor.read_co2_raw()).unwrap_err(),
+            Error::Reading(MockError)
+        );
+        uart.assert_done();
+
+        // the driver recovers once the UART works again
+        uart.expect_write(Command::ReadCo2).read(&CO2_420);
+        assert_eq!(block_on(sensor.read_co2()).unwrap().co2, 420);
+        uart.assert_done();
+    }
+}
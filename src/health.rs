@@ -0,0 +1,147 @@
+use embedded_io_async::{Read, Write};
+
+use crate::model::{reports_temperature, DetectionRange, Model, SupportsFirmwareVersion};
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Error, FirmwareVersion, Measurement, MHZ};
+
+/// Lowest and highest temperature in degrees Celsius considered plausible,
+/// the range the sensor is specified for.
+pub const PLAUSIBLE_TEMPERATURE: (i16, i16) = (-10, 50);
+
+/// Outcome of [`MHZ::health_check`].
+#[derive(Debug, Clone)]
+pub struct HealthReport<TxError, RxError> {
+    /// The first successful measurement, None if every attempt failed.
+    pub measurement: Option<Measurement>,
+    /// Number of reads performed.
+    pub attempts: u8,
+    /// How many reads returned a corrupt packet.
+    pub checksum_failures: u8,
+    /// The error of the last failed read if any.
+    pub last_error: Option<Error<TxError, RxError>>,
+    /// Whether the CO2 concentration lies within the detection range.
+    pub co2_plausible: bool,
+    /// Whether the temperature lies within [`PLAUSIBLE_TEMPERATURE`]. Always
+    /// true for models that do not report temperature.
+    pub temperature_plausible: bool,
+    /// Only queried by [`MHZ::health_check_with_firmware`].
+    pub firmware: Option<FirmwareVersion>,
+    /// Why querying the firmware version failed, kept apart from
+    /// [`last_error`](HealthReport::last_error).
+    pub firmware_error: Option<Error<TxError, RxError>>,
+}
+
+impl<TxError, RxError> HealthReport<TxError, RxError> {
+    /// A measurement succeeded and it was plausible.
+    pub fn is_healthy(&self) -> bool {
+        self.measurement.is_some() && self.co2_plausible && self.temperature_plausible
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Read the sensor, retrying up to `max_attempts` times, and check the
+    /// measurement is plausible for the configured detection `range`.
+    pub async fn health_check(
+        &mut self,
+        max_attempts: u8,
+        range: M::Range,
+    ) -> HealthReport<Tx::Error, Rx::Error> {
+        let mut report = HealthReport {
+            measurement: None,
+            attempts: 0,
+            checksum_failures: 0,
+            last_error: None,
+            co2_plausible: false,
+            temperature_plausible: false,
+            firmware: None,
+            firmware_error: None,
+        };
+
+        while report.attempts < max_attempts {
+            if report.attempts > 0 {
+                self.observe().retry();
+            }
+            report.attempts += 1;
+            match self.read_co2().await {
+                Ok(measurement) => {
+                    report.measurement = Some(measurement);
+                    break;
+                }
+                Err(e) => {
+                    if matches!(e, Error::InvalidChecksum { .. }) {
+                        report.checksum_failures += 1;
+                    }
+                    report.last_error = Some(e);
+                }
+            }
+        }
+
+        if let Some(measurement) = report.measurement {
+            report.co2_plausible = measurement.co2 > 0 && measurement.co2 <= range.max_ppm();
+            let (min, max) = PLAUSIBLE_TEMPERATURE;
+            report.temperature_plausible =
+                !reports_temperature::<M>() || (min..=max).contains(&measurement.temp_celsius());
+        }
+        report
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: SupportsFirmwareVersion,
+    P: Pacing,
+    O: Observer,
+{
+    /// Like [`health_check`](MHZ::health_check) but also queries the
+    /// firmware version.
+    pub async fn health_check_with_firmware(
+        &mut self,
+        max_attempts: u8,
+        range: M::Range,
+    ) -> HealthReport<Tx::Error, Rx::Error> {
+        let mut report = self.health_check(max_attempts, range).await;
+        match self.read_firmware_version().await {
+            Ok(version) => report.firmware = Some(version),
+            Err(e) => report.firmware_error = Some(e),
+        }
+        report
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::model::Range;
+    use crate::sim::FakeMhz;
+    use futures::executor::block_on;
+
+    #[test]
+    fn keeps_read_and_firmware_errors() {
+        let sim = FakeMhz::new();
+        sim.with(|s| s.corrupt_responses = 2);
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        let report = block_on(sensor.health_check_with_firmware(1, Range::Ppm5000));
+        assert!(!report.is_healthy());
+        assert!(matches!(
+            report.last_error,
+            Some(Error::InvalidChecksum { .. })
+        ));
+        assert!(matches!(
+            report.firmware_error,
+            Some(Error::InvalidChecksum { .. })
+        ));
+        assert!(report.firmware.is_none());
+    }
+}
@@ -0,0 +1,97 @@
+//! Hardware in the loop tests against a real sensor. They are ignored by
+//! default, run them using:
+//!
+//! `MHZ_SERIAL_PORT=/dev/ttyUSB0 cargo test --features hil-tests -- --ignored hil`
+//!
+//! The tests change the range and ABC setting, they restore the previous
+//! state if they succeed.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::linux::LinuxSensor;
+use crate::model::{DetectionRange, Range};
+use crate::{MHZ, MIN_COMMAND_INTERVAL_MS};
+
+/// Environment variable holding the path of the serial port.
+const PORT_VAR: &str = "MHZ_SERIAL_PORT";
+
+/// There is only one sensor, tests take turns.
+static SENSOR: Mutex<()> = Mutex::new(());
+
+fn with_sensor(test: impl AsyncFnOnce(&mut LinuxSensor)) {
+    let _guard = SENSOR
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let path = std::env::var(PORT_VAR)
+        .unwrap_or_else(|_| panic!("set {PORT_VAR} to the serial port the sensor is on"));
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(async {
+            let mut sensor = MHZ::open(&path).expect("could not open serial port");
+            test(&mut sensor).await;
+            pause().await;
+        });
+}
+
+async fn pause() {
+    tokio::time::sleep(Duration::from_millis(MIN_COMMAND_INTERVAL_MS.into())).await;
+}
+
+#[test]
+#[ignore = "needs a sensor, see the module docs"]
+fn read() {
+    with_sensor(async |sensor| {
+        let measurement = sensor.read_co2().await.unwrap();
+        assert!(measurement.co2 > 0);
+        assert!(
+            (-10..=60).contains(&measurement.temp_celsius()),
+            "{measurement:?}"
+        );
+    });
+}
+
+#[test]
+#[ignore = "needs a sensor, see the module docs"]
+fn raw_read() {
+    with_sensor(async |sensor| {
+        let raw = sensor.read_co2_raw().await.unwrap();
+        assert!(raw.co2 > 0, "{raw:?}");
+    });
+}
+
+#[test]
+#[ignore = "needs a sensor, see the module docs"]
+fn set_and_get_range() {
+    with_sensor(async |sensor| {
+        let original = sensor.read_range_ppm().await.unwrap();
+        let original = Range::from_max_ppm(original)
+            .unwrap_or_else(|| panic!("sensor has an unknown range: {original}ppm"));
+        let other = match original {
+            Range::Ppm2000 => Range::Ppm5000,
+            _ => Range::Ppm2000,
+        };
+        pause().await;
+        sensor.set_range(other).await.unwrap();
+        pause().await;
+        assert_eq!(sensor.read_range_ppm().await.unwrap(), other.max_ppm());
+        pause().await;
+        sensor.set_range(original).await.unwrap();
+    });
+}
+
+#[test]
+#[ignore = "needs a sensor, see the module docs"]
+fn toggle_abc() {
+    with_sensor(async |sensor| {
+        let original = sensor.read_abc().await.unwrap();
+        pause().await;
+        sensor.set_abc(!original).await.unwrap();
+        pause().await;
+        assert_eq!(sensor.read_abc().await.unwrap(), !original);
+        pause().await;
+        sensor.set_abc(original).await.unwrap();
+    });
+}
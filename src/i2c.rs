@@ -0,0 +1,300 @@
+//! The MH-Z16 is often sold on a board with an SC16IS750 I2C to UART
+//! bridge. [`BridgeTx`] and [`BridgeRx`] drive that bridge so the sensor can
+//! be used with the regular [`MHZ`] API.
+//!
+//! Both halves need their own handle to the bus, use for example
+//! `embedded-hal-bus` or `embassy-embedded-hal` to share it.
+//!
+//! ```no_run
+//! # async fn run<I2C, D>(tx_i2c: I2C, rx_i2c: I2C, delay: D)
+//! # where
+//! #     I2C: embedded_hal_async::i2c::I2c,
+//! #     D: embedded_hal_async::delay::DelayNs + Clone,
+//! # {
+//! use mhzx::i2c::DEFAULT_ADDRESS;
+//! use mhzx::MHZ;
+//!
+//! let mut sensor = MHZ::from_i2c(tx_i2c, rx_i2c, delay, DEFAULT_ADDRESS)
+//!     .await
+//!     .unwrap();
+//! let measurement = sensor.read_co2().await.unwrap();
+//! # }
+//! ```
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::I2c;
+use embedded_io_async::{ErrorKind, ErrorType, Read, ReadReady, Write};
+
+use crate::model::Mhz16;
+use crate::MHZ;
+
+/// Address of the bridge with both address pins pulled high, as on the
+/// common MH-Z16 breakout boards.
+pub const DEFAULT_ADDRESS: u8 = 0x4D;
+
+/// Crystal frequency of the bridge on the common boards.
+const CRYSTAL_HZ: u32 = 14_745_600;
+const BAUDRATE: u32 = 9600;
+const FIFO_SIZE: usize = 64;
+/// How long to wait before asking the bridge for new data again.
+const POLL_INTERVAL_US: u32 = 1000;
+
+mod reg {
+    pub const RHR_THR: u8 = 0x00;
+    pub const DLL: u8 = 0x00;
+    pub const DLH: u8 = 0x01;
+    pub const FCR: u8 = 0x02;
+    pub const LCR: u8 = 0x03;
+    pub const TXLVL: u8 = 0x08;
+    pub const RXLVL: u8 = 0x09;
+    pub const IO_CONTROL: u8 = 0x0E;
+}
+
+/// Error talking to the bridge.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I2cError<E>(pub E);
+
+impl<E: core::fmt::Debug> embedded_io_async::Error for I2cError<E> {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl<E: core::fmt::Debug> core::fmt::Display for I2cError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "I2C bridge error: {:?}", self.0)
+    }
+}
+
+/// The MH-Z16 on an I2C bridge.
+pub type I2cSensor<I2C, D> = MHZ<BridgeTx<I2C, D>, BridgeRx<I2C, D>, Mhz16>;
+
+impl<I2C: I2c, D: DelayNs + Clone> MHZ<BridgeTx<I2C, D>, BridgeRx<I2C, D>, Mhz16> {
+    /// Reset the bridge at `address`, configure its UART as 9600 baud 8N1
+    /// and construct the sensor. `tx_i2c` and `rx_i2c` are two handles to
+    /// the same bus.
+    pub async fn from_i2c(
+        mut tx_i2c: I2C,
+        rx_i2c: I2C,
+        delay: D,
+        address: u8,
+    ) -> Result<Self, I2cError<I2C::Error>> {
+        // software reset, the bridge does not acknowledge it
+        let _ = write_register(&mut tx_i2c, address, reg::IO_CONTROL, 0x08).await;
+
+        let divisor = CRYSTAL_HZ / (BAUDRATE * 16);
+        let [_, _, high, low] = divisor.to_be_bytes();
+        for (register, value) in [
+            (reg::LCR, 0x80), // divisor latch
+            (reg::DLL, low),
+            (reg::DLH, high),
+            (reg::LCR, 0x03), // 8N1
+            (reg::FCR, 0x07), // enable and reset FIFOs
+        ] {
+            write_register(&mut tx_i2c, address, register, value).await?;
+        }
+
+        let tx = BridgeTx {
+            i2c: tx_i2c,
+            delay: delay.clone(),
+            address,
+        };
+        let rx = BridgeRx {
+            i2c: rx_i2c,
+            delay,
+            address,
+        };
+        Ok(MHZ::from_tx_rx_with_model(tx, rx, Mhz16))
+    }
+}
+
+async fn write_register<I2C: I2c>(
+    i2c: &mut I2C,
+    address: u8,
+    register: u8,
+    value: u8,
+) -> Result<(), I2cError<I2C::Error>> {
+    i2c.write(address, &[register << 3, value])
+        .await
+        .map_err(I2cError)
+}
+
+/// Wait until `register` is non zero, then return its value.
+async fn wait_for_level<I2C: I2c>(
+    i2c: &mut I2C,
+    delay: &mut impl DelayNs,
+    address: u8,
+    register: u8,
+) -> Result<usize, I2cError<I2C::Error>> {
+    let mut level = [0u8];
+    loop {
+        i2c.write_read(address, &[register << 3], &mut level)
+            .await
+            .map_err(I2cError)?;
+        match level[0] {
+            0 => delay.delay_us(POLL_INTERVAL_US).await,
+            level => return Ok(level as usize),
+        }
+    }
+}
+
+/// Transmit half of the bridged UART.
+pub struct BridgeTx<I2C, D> {
+    i2c: I2C,
+    delay: D,
+    address: u8,
+}
+
+/// Receive half of the bridged UART.
+pub struct BridgeRx<I2C, D> {
+    i2c: I2C,
+    delay: D,
+    address: u8,
+}
+
+impl<I2C: I2c, D> ErrorType for BridgeTx<I2C, D> {
+    type Error = I2cError<I2C::Error>;
+}
+
+impl<I2C: I2c, D> ErrorType for BridgeRx<I2C, D> {
+    type Error = I2cError<I2C::Error>;
+}
+
+impl<I2C: I2c, D: DelayNs> Write for BridgeTx<I2C, D> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let space =
+            wait_for_level(&mut self.i2c, &mut self.delay, self.address, reg::TXLVL).await?;
+
+        let n = buf.len().min(space).min(FIFO_SIZE);
+        let mut frame = [0u8; FIFO_SIZE + 1];
+        frame[0] = reg::RHR_THR << 3;
+        frame[1..=n].copy_from_slice(&buf[..n]);
+        self.i2c
+            .write(self.address, &frame[..=n])
+            .await
+            .map_err(I2cError)?;
+        Ok(n)
+    }
+}
+
+impl<I2C: I2c, D: DelayNs> Read for BridgeRx<I2C, D> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let available =
+            wait_for_level(&mut self.i2c, &mut self.delay, self.address, reg::RXLVL).await?;
+
+        let n = buf.len().min(available);
+        self.i2c
+            .write_read(self.address, &[reg::RHR_THR << 3], &mut buf[..n])
+            .await
+            .map_err(I2cError)?;
+        Ok(n)
+    }
+}
+
+impl<I2C: I2c, D: DelayNs> ReadReady for BridgeRx<I2C, D> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        // asking the bridge needs async IO, stale bytes are skipped while
+        // reading the response instead
+        Ok(false)
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::sim::NoDelay;
+    use crate::Command;
+    use core::cell::RefCell;
+    use embedded_hal_async::i2c::{ErrorType, Operation};
+    use futures::executor::block_on;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    const RESPONSE: [u8; 9] = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0, 0, 0, 0x97];
+
+    /// Just enough of the SC16IS750 and a sensor answering [`Command::ReadCo2`].
+    #[derive(Default)]
+    struct Chip {
+        registers: [u8; 16],
+        divisor_latch: [u8; 2],
+        transmitted: Vec<u8>,
+        rx_fifo: VecDeque<u8>,
+    }
+
+    #[derive(Clone, Default)]
+    struct FakeBus {
+        chip: Rc<RefCell<Chip>>,
+        register: u8,
+    }
+
+    impl ErrorType for FakeBus {
+        type Error = core::convert::Infallible;
+    }
+
+    impl I2c for FakeBus {
+        async fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            assert_eq!(address, DEFAULT_ADDRESS);
+            let mut chip = self.chip.borrow_mut();
+            for op in operations {
+                match op {
+                    Operation::Write([register, data @ ..]) => {
+                        self.register = *register >> 3;
+                        match (self.register, data) {
+                            (_, []) => (),
+                            (register @ (reg::DLL | reg::DLH), [value])
+                                if chip.registers[reg::LCR as usize] & 0x80 != 0 =>
+                            {
+                                chip.divisor_latch[register as usize] = *value
+                            }
+                            (reg::RHR_THR, data) => chip.transmitted.extend_from_slice(data),
+                            (register, [value]) => chip.registers[register as usize] = *value,
+                            _ => unreachable!("only THR supports burst writes"),
+                        }
+                    }
+                    Operation::Write([]) => (),
+                    Operation::Read(buf) => match self.register {
+                        reg::TXLVL => buf[0] = FIFO_SIZE as u8,
+                        reg::RXLVL => buf[0] = chip.rx_fifo.len() as u8,
+                        reg::RHR_THR => {
+                            for byte in buf.iter_mut() {
+                                *byte = chip.rx_fifo.pop_front().unwrap();
+                            }
+                        }
+                        _ => unreachable!(),
+                    },
+                }
+            }
+
+            if chip.transmitted == Command::ReadCo2.to_frame().as_bytes() {
+                chip.transmitted.clear();
+                chip.rx_fifo.extend(RESPONSE);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn read_co2_through_bridge() {
+        let bus = FakeBus::default();
+        block_on(async {
+            let mut sensor = MHZ::from_i2c(bus.clone(), bus.clone(), NoDelay, DEFAULT_ADDRESS)
+                .await
+                .unwrap();
+            assert_eq!(bus.chip.borrow().registers[reg::LCR as usize], 0x03);
+            assert_eq!(bus.chip.borrow().divisor_latch, [96, 0]);
+            assert_eq!(sensor.read_co2().await.unwrap().co2, 420);
+        });
+    }
+}
@@ -1,36 +1,159 @@
-#![cfg_attr(not(any(target_os = "linux", feature = "thiserror")), no_std)]
+#![cfg_attr(not(target_os = "linux"), no_std)]
 #![doc = include_str!("../README.md")]
 
-use embedded_io_async::{Read, ReadExactError, Write};
+use core::marker::PhantomData;
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, ReadReady, Write};
 
+#[macro_use]
+mod fmt;
+
+mod air_quality;
+mod any;
+pub use air_quality::{AirQuality, AirQualityThresholds};
+pub use any::AnyMhz;
+mod averaging;
+#[cfg(all(feature = "embassy", feature = "embassy-sync"))]
+pub mod background;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub use averaging::{Averaged, AVERAGING_INTERVAL_MS, OUTLIER_MIN_DEVIATION_PPM};
+mod buffered;
+mod builder;
+pub use builder::{MhzBuilder, DEFAULT_CONFIG_RETRIES};
+pub mod cache;
+mod calibration;
+pub use calibration::{
+    GuidedCalibrationError, HdCalibrationError, ZeroCalibration, GUIDED_MAX_SPREAD_PPM,
+    GUIDED_READ_INTERVAL_MS, GUIDED_TOLERANCE_PPM, HD_HOLD_MS, HD_SETTLE_MS, HD_TOLERANCE_PPM,
+    MIN_FRESH_AIR_EXPOSURE_MS,
+};
+pub mod capture;
+mod command;
+pub use command::Command;
+pub mod compensation;
+pub mod config;
+pub mod correction;
+use correction::Correction;
+pub mod decode;
+mod deframer;
+mod diagnose;
+pub use diagnose::Diagnosis;
+#[cfg(feature = "embassy")]
+mod embassy;
+pub use deframer::Deframer;
 mod error;
+pub mod export;
+#[cfg(feature = "extended-commands")]
+pub mod extended;
 pub use error::Error;
+pub mod filter;
+mod frame;
+pub use frame::{AnnotatedFrame, Frame};
+pub mod model;
+use model::{
+    Configurable, DetectionRange, Mhz19B, Model, ReadOnly, SupportsAbc, SupportsFirmwareVersion,
+    SupportsRange, SupportsTemperature,
+};
+#[cfg(all(target_os = "linux", test))]
+mod harness;
+mod health;
+#[cfg(all(test, feature = "hil-tests"))]
+mod hil;
+pub mod i2c;
+#[cfg(feature = "linux")]
+pub mod linux;
+pub use health::{HealthReport, PLAUSIBLE_TEMPERATURE};
 mod measurement;
-pub use measurement::{Measurement, RawMeasurement};
+pub mod mock;
+pub mod mux;
+pub use measurement::{FirmwareVersion, Measurement, RawMeasurement};
+pub mod observer;
+use observer::{EventCounts, NoObserver, Observer};
+pub mod pacing;
+#[cfg(feature = "nb")]
+pub mod polling;
+pub mod power;
+use pacing::{NoPacing, Pacing};
+mod probe;
+pub use probe::{ProbeError, SensorInfo};
+pub mod protocol;
 mod read_package;
-use read_package::read_package;
+mod ring;
+pub use read_package::decode_chunks;
+use read_package::read_response;
+mod sensor;
+#[cfg(feature = "embassy-sync")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod sigrok;
+pub use sensor::{Co2Reading, Co2Sensor};
+pub mod sim;
+pub mod stats;
+#[cfg(feature = "testutil")]
+pub mod testutil;
+mod time;
+pub mod timing;
+pub use time::{Clock, IntoMillis};
+#[cfg(feature = "tokio")]
+pub mod tokio_io;
+pub mod trend;
+mod try_read;
+pub use try_read::PendingCo2;
+pub mod twa;
+pub mod units;
+pub mod validate;
+pub use units::Ppm;
+mod verify;
+pub mod warmup;
+pub mod watchdog;
+pub mod wire;
 
 const PAYLOAD_SIZE: usize = 9;
 
-pub mod commands {
-    /// Read "final" CO2 concentration.
-    pub const READ_CO2: [u8; 9] = [0xFF, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
-    /// Read raw CO2 concentration.
-    pub const READ_RAW_CO2: [u8; 9] = [0xFF, 0x01, 0x85, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7a];
-}
+/// Number of bytes that may be discarded while searching for a response
+/// before giving up with [`Error::Desynchronized`].
+pub const DEFAULT_RESYNC_LIMIT: usize = 128;
+
+/// The sensor misbehaves when send commands more often then this.
+pub const MIN_COMMAND_INTERVAL_MS: u32 = 100;
+
+/// Default size of the buffer responses are read into, fits 5 packages.
+pub const DEFAULT_RX_BUFFER: usize = 5 * PAYLOAD_SIZE;
 
 /// A struct representing sensor interface.
-pub struct MHZ<Tx, Rx> {
+///
+/// The `M` parameter selects the [`Model`] of the sensor, it determines which
+/// commands are available. `RX_BUF` is the size of the buffer responses are
+/// read into. A bigger buffer absorbs more stale packages per read, a smaller
+/// one saves stack space. See [`with_rx_buffer`](MHZ::with_rx_buffer).
+/// `P` spaces commands apart, see [`with_pacing`](MHZ::with_pacing). `O`
+/// is told about protocol events, see [`with_observer`](MHZ::with_observer).
+pub struct MHZ<
+    Tx,
+    Rx,
+    M = Mhz19B,
+    const RX_BUF: usize = DEFAULT_RX_BUFFER,
+    P = NoPacing,
+    O = NoObserver,
+> {
     uart_tx: Tx,
     uart_rx: Rx,
+    resync_limit: usize,
+    echo_cancel: bool,
+    pacing: P,
+    observer: O,
+    stats: EventCounts,
+    correction: Correction,
+    /// Set by [`with_rx_drain`](MHZ::with_rx_drain).
+    rx_ready: Option<fn(&mut Rx) -> bool>,
+    model: PhantomData<M>,
 }
 
 impl<Tx, Rx> MHZ<Tx, Rx>
 where
     Tx: Write,
-    Tx::Error: defmt::Format,
     Rx: Read,
-    Rx::Error: defmt::Format,
 {
     /// Constructs the [`Sensor`](struct.Sensor.html) interface from 2 'halves' of UART.
     /// # Warning, take care to setup the UART with the correct settings:
@@ -38,51 +161,438 @@ where
     /// - Date bits: 8 bits
     /// - Stop bits: 1 bit
     /// - Calibrate byte: no
+    ///
+    /// If the receiving half implements [`ReadReady`] stale bytes can be
+    /// discarded before every command using
+    /// [`with_rx_drain`](MHZ::with_rx_drain).
     pub fn from_tx_rx(uart_tx: Tx, uart_rx: Rx) -> MHZ<Tx, Rx> {
-        MHZ { uart_tx, uart_rx }
+        MHZ::from_tx_rx_with_model(uart_tx, uart_rx, Mhz19B)
     }
+}
 
-    async fn read_into(&mut self, buf: &mut [u8]) -> Result<(), Error<Tx::Error, Rx::Error>> {
-        self.uart_rx.read_exact(buf).await.map_err(|e| match e {
-            ReadExactError::UnexpectedEof => Error::ReadingEOF,
-            ReadExactError::Other(e) => Error::Reading(e),
-        })
+impl<Tx, Rx, M> MHZ<Tx, Rx, M>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+{
+    /// Like [`from_tx_rx`](MHZ::from_tx_rx) but for a specific [`Model`].
+    pub fn from_tx_rx_with_model(uart_tx: Tx, uart_rx: Rx, _model: M) -> MHZ<Tx, Rx, M> {
+        MHZ {
+            uart_tx,
+            uart_rx,
+            resync_limit: DEFAULT_RESYNC_LIMIT,
+            echo_cancel: false,
+            pacing: NoPacing,
+            observer: NoObserver,
+            stats: EventCounts::default(),
+            correction: Correction::NONE,
+            rx_ready: None,
+            model: PhantomData,
+        }
     }
+}
 
-    pub async fn read_co2(
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Change the size of the buffer responses are read into.
+    /// Defaults to [`DEFAULT_RX_BUFFER`].
+    pub fn with_rx_buffer<const N: usize>(self) -> MHZ<Tx, Rx, M, N, P, O> {
+        MHZ {
+            uart_tx: self.uart_tx,
+            uart_rx: self.uart_rx,
+            resync_limit: self.resync_limit,
+            echo_cancel: self.echo_cancel,
+            pacing: self.pacing,
+            observer: self.observer,
+            stats: self.stats,
+            correction: self.correction,
+            rx_ready: self.rx_ready,
+            model: PhantomData,
+        }
+    }
+
+    /// Space commands apart using `pacing`, for example
+    /// [`Paced`](pacing::Paced). By default commands are send immediately.
+    pub fn with_pacing<P2: Pacing>(self, pacing: P2) -> MHZ<Tx, Rx, M, RX_BUF, P2, O> {
+        MHZ {
+            uart_tx: self.uart_tx,
+            uart_rx: self.uart_rx,
+            resync_limit: self.resync_limit,
+            echo_cancel: self.echo_cancel,
+            pacing,
+            observer: self.observer,
+            stats: self.stats,
+            correction: self.correction,
+            rx_ready: self.rx_ready,
+            model: PhantomData,
+        }
+    }
+
+    /// Report protocol events to `observer`, for example to count them
+    /// into metrics. By default they are not reported.
+    pub fn with_observer<O2: Observer>(self, observer: O2) -> MHZ<Tx, Rx, M, RX_BUF, P, O2> {
+        MHZ {
+            uart_tx: self.uart_tx,
+            uart_rx: self.uart_rx,
+            resync_limit: self.resync_limit,
+            echo_cancel: self.echo_cancel,
+            pacing: self.pacing,
+            observer,
+            stats: self.stats,
+            correction: self.correction,
+            rx_ready: self.rx_ready,
+            model: PhantomData,
+        }
+    }
+
+    /// Access the observer, for example to read out the counts.
+    pub fn observer(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    /// Counts of reads, failures and recoveries since construction or the
+    /// last [`reset_stats`](MHZ::reset_stats).
+    pub fn stats(&self) -> EventCounts {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = EventCounts::default();
+    }
+
+    /// The observer together with the built in [`stats`](MHZ::stats).
+    fn observe(&mut self) -> (&mut O, &mut EventCounts) {
+        (&mut self.observer, &mut self.stats)
+    }
+
+    fn timed_out(&mut self) -> Error<Tx::Error, Rx::Error> {
+        self.observe().timeout();
+        Error::Timeout
+    }
+
+    /// Give up with [`Error::Desynchronized`] after discarding more then
+    /// `bytes` while searching for a response. Defaults to
+    /// [`DEFAULT_RESYNC_LIMIT`].
+    pub fn set_resync_limit(&mut self, bytes: usize) {
+        self.resync_limit = bytes;
+    }
+
+    /// Enable on boards where TX is looped back to RX, for example through
+    /// a single wire level shifter. Every command then echoes into the
+    /// receiver, those bytes are discarded right after sending.
+    pub fn set_echo_cancel(&mut self, enabled: bool) {
+        self.echo_cancel = enabled;
+    }
+
+    /// Discard what `ready` reports waiting. Gives up after
+    /// `resync_limit` bytes so a noisy line can not keep us here.
+    async fn discard_stale(
         &mut self,
-    ) -> Result<measurement::Measurement, Error<Tx::Error, Rx::Error>> {
+        ready: fn(&mut Rx) -> bool,
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        let mut buf = [0u8; PAYLOAD_SIZE];
+        let mut discarded = 0usize;
+        while discarded <= self.resync_limit && ready(&mut self.uart_rx) {
+            let n = self.uart_rx.read(&mut buf).await.map_err(Error::Reading)?;
+            if n == 0 {
+                break;
+            }
+            discarded = discarded.saturating_add(n);
+            trace!("discarded {} stale bytes", n);
+        }
+        Ok(())
+    }
+
+    async fn write_command(&mut self, command: &Frame) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        self.pacing.before_command().await;
+        if let Some(ready) = self.rx_ready {
+            self.discard_stale(ready).await?;
+        }
         self.uart_tx
-            .write_all(&commands::READ_CO2)
+            .write_all(command.as_bytes())
             .await
             .map_err(Error::WritingToUart)?;
-        defmt::trace!("flushing uart");
         self.uart_tx.flush().await.map_err(Error::FlushingUart)?;
+        trace_frame!(Tx, command.as_bytes());
+        self.observe().frame_sent(command);
+        if self.echo_cancel {
+            self.discard_echo(command).await?;
+        }
+        Ok(())
+    }
 
-        defmt::trace!("reading uart");
-        let package = read_package::<Tx, Rx>(&mut self.uart_rx).await?;
+    /// Read back the echo of `command`, never reads past it.
+    async fn discard_echo(&mut self, command: &Frame) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        let mut echo = [0u8; PAYLOAD_SIZE];
+        let mut received = 0;
+        while received < PAYLOAD_SIZE {
+            let n = self
+                .uart_rx
+                .read(&mut echo[received..])
+                .await
+                .map_err(Error::Reading)?;
+            if n == 0 {
+                return Err(Error::ReadingEOF);
+            }
+            received += n;
+        }
+        if *command != echo {
+            debug!("echo does not match the command send");
+        }
+        Ok(())
+    }
+
+    /// A [`Protocol`](protocol::Protocol) with `command` queued.
+    fn protocol(&self, command: Frame) -> protocol::Protocol {
+        let mut protocol = protocol::Protocol::new();
+        protocol.set_resync_limit(self.resync_limit);
+        protocol.request_frame(command);
+        protocol
+    }
 
-        defmt::trace!("checking packet checksum");
-        if !measurement::checksum_valid(&package) {
-            return Err(Error::InvalidChecksum);
+    /// Send a command and read the response to it.
+    async fn transact(
+        &mut self,
+        command: &Frame,
+    ) -> Result<[u8; PAYLOAD_SIZE], Error<Tx::Error, Rx::Error>> {
+        let mut protocol = self.protocol(*command);
+        if let Some(frame) = protocol.next_frame_to_send() {
+            self.write_command(&frame).await?;
         }
-        measurement::Measurement::parse_response(package)
+
+        trace!("reading uart");
+        let package = read_response::<Tx::Error, Rx, RX_BUF>(
+            &mut self.uart_rx,
+            &mut protocol,
+            &mut (&mut self.observer, &mut self.stats),
+        )
+        .await?;
+        trace_frame!(Rx, package);
+        self.observe().frame_received(&Frame::from_bytes(package));
+
+        trace!("checking packet checksum");
+        self.verify_checksum(&package)?;
+        Ok(package)
+    }
+
+    /// Parse a 0x86 response and apply the [`Correction`].
+    fn parse_measurement(
+        &self,
+        package: [u8; PAYLOAD_SIZE],
+    ) -> Result<measurement::Measurement, Error<Tx::Error, Rx::Error>> {
+        measurement::Measurement::parse_response(package).map(|m| m.corrected(&self.correction))
+    }
+
+    fn verify_checksum(
+        &mut self,
+        package: &[u8; PAYLOAD_SIZE],
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        let res = measurement::verify_checksum(package);
+        match res {
+            Ok(()) => self.observe().frame_accepted(&Frame::from_bytes(*package)),
+            Err(_) => self
+                .observe()
+                .checksum_failure(&Frame::from_bytes(*package)),
+        }
+        res
+    }
+
+    pub async fn read_co2(
+        &mut self,
+    ) -> Result<measurement::Measurement, Error<Tx::Error, Rx::Error>> {
+        let package = self.transact(&Command::ReadCo2.to_frame()).await?;
+        self.parse_measurement(package)
     }
 
     pub async fn read_co2_raw(
         &mut self,
     ) -> Result<measurement::RawMeasurement, Error<Tx::Error, Rx::Error>> {
-        self.uart_tx
-            .write_all(&commands::READ_RAW_CO2)
+        let package = self.transact(&Command::ReadRawCo2.to_frame()).await?;
+        measurement::RawMeasurement::parse_response(package)
+    }
+
+    /// Like [`read_co2`](MHZ::read_co2) but gives up with [`Error::Timeout`]
+    /// once `clock` passes `deadline_ms`, `delay` is used to wake up at the
+    /// deadline. A late response can be discarded using
+    /// [`with_rx_drain`](MHZ::with_rx_drain).
+    pub async fn read_co2_before(
+        &mut self,
+        deadline_ms: u64,
+        clock: &impl Clock,
+        delay: &mut impl DelayNs,
+    ) -> Result<measurement::Measurement, Error<Tx::Error, Rx::Error>> {
+        let Some(remaining) = deadline_ms.checked_sub(clock.now_ms()).filter(|ms| *ms > 0) else {
+            return Err(self.timed_out());
+        };
+        let remaining = remaining.min(u64::from(u32::MAX)) as u32;
+        time::with_timeout(delay, remaining, self.read_co2())
             .await
-            .map_err(Error::WritingToUart)?;
-        self.uart_tx.flush().await.map_err(Error::FlushingUart)?;
+            .unwrap_or_else(|| Err(self.timed_out()))
+    }
 
-        let mut buf = [0u8; PAYLOAD_SIZE];
-        self.read_into(&mut buf).await?;
-        if !measurement::checksum_valid(&buf) {
-            return Err(Error::InvalidChecksum);
+    /// Read the final and the raw CO2 concentration back to back, waiting
+    /// [`MIN_COMMAND_INTERVAL_MS`] in between.
+    pub async fn read_both(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<(measurement::Measurement, measurement::RawMeasurement), Error<Tx::Error, Rx::Error>>
+    {
+        let measurement = self.read_co2().await?;
+        delay.delay_ms(MIN_COMMAND_INTERVAL_MS).await;
+        let raw = self.read_co2_raw().await?;
+        Ok((measurement, raw))
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read + ReadReady,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Discard anything still in the receive buffer before every command,
+    /// see [`drain_rx`](MHZ::drain_rx). Off by default.
+    pub fn with_rx_drain(mut self) -> Self {
+        self.rx_ready = Some(rx_ready::<Rx>);
+        self
+    }
+
+    /// Discard anything still in the receive buffer, so responses from a
+    /// previous, cancelled or timed out transaction can not be mistaken for
+    /// the response to the next command. Stops after
+    /// [`set_resync_limit`](MHZ::set_resync_limit) bytes on a noisy line.
+    pub async fn drain_rx(&mut self) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        self.discard_stale(rx_ready::<Rx>).await
+    }
+}
+
+/// Errors count as ready, the read that follows reports them.
+fn rx_ready<Rx: ReadReady>(rx: &mut Rx) -> bool {
+    rx.read_ready().unwrap_or(true)
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Configurable,
+    P: Pacing,
+    O: Observer,
+{
+    /// Perform a zero point calibration, the current CO2 concentration will
+    /// be taken as 400ppm. The sensor does not respond to this command.
+    ///
+    /// Requires a [`ZeroCalibration`] guard confirming the sensor is in
+    /// fresh air. With [`Paced`](pacing::Paced) pacing the next command
+    /// waits for [`calibration_settle_ms`](timing::Timing::calibration_settle_ms).
+    pub async fn calibrate_zero(
+        &mut self,
+        _fresh_air: ZeroCalibration,
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        debug!("performing zero point calibration");
+        self.write_command(&Command::CalibrateZero.to_frame())
+            .await?;
+        self.pacing.settle(M::TIMING.calibration_settle_ms);
+        Ok(())
+    }
+
+    /// Give up calibration and configuration, the returned sensor can only
+    /// be read. See [`ReadOnly`].
+    pub fn read_only(self) -> MHZ<Tx, Rx, ReadOnly<M>, RX_BUF, P, O> {
+        MHZ {
+            uart_tx: self.uart_tx,
+            uart_rx: self.uart_rx,
+            resync_limit: self.resync_limit,
+            echo_cancel: self.echo_cancel,
+            pacing: self.pacing,
+            observer: self.observer,
+            stats: self.stats,
+            correction: self.correction,
+            rx_ready: self.rx_ready,
+            model: PhantomData,
         }
-        measurement::RawMeasurement::parse_response(buf)
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: SupportsRange,
+    P: Pacing,
+    O: Observer,
+{
+    /// Set the detection range. The sensor does not respond to this command.
+    /// With [`Paced`](pacing::Paced) pacing the next command waits for
+    /// [`config_settle_ms`](timing::Timing::config_settle_ms).
+    pub async fn set_range(&mut self, range: M::Range) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        debug!("setting range to {}ppm", range.max_ppm());
+        self.write_command(&Command::SetRange(range.into()).to_frame())
+            .await?;
+        self.pacing.settle(M::TIMING.config_settle_ms);
+        Ok(())
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: SupportsFirmwareVersion,
+    P: Pacing,
+    O: Observer,
+{
+    pub async fn read_firmware_version(
+        &mut self,
+    ) -> Result<FirmwareVersion, Error<Tx::Error, Rx::Error>> {
+        let package = self
+            .transact(&Command::ReadFirmwareVersion.to_frame())
+            .await?;
+        FirmwareVersion::parse_response(package)
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: SupportsTemperature,
+    P: Pacing,
+    O: Observer,
+{
+    /// Read only the temperature in degrees Celsius. It is measured inside
+    /// the sensor for internal compensation and only accurate to about
+    /// ±1-2°C, more when the sensor is warming itself up.
+    pub async fn read_temperature(&mut self) -> Result<i16, Error<Tx::Error, Rx::Error>> {
+        Ok(self.read_co2().await?.temp_celsius())
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: SupportsAbc,
+    P: Pacing,
+    O: Observer,
+{
+    /// Turn automatic baseline correction (ABC) on or off. The sensor does not
+    /// respond to this command. With [`Paced`](pacing::Paced) pacing the next
+    /// command waits for [`config_settle_ms`](timing::Timing::config_settle_ms).
+    pub async fn set_abc(&mut self, enabled: bool) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        debug!("setting abc to: {}", enabled);
+        self.write_command(&Command::SetAbc(enabled).to_frame())
+            .await?;
+        self.pacing.settle(M::TIMING.config_settle_ms);
+        Ok(())
     }
 }
@@ -0,0 +1,109 @@
+//! Use the sensor from Linux, for example on a Raspberry Pi.
+//!
+//! ```no_run
+//! # async fn run() {
+//! use mhzx::linux::LinuxSensor;
+//!
+//! let mut sensor = LinuxSensor::open("/dev/ttyAMA0").unwrap();
+//! let measurement = sensor.read_co2().await.unwrap();
+//! println!("co2 concentration: {}", measurement.co2);
+//! # }
+//! ```
+
+extern crate std;
+
+use core::future::poll_fn;
+use core::pin::Pin;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use embedded_io_async::{ErrorType, Read, ReadReady, Write};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_serial::{DataBits, Parity, SerialPort, SerialPortBuilderExt, SerialStream, StopBits};
+
+use crate::model::{Mhz19B, Model};
+use crate::MHZ;
+
+/// A sensor connected to a serial port.
+pub type LinuxSensor<M = Mhz19B> = MHZ<SerialTx, SerialRx, M>;
+
+type Shared = Arc<Mutex<SerialStream>>;
+
+fn lock(port: &Shared) -> MutexGuard<'_, SerialStream> {
+    // the lock is never held across an await or a panic
+    port.lock().expect("lock is never poisoned")
+}
+
+/// Sending half of a serial port.
+pub struct SerialTx(Shared);
+
+/// Receiving half of a serial port.
+pub struct SerialRx(Shared);
+
+/// Open `path` configured as 9600 baud 8N1 and split it for use with
+/// [`MHZ`]. Must be called from within a tokio runtime.
+pub fn open_serial(path: &str) -> Result<(SerialTx, SerialRx), tokio_serial::Error> {
+    let port = tokio_serial::new(path, 9600)
+        .data_bits(DataBits::Eight)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .open_native_async()?;
+    let port = Arc::new(Mutex::new(port));
+    Ok((SerialTx(port.clone()), SerialRx(port)))
+}
+
+impl MHZ<SerialTx, SerialRx> {
+    /// Open the sensor connected to the serial port at `path`. Must be
+    /// called from within a tokio runtime.
+    pub fn open(path: &str) -> Result<Self, tokio_serial::Error> {
+        let (tx, rx) = open_serial(path)?;
+        Ok(MHZ::from_tx_rx(tx, rx))
+    }
+}
+
+impl<M: Model> MHZ<SerialTx, SerialRx, M> {
+    /// Like [`open`](MHZ::open) for a specific [`Model`].
+    pub fn open_model(path: &str, model: M) -> Result<Self, tokio_serial::Error> {
+        let (tx, rx) = open_serial(path)?;
+        Ok(MHZ::from_tx_rx_with_model(tx, rx, model))
+    }
+}
+
+impl ErrorType for SerialTx {
+    type Error = io::Error;
+}
+
+impl ErrorType for SerialRx {
+    type Error = io::Error;
+}
+
+impl Write for SerialTx {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        poll_fn(|cx| Pin::new(&mut *lock(&self.0)).poll_write(cx, buf)).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        poll_fn(|cx| Pin::new(&mut *lock(&self.0)).poll_flush(cx)).await
+    }
+}
+
+impl Read for SerialRx {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        poll_fn(|cx| {
+            let mut buf = ReadBuf::new(buf);
+            Pin::new(&mut *lock(&self.0))
+                .poll_read(cx, &mut buf)
+                .map_ok(|()| buf.filled().len())
+        })
+        .await
+    }
+}
+
+impl ReadReady for SerialRx {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        lock(&self.0)
+            .bytes_to_read()
+            .map(|n| n > 0)
+            .map_err(io::Error::from)
+    }
+}
@@ -1,61 +1,155 @@
 use super::Error;
 use super::PAYLOAD_SIZE;
-use core::fmt;
-use defmt;
-
-pub(crate) fn checksum(bytes: &[u8; PAYLOAD_SIZE]) -> u8 {
-    (!bytes
-        .iter()
-        .skip(1)
-        .take(7)
-        .fold(0u8, |s, i| s.wrapping_add(*i)))
-    .wrapping_add(1)
+use crate::model::DetectionRange;
+use crate::units::Ppm;
+
+/// A `const fn` so command frames can be checked at compile time.
+pub(crate) const fn checksum(bytes: &[u8; PAYLOAD_SIZE]) -> u8 {
+    let mut sum = 0u8;
+    let mut i = 1;
+    while i < PAYLOAD_SIZE - 1 {
+        sum = sum.wrapping_add(bytes[i]);
+        i += 1;
+    }
+    (!sum).wrapping_add(1)
 }
 
-pub(crate) fn checksum_valid(bytes: &[u8; PAYLOAD_SIZE]) -> bool {
+pub(crate) const fn checksum_valid(bytes: &[u8; PAYLOAD_SIZE]) -> bool {
     checksum(bytes) == bytes[8]
 }
 
-#[derive(defmt::Format, Debug)]
+pub(crate) fn verify_checksum<TxError, RxError>(
+    packet: &[u8; PAYLOAD_SIZE],
+) -> Result<(), Error<TxError, RxError>> {
+    let computed = checksum(packet);
+    if computed == packet[8] {
+        Ok(())
+    } else {
+        Err(Error::InvalidChecksum {
+            packet: *packet,
+            computed,
+            received: packet[8],
+        })
+    }
+}
+
+pub(crate) fn check_header<RxError, TxError>(
+    p: &[u8; PAYLOAD_SIZE],
+    expected: u8,
+) -> Result<(), Error<RxError, TxError>> {
+    if p[0] != 0xFF {
+        return Err(Error::WrongStartByte(p[0]));
+    }
+    if p[1] != expected {
+        return Err(Error::UnexpectedCommand {
+            expected,
+            got: p[1],
+        });
+    }
+    Ok(())
+}
+
+/// With the `minicbor` feature this encodes as a CBOR map keyed by the
+/// field indices, decoders skip fields they do not know.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "postcard-schema", derive(postcard_schema::Schema))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "minicbor",
+    derive(minicbor::Encode, minicbor::Decode),
+    cbor(map)
+)]
+#[derive(Debug, Clone, Copy)]
 pub struct Measurement {
-    /// CO2 concentration, PPM.
-    pub co2: u16,
+    /// CO2 concentration.
+    #[cfg_attr(feature = "minicbor", n(0))]
+    pub co2: Ppm,
     /// Temperature, degrees Celsius plus 40.
+    #[cfg_attr(feature = "minicbor", n(1))]
     pub temp: u8,
     /// If ABC is turned on - counter in "ticks" within a calibration cycle.
+    #[cfg_attr(feature = "minicbor", n(2))]
     pub calib_ticks: u8,
     /// If ABC is turned on - the number of performed calibration cycles.
+    #[cfg_attr(feature = "minicbor", n(3))]
     pub calib_cycles: u8,
+    /// Undocumented, bytes 6 and 7 of the response as big endian. Earlier
+    /// versions of this driver exposed them as `u` (and byte 5, now
+    /// [`calib_ticks`](Measurement::calib_ticks), as `s`). Their meaning is
+    /// unknown, they are only here so long running deployments can keep
+    /// trending them.
+    #[cfg_attr(feature = "minicbor", n(4))]
+    pub undocumented_u: u16,
 }
 
-#[derive(defmt::Format, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "postcard-schema", derive(postcard_schema::Schema))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(
+    feature = "minicbor",
+    derive(minicbor::Encode, minicbor::Decode),
+    cbor(map)
+)]
+#[derive(Debug, Clone, Copy)]
 pub struct RawMeasurement {
     // Smoothed temperature ADC value.
+    #[cfg_attr(feature = "minicbor", n(0))]
     pub adc_temp: u16,
     // CO2 level before clamping
+    #[cfg_attr(feature = "minicbor", n(1))]
     pub co2: u16,
     // Minimum light ADC value.
+    #[cfg_attr(feature = "minicbor", n(2))]
     pub adc_min_light: u16,
 }
 
+/// Firmware version as reported by the sensor, four ASCII characters
+/// for example "0443".
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirmwareVersion(pub [u8; 4]);
+
+impl FirmwareVersion {
+    /// The version as text, None if the sensor send non ASCII bytes.
+    pub fn as_str(&self) -> Option<&str> {
+        core::str::from_utf8(&self.0).ok().filter(|s| s.is_ascii())
+    }
+
+    pub(crate) fn parse_response<RxError, TxError>(
+        p: [u8; PAYLOAD_SIZE],
+    ) -> Result<Self, Error<RxError, TxError>> {
+        check_header(&p, 0xA0)?;
+        let [_, _, a, b, c, d, _, _, _] = p;
+        Ok(FirmwareVersion([a, b, c, d]))
+    }
+}
+
 impl Measurement {
+    /// Temperature in degrees Celsius. Not every model reports the
+    /// temperature, see [`Model::Temperature`](crate::model::Model::Temperature).
+    pub fn temp_celsius(&self) -> i16 {
+        i16::from(self.temp) - 40
+    }
+
+    /// True if the concentration is at the top of the configured detection
+    /// `range`. The sensor then reports the maximum of the range regardless
+    /// of how high the real concentration is.
+    pub fn is_clamped(&self, range: impl DetectionRange) -> bool {
+        self.co2 >= range.max_ppm()
+    }
+
     pub(crate) fn parse_response<RxError, TxError>(
         p: [u8; PAYLOAD_SIZE],
-    ) -> Result<Self, Error<RxError, TxError>>
-    where
-        RxError: defmt::Format + fmt::Debug,
-        TxError: defmt::Format + fmt::Debug,
-    {
-        if p[0] != 0xFF || p[1] != 0x86 {
-            return Err(Error::InvalidPacket);
-        }
+    ) -> Result<Self, Error<RxError, TxError>> {
+        check_header(&p, 0x86)?;
 
-        let [_, _, ch, cl, temp, calib_ticks, calib_cycles, _, _] = p;
+        let [_, _, ch, cl, temp, calib_ticks, calib_cycles, u_low, _] = p;
         Ok(Measurement {
-            co2: u16::from_be_bytes([ch, cl]),
+            co2: Ppm(u16::from_be_bytes([ch, cl])),
             temp,
             calib_ticks,
             calib_cycles,
+            undocumented_u: u16::from_be_bytes([calib_cycles, u_low]),
         })
     }
 }
@@ -63,14 +157,8 @@ impl Measurement {
 impl RawMeasurement {
     pub(crate) fn parse_response<RxError, TxError>(
         p: [u8; PAYLOAD_SIZE],
-    ) -> Result<Self, Error<RxError, TxError>>
-    where
-        RxError: defmt::Format + fmt::Debug,
-        TxError: defmt::Format + fmt::Debug,
-    {
-        if p[0] != 0xFF || p[1] != 0x85 {
-            return Err(Error::InvalidPacket);
-        }
+    ) -> Result<Self, Error<RxError, TxError>> {
+        check_header(&p, 0x85)?;
 
         let [_, _, th, tl, ch, cl, lh, ll, _] = p;
         Ok(RawMeasurement {
@@ -84,7 +172,6 @@ impl RawMeasurement {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::commands;
 
     #[test]
     fn parse_measurement() {
@@ -94,16 +181,98 @@ mod tests {
         // checksum mismatch
         let p = [0xFF, 0x86, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x78];
         assert!(!checksum_valid(&p));
+        assert_eq!(
+            verify_checksum::<(), ()>(&p).unwrap_err(),
+            Error::InvalidChecksum {
+                packet: p,
+                computed: 0x79,
+                received: 0x78
+            }
+        );
 
         // invalid command field
         let p = [0xFF, 0x87, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x78];
         assert!(checksum_valid(&p));
-        Measurement::parse_response::<(), ()>(p).unwrap_err();
+        assert_eq!(
+            Measurement::parse_response::<(), ()>(p).unwrap_err(),
+            Error::UnexpectedCommand {
+                expected: 0x86,
+                got: 0x87
+            }
+        );
 
         // byte0 is not 0xFF
         let p = [0xFE, 0x86, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
         assert!(checksum_valid(&p));
-        Measurement::parse_response::<(), ()>(p).unwrap_err();
+        assert_eq!(
+            Measurement::parse_response::<(), ()>(p).unwrap_err(),
+            Error::WrongStartByte(0xFE)
+        );
+    }
+
+    #[test]
+    fn temperature() {
+        let p = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97];
+        let m = Measurement::parse_response::<(), ()>(p).unwrap();
+        assert_eq!(m.temp_celsius(), 22);
+
+        let p = [0xFF, 0x86, 0x01, 0xA4, 0x1E, 0x00, 0x00, 0x00, 0xB7];
+        let m = Measurement::parse_response::<(), ()>(p).unwrap();
+        assert_eq!(m.temp_celsius(), -10);
+    }
+
+    #[test]
+    fn undocumented_bytes() {
+        let p = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x41, 0x02, 0x30, 0x00];
+        let m = Measurement::parse_response::<(), ()>(p).unwrap();
+        assert_eq!(m.calib_ticks, 0x41);
+        assert_eq!(m.calib_cycles, 0x02);
+        assert_eq!(m.undocumented_u, 0x0230);
+    }
+
+    #[test]
+    fn firmware_version() {
+        let p = [0xFF, 0xA0, b'0', b'4', b'4', b'3', 0x00, 0x00, 0x00];
+        let version = FirmwareVersion::parse_response::<(), ()>(p).unwrap();
+        assert_eq!(version.as_str(), Some("0443"));
+    }
+
+    #[test]
+    fn clamping() {
+        use crate::model::Range;
+
+        let m = Measurement {
+            co2: Ppm(5000),
+            temp: 60,
+            calib_ticks: 0,
+            calib_cycles: 0,
+            undocumented_u: 0,
+        };
+        assert!(m.is_clamped(Range::Ppm5000));
+        assert!(!m.is_clamped(Range::Ppm10000));
+    }
+
+    #[cfg(feature = "minicbor")]
+    #[test]
+    fn cbor_round_trip() {
+        let m = Measurement {
+            co2: Ppm(612),
+            temp: 61,
+            calib_ticks: 3,
+            calib_cycles: 1,
+            undocumented_u: 0,
+        };
+        let mut buf = [0u8; 32];
+        minicbor::encode(m, &mut buf[..]).unwrap();
+        let decoded: Measurement = minicbor::decode(&buf).unwrap();
+        assert_eq!(decoded.co2, m.co2);
+        assert_eq!(decoded.calib_ticks, 3);
+
+        // the UART error types need to implement Encode/Decode as well
+        let e = Error::<u8>::Desynchronized { discarded: 200 };
+        minicbor::encode(&e, &mut buf[..]).unwrap();
+        let decoded: Error<u8> = minicbor::decode(&buf).unwrap();
+        assert_eq!(decoded, e);
     }
 
     #[test]
@@ -116,8 +285,5 @@ mod tests {
             corrupted[i] = corrupted[i].wrapping_add(1);
             assert!(!checksum_valid(&corrupted));
         }
-
-        assert!(checksum_valid(&commands::READ_CO2));
-        assert!(checksum_valid(&commands::READ_RAW_CO2));
     }
 }
@@ -0,0 +1,155 @@
+//! A stand-in for [`MHZ`](crate::MHZ) in unit tests of code using this crate.
+//!
+//! [`MockSensor`] has the same methods as the real driver but returns
+//! scripted results and records the configuration commands it receives.
+//!
+//! ```
+//! # futures::executor::block_on(async {
+//! use mhzx::mock::MockSensor;
+//! use mhzx::{Error, Measurement, Ppm};
+//!
+//! let mut sensor: MockSensor = MockSensor::new();
+//! sensor.push_co2(Ok(Measurement {
+//!     co2: Ppm(800),
+//!     temp: 62,
+//!     calib_ticks: 0,
+//!     calib_cycles: 0,
+//!     undocumented_u: 0,
+//! }));
+//! sensor.push_co2(Err(Error::ReadingEOF));
+//!
+//! assert_eq!(sensor.read_co2().await.unwrap().co2, 800);
+//! assert!(sensor.read_co2().await.is_err());
+//! # });
+//! ```
+
+use core::convert::Infallible;
+
+use crate::model::{
+    Configurable, Mhz19B, Model, SupportsAbc, SupportsFirmwareVersion, SupportsRange,
+    SupportsTemperature,
+};
+use crate::ring::Ring;
+use crate::{Error, FirmwareVersion, Measurement, RawMeasurement, ZeroCalibration};
+
+type Script<T, TxError, RxError, const N: usize> = Ring<Result<T, Error<TxError, RxError>>, N>;
+
+/// Returns scripted results, can hold `N` results per method. Panics when a
+/// method is called with no result left for it.
+pub struct MockSensor<
+    M: Model = Mhz19B,
+    TxError = Infallible,
+    RxError = Infallible,
+    const N: usize = 16,
+> {
+    co2: Script<Measurement, TxError, RxError, N>,
+    raw: Script<RawMeasurement, TxError, RxError, N>,
+    firmware: Script<FirmwareVersion, TxError, RxError, N>,
+    /// Number of zero point calibrations performed.
+    pub zero_calibrations: usize,
+    /// The last range set.
+    pub range: Option<M::Range>,
+    /// The last ABC state set.
+    pub abc: Option<bool>,
+}
+
+impl<M: Model, TxError, RxError, const N: usize> MockSensor<M, TxError, RxError, N> {
+    pub const fn new() -> Self {
+        MockSensor {
+            co2: Ring::new(),
+            raw: Ring::new(),
+            firmware: Ring::new(),
+            zero_calibrations: 0,
+            range: None,
+            abc: None,
+        }
+    }
+
+    /// Script the result of the next unscripted [`read_co2`](Self::read_co2).
+    /// Panics if the script is full.
+    pub fn push_co2(&mut self, result: Result<Measurement, Error<TxError, RxError>>) {
+        push(&mut self.co2, result)
+    }
+
+    /// Script the result of the next unscripted
+    /// [`read_co2_raw`](Self::read_co2_raw). Panics if the script is full.
+    pub fn push_co2_raw(&mut self, result: Result<RawMeasurement, Error<TxError, RxError>>) {
+        push(&mut self.raw, result)
+    }
+
+    /// Script the result of the next unscripted firmware version query.
+    /// Panics if the script is full.
+    pub fn push_firmware_version(
+        &mut self,
+        result: Result<FirmwareVersion, Error<TxError, RxError>>,
+    ) {
+        push(&mut self.firmware, result)
+    }
+
+    pub async fn read_co2(&mut self) -> Result<Measurement, Error<TxError, RxError>> {
+        self.co2
+            .pop_front()
+            .expect("no scripted read_co2 result left")
+    }
+
+    pub async fn read_co2_raw(&mut self) -> Result<RawMeasurement, Error<TxError, RxError>> {
+        self.raw
+            .pop_front()
+            .expect("no scripted read_co2_raw result left")
+    }
+}
+
+impl<M: Configurable, TxError, RxError, const N: usize> MockSensor<M, TxError, RxError, N> {
+    pub async fn calibrate_zero(
+        &mut self,
+        _fresh_air: ZeroCalibration,
+    ) -> Result<(), Error<TxError, RxError>> {
+        self.zero_calibrations += 1;
+        Ok(())
+    }
+}
+
+impl<M: SupportsRange, TxError, RxError, const N: usize> MockSensor<M, TxError, RxError, N> {
+    pub async fn set_range(&mut self, range: M::Range) -> Result<(), Error<TxError, RxError>> {
+        self.range = Some(range);
+        Ok(())
+    }
+}
+
+impl<M: SupportsAbc, TxError, RxError, const N: usize> MockSensor<M, TxError, RxError, N> {
+    pub async fn set_abc(&mut self, enabled: bool) -> Result<(), Error<TxError, RxError>> {
+        self.abc = Some(enabled);
+        Ok(())
+    }
+}
+
+impl<M: SupportsFirmwareVersion, TxError, RxError, const N: usize>
+    MockSensor<M, TxError, RxError, N>
+{
+    pub async fn read_firmware_version(
+        &mut self,
+    ) -> Result<FirmwareVersion, Error<TxError, RxError>> {
+        self.firmware
+            .pop_front()
+            .expect("no scripted read_firmware_version result left")
+    }
+}
+
+impl<M: SupportsTemperature, TxError, RxError, const N: usize> MockSensor<M, TxError, RxError, N> {
+    /// Pops a scripted [`read_co2`](Self::read_co2) result.
+    pub async fn read_temperature(&mut self) -> Result<i16, Error<TxError, RxError>> {
+        Ok(self.read_co2().await?.temp_celsius())
+    }
+}
+
+impl<M: Model, TxError, RxError, const N: usize> Default for MockSensor<M, TxError, RxError, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn push<T, const N: usize>(script: &mut Ring<T, N>, item: T) {
+    if script.push_back(item).is_err() {
+        panic!("script is full, increase N");
+    }
+}
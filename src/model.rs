@@ -0,0 +1,280 @@
+//! Marker types for the members of the MH-Z* family.
+//!
+//! The models share the UART protocol but differ in the commands they
+//! support, the detection ranges they can be set to and their timings.
+//! [`MHZ`](crate::MHZ) is generic over one of these markers so that sending
+//! an unsupported command or selecting an invalid range fails to compile.
+
+use core::marker::PhantomData;
+
+use crate::timing::Timing;
+
+/// A detection range the sensor can be set to.
+pub trait DetectionRange: Copy {
+    /// The upper bound of the range in ppm.
+    fn max_ppm(self) -> u16;
+    /// The range with upper bound `max_ppm`, if there is one.
+    fn from_max_ppm(max_ppm: u16) -> Option<Self> {
+        let _ = max_ppm;
+        None
+    }
+}
+
+/// Detection ranges supported by most of the family.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Range {
+    /// 0 - 2000ppm
+    Ppm2000,
+    /// 0 - 5000ppm
+    Ppm5000,
+    /// 0 - 10000ppm
+    Ppm10000,
+}
+
+impl Range {
+    /// The upper bound of the range in ppm, usable in const contexts.
+    pub const fn max_ppm(self) -> u16 {
+        match self {
+            Range::Ppm2000 => 2000,
+            Range::Ppm5000 => 5000,
+            Range::Ppm10000 => 10000,
+        }
+    }
+}
+
+impl DetectionRange for Range {
+    fn max_ppm(self) -> u16 {
+        Range::max_ppm(self)
+    }
+
+    fn from_max_ppm(max_ppm: u16) -> Option<Self> {
+        match max_ppm {
+            2000 => Some(Range::Ppm2000),
+            5000 => Some(Range::Ppm5000),
+            10000 => Some(Range::Ppm10000),
+            _ => None,
+        }
+    }
+}
+
+/// Detection ranges supported by the MH-Z19C.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mhz19cRange {
+    /// 0 - 2000ppm
+    Ppm2000,
+    /// 0 - 5000ppm
+    Ppm5000,
+}
+
+impl DetectionRange for Mhz19cRange {
+    fn max_ppm(self) -> u16 {
+        match self {
+            Mhz19cRange::Ppm2000 => 2000,
+            Mhz19cRange::Ppm5000 => 5000,
+        }
+    }
+
+    fn from_max_ppm(max_ppm: u16) -> Option<Self> {
+        match max_ppm {
+            2000 => Some(Mhz19cRange::Ppm2000),
+            5000 => Some(Mhz19cRange::Ppm5000),
+            _ => None,
+        }
+    }
+}
+
+impl From<Mhz19cRange> for Range {
+    fn from(range: Mhz19cRange) -> Self {
+        match range {
+            Mhz19cRange::Ppm2000 => Range::Ppm2000,
+            Mhz19cRange::Ppm5000 => Range::Ppm5000,
+        }
+    }
+}
+
+/// Whether a model has a feature: [`Supported`] or [`Unsupported`].
+pub trait Capability {
+    const SUPPORTED: bool;
+}
+
+/// The model has the feature.
+#[derive(Debug, Clone, Copy)]
+pub enum Supported {}
+
+/// The model lacks the feature.
+#[derive(Debug, Clone, Copy)]
+pub enum Unsupported {}
+
+impl Capability for Supported {
+    const SUPPORTED: bool = true;
+}
+
+impl Capability for Unsupported {
+    const SUPPORTED: bool = false;
+}
+
+/// A member of the MH-Z* family.
+pub trait Model {
+    /// Name of the model as printed on the sensor.
+    const NAME: &'static str;
+    /// Time after power on before readings can be trusted.
+    const WARMUP_MS: u32;
+    /// Whether byte 4 of the 0x86 response carries the temperature. Decides
+    /// if the model implements [`SupportsTemperature`].
+    type Temperature: Capability;
+    /// Whether the model answers command 0xA0. Decides if the model
+    /// implements [`SupportsFirmwareVersion`].
+    type FirmwareVersion: Capability;
+    /// The detection ranges this model supports, a subset of [`Range`].
+    type Range: DetectionRange + Into<Range>;
+    /// The detection range the sensor ships with.
+    const DEFAULT_RANGE: Self::Range;
+    /// Datasheet timings, the [`Timing::DEFAULT`] ones with
+    /// [`WARMUP_MS`](Model::WARMUP_MS) unless overridden.
+    const TIMING: Timing = Timing {
+        warmup_ms: Self::WARMUP_MS,
+        ..Timing::DEFAULT
+    };
+}
+
+/// Models that can change their detection range (command 0x99).
+pub trait SupportsRange: Model {}
+
+/// Models that can toggle automatic baseline correction (command 0x79).
+pub trait SupportsAbc: Model {}
+
+/// Models that report their firmware version (command 0xA0), those with
+/// [`Model::FirmwareVersion`] [`Supported`].
+pub trait SupportsFirmwareVersion: Model<FirmwareVersion = Supported> {}
+impl<M: Model<FirmwareVersion = Supported>> SupportsFirmwareVersion for M {}
+
+/// Models that report their temperature in the 0x86 response, those with
+/// [`Model::Temperature`] [`Supported`].
+pub trait SupportsTemperature: Model<Temperature = Supported> {}
+impl<M: Model<Temperature = Supported>> SupportsTemperature for M {}
+
+/// Whether `M` reports its temperature, for code generic over every model.
+pub const fn reports_temperature<M: Model>() -> bool {
+    M::Temperature::SUPPORTED
+}
+
+/// Whether `M` reports its firmware version, for code generic over every
+/// model.
+pub const fn reports_firmware_version<M: Model>() -> bool {
+    M::FirmwareVersion::SUPPORTED
+}
+
+/// Models the driver may calibrate (command 0x87) and send undocumented
+/// commands to. Every model but [`ReadOnly`].
+pub trait Configurable: Model {}
+
+/// `M` without any command that changes the sensor: no calibration, range,
+/// ABC or undocumented commands. For production firmware that must never
+/// be able to send those by accident.
+///
+/// ```compile_fail
+/// # async fn run<Tx, Rx>(tx: Tx, rx: Rx)
+/// # where
+/// #     Tx: embedded_io_async::Write,
+/// #     Rx: embedded_io_async::Read,
+/// # {
+/// use mhzx::model::{Mhz19B, ReadOnly};
+/// use mhzx::{ZeroCalibration, MHZ};
+///
+/// let mut sensor = MHZ::from_tx_rx_with_model(tx, rx, ReadOnly::<Mhz19B>::new());
+/// sensor.read_co2().await;
+/// sensor.calibrate_zero(ZeroCalibration::confirm_fresh_air()).await;
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOnly<M>(PhantomData<M>);
+
+impl<M: Model> ReadOnly<M> {
+    pub const fn new() -> Self {
+        ReadOnly(PhantomData)
+    }
+}
+
+impl<M: Model> Model for ReadOnly<M> {
+    const NAME: &'static str = M::NAME;
+    const WARMUP_MS: u32 = M::WARMUP_MS;
+    type Temperature = M::Temperature;
+    type FirmwareVersion = M::FirmwareVersion;
+    type Range = M::Range;
+    const DEFAULT_RANGE: M::Range = M::DEFAULT_RANGE;
+    const TIMING: Timing = M::TIMING;
+}
+
+/// The MH-Z19B.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mhz19B;
+
+impl Model for Mhz19B {
+    const NAME: &'static str = "MH-Z19B";
+    const WARMUP_MS: u32 = 3 * 60 * 1000;
+    type Temperature = Supported;
+    type FirmwareVersion = Supported;
+    type Range = Range;
+    const DEFAULT_RANGE: Range = Range::Ppm5000;
+}
+impl SupportsRange for Mhz19B {}
+impl SupportsAbc for Mhz19B {}
+impl Configurable for Mhz19B {}
+
+/// The MH-Z19C.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mhz19C;
+
+impl Model for Mhz19C {
+    const NAME: &'static str = "MH-Z19C";
+    const WARMUP_MS: u32 = 60 * 1000;
+    type Temperature = Supported;
+    type FirmwareVersion = Supported;
+    type Range = Mhz19cRange;
+    const DEFAULT_RANGE: Mhz19cRange = Mhz19cRange::Ppm5000;
+}
+impl SupportsRange for Mhz19C {}
+impl SupportsAbc for Mhz19C {}
+impl Configurable for Mhz19C {}
+
+/// The MH-Z14A.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mhz14A;
+
+impl Model for Mhz14A {
+    const NAME: &'static str = "MH-Z14A";
+    const WARMUP_MS: u32 = 3 * 60 * 1000;
+    type Temperature = Unsupported;
+    type FirmwareVersion = Unsupported;
+    type Range = Range;
+    const DEFAULT_RANGE: Range = Range::Ppm5000;
+    const TIMING: Timing = Timing {
+        warmup_ms: Self::WARMUP_MS,
+        t90_ms: 90 * 1000,
+        ..Timing::DEFAULT
+    };
+}
+impl SupportsRange for Mhz14A {}
+impl SupportsAbc for Mhz14A {}
+impl Configurable for Mhz14A {}
+
+/// The MH-Z16, only supports reading and calibration.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mhz16;
+
+impl Model for Mhz16 {
+    const NAME: &'static str = "MH-Z16";
+    const WARMUP_MS: u32 = 3 * 60 * 1000;
+    type Temperature = Unsupported;
+    type FirmwareVersion = Unsupported;
+    type Range = Range;
+    const DEFAULT_RANGE: Range = Range::Ppm5000;
+    const TIMING: Timing = Timing {
+        warmup_ms: Self::WARMUP_MS,
+        t90_ms: 30 * 1000,
+        ..Timing::DEFAULT
+    };
+}
+impl Configurable for Mhz16 {}
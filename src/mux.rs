@@ -0,0 +1,226 @@
+//! Multiple sensors sharing one UART through a multiplexer.
+//!
+//! ```no_run
+//! # async fn run<Tx, Rx, P, D>(sensor: mhzx::MHZ<Tx, Rx>, pins: [P; 2], delay: D)
+//! # where
+//! #     Tx: embedded_io_async::Write,
+//! #     Rx: embedded_io_async::Read,
+//! #     P: embedded_hal::digital::OutputPin,
+//! #     D: embedded_hal_async::delay::DelayNs,
+//! # {
+//! use mhzx::mux::MuxedSensors;
+//!
+//! // two address pins select one of four sensors
+//! let mut sensors = MuxedSensors::<_, _, _, _, _, 4>::new(sensor, pins, delay);
+//! for channel in 0..4 {
+//!     if let Ok(measurement) = sensors.read_co2(channel).await {
+//!         let _ = measurement.co2;
+//!     }
+//! }
+//! # }
+//! ```
+
+use core::fmt;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+
+use crate::model::Model;
+use crate::{Error, IntoMillis, Measurement, MHZ};
+
+/// Time the UART lines need to settle after switching channels.
+pub const DEFAULT_SETTLE_MS: u32 = 10;
+
+/// Connects the UART to one of the sensors.
+pub trait Selector {
+    type Error;
+
+    fn select(&mut self, channel: usize) -> Result<(), Self::Error>;
+}
+
+/// Address pins of a multiplexer, the first pin is the least significant
+/// bit of the channel.
+impl<P: OutputPin, const BITS: usize> Selector for [P; BITS] {
+    type Error = P::Error;
+
+    fn select(&mut self, channel: usize) -> Result<(), Self::Error> {
+        for (bit, pin) in self.iter_mut().enumerate() {
+            if channel & (1 << bit) != 0 {
+                pin.set_high()?;
+            } else {
+                pin.set_low()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Error reading one of the sensors.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MuxError<SelectError, TxError, RxError> {
+    /// The channel is out of range.
+    NoSuchChannel(usize),
+    /// Switching the multiplexer failed.
+    Select(SelectError),
+    Sensor(Error<TxError, RxError>),
+}
+
+impl<SelectError, TxError, RxError> fmt::Display for MuxError<SelectError, TxError, RxError>
+where
+    SelectError: fmt::Debug,
+    TxError: fmt::Display,
+    RxError: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MuxError::NoSuchChannel(channel) => write!(f, "There is no channel {channel}"),
+            MuxError::Select(e) => write!(f, "Could not switch multiplexer: {e:?}"),
+            MuxError::Sensor(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// Error counters of one channel.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelStats {
+    pub reads: u32,
+    pub errors: u32,
+    /// Errors since the last successful read.
+    pub consecutive_errors: u32,
+}
+
+/// `CHANNELS` sensors behind a multiplexer on one UART.
+pub struct MuxedSensors<Tx, Rx, M, S, D, const CHANNELS: usize> {
+    sensor: MHZ<Tx, Rx, M>,
+    selector: S,
+    delay: D,
+    settle_ms: u32,
+    selected: Option<usize>,
+    stats: [ChannelStats; CHANNELS],
+}
+
+impl<Tx, Rx, M, S, D, const CHANNELS: usize> MuxedSensors<Tx, Rx, M, S, D, CHANNELS>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    S: Selector,
+    D: DelayNs,
+{
+    pub fn new(sensor: MHZ<Tx, Rx, M>, selector: S, delay: D) -> Self {
+        MuxedSensors {
+            sensor,
+            selector,
+            delay,
+            settle_ms: DEFAULT_SETTLE_MS,
+            selected: None,
+            stats: [ChannelStats::default(); CHANNELS],
+        }
+    }
+
+    /// Time to wait after switching channels. Defaults to
+    /// [`DEFAULT_SETTLE_MS`].
+    pub fn set_settle_ms(&mut self, settle: impl IntoMillis) {
+        self.settle_ms = settle.into_millis();
+    }
+
+    /// Switch to `channel` and read the sensor there.
+    pub async fn read_co2(
+        &mut self,
+        channel: usize,
+    ) -> Result<Measurement, MuxError<S::Error, Tx::Error, Rx::Error>> {
+        if channel >= CHANNELS {
+            return Err(MuxError::NoSuchChannel(channel));
+        }
+
+        if self.selected != Some(channel) {
+            // forget the selection, the mux is in an unknown state if this fails
+            self.selected = None;
+            self.selector.select(channel).map_err(MuxError::Select)?;
+            self.selected = Some(channel);
+            self.delay.delay_ms(self.settle_ms).await;
+        }
+
+        let stats = &mut self.stats[channel];
+        stats.reads += 1;
+        match self.sensor.read_co2().await {
+            Ok(measurement) => {
+                stats.consecutive_errors = 0;
+                Ok(measurement)
+            }
+            Err(e) => {
+                stats.errors += 1;
+                stats.consecutive_errors += 1;
+                debug!("reading channel {} failed", channel);
+                Err(MuxError::Sensor(e))
+            }
+        }
+    }
+
+    /// Error counters of `channel`, None if it is out of range.
+    pub fn stats(&self, channel: usize) -> Option<ChannelStats> {
+        self.stats.get(channel).copied()
+    }
+
+    /// Give back the sensor, selector and delay.
+    pub fn release(self) -> (MHZ<Tx, Rx, M>, S, D) {
+        (self.sensor, self.selector, self.delay)
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::sim::{CountingDelay, FakeMhz};
+    use core::convert::Infallible;
+    use futures::executor::block_on;
+
+    /// Every channel reports a different concentration.
+    struct SimSelector<'a>(&'a FakeMhz);
+
+    impl Selector for SimSelector<'_> {
+        type Error = Infallible;
+
+        fn select(&mut self, channel: usize) -> Result<(), Self::Error> {
+            self.0.set_co2(400 + 100 * channel as u16);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn reads_selected_channel() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let sensor = MHZ::from_tx_rx(tx, rx);
+        let mut sensors = MuxedSensors::<_, _, _, _, _, 4>::new(
+            sensor,
+            SimSelector(&sim),
+            CountingDelay::default(),
+        );
+
+        block_on(async {
+            assert_eq!(sensors.read_co2(2).await.unwrap().co2, 600);
+            assert_eq!(sensors.read_co2(2).await.unwrap().co2, 600);
+            assert_eq!(sensors.read_co2(1).await.unwrap().co2, 500);
+            assert_eq!(
+                sensors.read_co2(4).await.unwrap_err(),
+                MuxError::NoSuchChannel(4)
+            );
+
+            sim.corrupt_next(1);
+            assert!(sensors.read_co2(1).await.is_err());
+        });
+
+        let stats = sensors.stats(1).unwrap();
+        assert_eq!(
+            (stats.reads, stats.errors, stats.consecutive_errors),
+            (2, 1, 1)
+        );
+        assert_eq!(sensors.stats(2).unwrap().errors, 0);
+        // settled after each of the two switches only
+        assert_eq!(sensors.delay.0, 2 * DEFAULT_SETTLE_MS);
+    }
+}
@@ -0,0 +1,231 @@
+//! Protocol level events, install an [`Observer`] using
+//! [`MHZ::with_observer`] to count them into your own metrics.
+//!
+//! ```no_run
+//! # async fn run<Tx, Rx>(tx: Tx, rx: Rx)
+//! # where
+//! #     Tx: embedded_io_async::Write,
+//! #     Rx: embedded_io_async::Read,
+//! # {
+//! use mhzx::observer::EventCounts;
+//! use mhzx::MHZ;
+//!
+//! let mut sensor = MHZ::from_tx_rx(tx, rx).with_observer(EventCounts::default());
+//! let _ = sensor.read_co2().await;
+//! let resyncs = sensor.observer().resyncs;
+//! # }
+//! ```
+
+use crate::Frame;
+#[cfg(doc)]
+use crate::MHZ;
+
+/// Called by [`MHZ`] as frames are exchanged with the sensor. Every method
+/// does nothing by default, implement the ones you need.
+pub trait Observer {
+    /// A command was written to the UART.
+    fn frame_sent(&mut self, _frame: &Frame) {}
+    /// A response was received, before its checksum is verified.
+    fn frame_received(&mut self, _frame: &Frame) {}
+    /// A response passed its checksum.
+    fn frame_accepted(&mut self, _frame: &Frame) {}
+    /// A frame did not match its checksum.
+    fn checksum_failure(&mut self, _frame: &Frame) {}
+    /// Bytes were discarded to find the start of the next frame.
+    fn resync(&mut self) {}
+    /// A complete response was skipped, a newer one followed it or it
+    /// answered another command.
+    fn stale_frame(&mut self) {}
+    /// An operation is attempted again after it failed.
+    fn retry(&mut self) {}
+    /// The sensor did not respond before the deadline.
+    fn timeout(&mut self) {}
+}
+
+/// Ignores every event, the default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoObserver;
+
+impl Observer for NoObserver {}
+
+/// Counts every event. Every [`MHZ`] keeps one for link health, read it
+/// using [`MHZ::stats`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EventCounts {
+    pub frames_sent: u32,
+    pub frames_received: u32,
+    /// Responses received with a valid checksum.
+    pub frames_accepted: u32,
+    pub checksum_failures: u32,
+    pub resyncs: u32,
+    pub stale_frames: u32,
+    pub retries: u32,
+    pub timeouts: u32,
+}
+
+impl Observer for EventCounts {
+    fn frame_sent(&mut self, _frame: &Frame) {
+        self.frames_sent = self.frames_sent.wrapping_add(1);
+    }
+
+    fn frame_received(&mut self, _frame: &Frame) {
+        self.frames_received = self.frames_received.wrapping_add(1);
+    }
+
+    fn frame_accepted(&mut self, _frame: &Frame) {
+        self.frames_accepted = self.frames_accepted.wrapping_add(1);
+    }
+
+    fn checksum_failure(&mut self, _frame: &Frame) {
+        self.checksum_failures = self.checksum_failures.wrapping_add(1);
+    }
+
+    fn resync(&mut self) {
+        self.resyncs = self.resyncs.wrapping_add(1);
+    }
+
+    fn stale_frame(&mut self) {
+        self.stale_frames = self.stale_frames.wrapping_add(1);
+    }
+
+    fn retry(&mut self) {
+        self.retries = self.retries.wrapping_add(1);
+    }
+
+    fn timeout(&mut self) {
+        self.timeouts = self.timeouts.wrapping_add(1);
+    }
+}
+
+impl<O: Observer> Observer for &mut O {
+    fn frame_sent(&mut self, frame: &Frame) {
+        O::frame_sent(self, frame)
+    }
+
+    fn frame_received(&mut self, frame: &Frame) {
+        O::frame_received(self, frame)
+    }
+
+    fn frame_accepted(&mut self, frame: &Frame) {
+        O::frame_accepted(self, frame)
+    }
+
+    fn checksum_failure(&mut self, frame: &Frame) {
+        O::checksum_failure(self, frame)
+    }
+
+    fn resync(&mut self) {
+        O::resync(self)
+    }
+
+    fn stale_frame(&mut self) {
+        O::stale_frame(self)
+    }
+
+    fn retry(&mut self) {
+        O::retry(self)
+    }
+
+    fn timeout(&mut self) {
+        O::timeout(self)
+    }
+}
+
+/// Passes every event to both observers.
+impl<A: Observer, B: Observer> Observer for (A, B) {
+    fn frame_sent(&mut self, frame: &Frame) {
+        self.0.frame_sent(frame);
+        self.1.frame_sent(frame);
+    }
+
+    fn frame_received(&mut self, frame: &Frame) {
+        self.0.frame_received(frame);
+        self.1.frame_received(frame);
+    }
+
+    fn frame_accepted(&mut self, frame: &Frame) {
+        self.0.frame_accepted(frame);
+        self.1.frame_accepted(frame);
+    }
+
+    fn checksum_failure(&mut self, frame: &Frame) {
+        self.0.checksum_failure(frame);
+        self.1.checksum_failure(frame);
+    }
+
+    fn resync(&mut self) {
+        self.0.resync();
+        self.1.resync();
+    }
+
+    fn stale_frame(&mut self) {
+        self.0.stale_frame();
+        self.1.stale_frame();
+    }
+
+    fn retry(&mut self) {
+        self.0.retry();
+        self.1.retry();
+    }
+
+    fn timeout(&mut self) {
+        self.0.timeout();
+        self.1.timeout();
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::model::Range;
+    use crate::sim::{FakeMhz, NoDelay};
+    use futures::executor::block_on;
+
+    #[test]
+    fn counts_events() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = crate::MHZ::from_tx_rx(tx, rx).with_observer(EventCounts::default());
+
+        block_on(sensor.read_co2()).unwrap();
+        sim.with(|s| s.corrupt_responses = 1);
+        let report = block_on(sensor.health_check(3, Range::Ppm5000));
+        assert!(report.is_healthy());
+
+        let counts = *sensor.observer();
+        assert_eq!(counts.frames_sent, 3);
+        assert_eq!(counts.frames_received, 3);
+        assert_eq!(counts.checksum_failures, 1);
+        assert_eq!(counts.retries, 1);
+    }
+
+    #[test]
+    fn link_stats() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = crate::MHZ::from_tx_rx(tx, rx);
+
+        block_on(sensor.read_co2()).unwrap();
+        let outdated = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97];
+        sim.prefix_next_response(&outdated);
+        block_on(sensor.read_co2()).unwrap();
+        sim.with(|s| s.corrupt_responses = 1);
+        let report = block_on(sensor.health_check(3, Range::Ppm5000));
+        assert!(report.is_healthy());
+        let err = block_on(sensor.read_co2_before(0, &|| 1, &mut NoDelay));
+        assert_eq!(err.unwrap_err(), crate::Error::Timeout);
+
+        let stats = sensor.stats();
+        assert_eq!(stats.frames_accepted, 3);
+        assert_eq!(stats.checksum_failures, 1);
+        assert_eq!(stats.stale_frames, 1);
+        assert_eq!(stats.resyncs, 1);
+        assert_eq!(stats.retries, 1);
+        assert_eq!(stats.timeouts, 1);
+
+        sensor.reset_stats();
+        assert_eq!(sensor.stats(), EventCounts::default());
+    }
+}
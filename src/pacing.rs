@@ -0,0 +1,147 @@
+//! Spacing between commands. The sensor misbehaves when polled faster then
+//! about every [`MIN_COMMAND_INTERVAL_MS`], it then answers with garbage or
+//! a stale response. [`MHZ::with_pacing`] makes the driver wait instead.
+//!
+//! ```no_run
+//! # fn run<Tx, Rx, D>(tx: Tx, rx: Rx, delay: D, uptime_ms: fn() -> u64)
+//! # where
+//! #     Tx: embedded_io_async::Write,
+//! #     Rx: embedded_io_async::Read,
+//! #     D: embedded_hal_async::delay::DelayNs,
+//! # {
+//! use mhzx::pacing::Paced;
+//! use mhzx::{MHZ, MIN_COMMAND_INTERVAL_MS};
+//!
+//! let sensor = MHZ::from_tx_rx(tx, rx)
+//!     .with_pacing(Paced::new(delay, uptime_ms, MIN_COMMAND_INTERVAL_MS));
+//! # }
+//! ```
+
+use embedded_hal_async::delay::DelayNs;
+
+use crate::{Clock, IntoMillis};
+#[cfg(doc)]
+use crate::{MHZ, MIN_COMMAND_INTERVAL_MS};
+
+/// Called by [`MHZ`] before sending every command.
+#[allow(async_fn_in_trait)]
+pub trait Pacing {
+    /// Return once the next command may be send.
+    async fn before_command(&mut self);
+
+    /// The command just send needs the sensor to settle for `ms` before
+    /// the next one, see [`Timing`](crate::timing::Timing). Ignored by
+    /// default.
+    fn settle(&mut self, ms: u32) {
+        let _ = ms;
+    }
+}
+
+/// Send commands as soon as they are issued, the default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoPacing;
+
+impl Pacing for NoPacing {
+    async fn before_command(&mut self) {}
+}
+
+/// Waits until at least `interval` passed since the previous command, and
+/// for the sensor to settle after calibration and configuration commands.
+pub struct Paced<D, C> {
+    delay: D,
+    clock: C,
+    interval_ms: u32,
+    last_command_ms: Option<u64>,
+    settled_at_ms: u64,
+}
+
+impl<D: DelayNs, C: Clock> Paced<D, C> {
+    pub fn new(delay: D, clock: C, interval: impl IntoMillis) -> Self {
+        Paced {
+            delay,
+            clock,
+            interval_ms: interval.into_millis(),
+            last_command_ms: None,
+            settled_at_ms: 0,
+        }
+    }
+
+    /// Give back the delay and clock.
+    pub fn release(self) -> (D, C) {
+        (self.delay, self.clock)
+    }
+}
+
+impl<D: DelayNs, C: Clock> Pacing for Paced<D, C> {
+    async fn before_command(&mut self) {
+        let now = self.clock.now_ms();
+        let mut ready_at = self.settled_at_ms;
+        if let Some(last) = self.last_command_ms {
+            ready_at = ready_at.max(last + u64::from(self.interval_ms));
+        }
+        if let Some(remaining) = ready_at.checked_sub(now).filter(|ms| *ms > 0) {
+            trace!("waiting {}ms before next command", remaining);
+            let remaining = remaining.min(u64::from(u32::MAX)) as u32;
+            self.delay.delay_ms(remaining).await;
+        }
+        self.last_command_ms = Some(self.clock.now_ms());
+    }
+
+    fn settle(&mut self, ms: u32) {
+        let settled_at = self.clock.now_ms() + u64::from(ms);
+        self.settled_at_ms = self.settled_at_ms.max(settled_at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use futures::executor::block_on;
+
+    struct FakeDelay<'a>(&'a Cell<u64>);
+
+    impl DelayNs for FakeDelay<'_> {
+        async fn delay_ns(&mut self, ns: u32) {
+            self.0.set(self.0.get() + u64::from(ns) / 1_000_000);
+        }
+    }
+
+    #[test]
+    fn waits_remaining_interval() {
+        let now = Cell::new(1000);
+        let interval = core::time::Duration::from_millis(100);
+        let mut paced = Paced::new(FakeDelay(&now), || now.get(), interval);
+
+        block_on(async {
+            paced.before_command().await;
+            assert_eq!(now.get(), 1000, "first command is not delayed");
+
+            now.set(1030);
+            paced.before_command().await;
+            assert_eq!(now.get(), 1100);
+
+            now.set(1500);
+            paced.before_command().await;
+            assert_eq!(now.get(), 1500);
+        });
+    }
+
+    #[test]
+    fn waits_for_settle() {
+        let now = Cell::new(1000);
+        let mut paced = Paced::new(FakeDelay(&now), || now.get(), 100);
+
+        block_on(async {
+            paced.before_command().await;
+            paced.settle(1000);
+            now.set(1200);
+            paced.before_command().await;
+            assert_eq!(now.get(), 2000);
+
+            paced.settle(50);
+            paced.before_command().await;
+            assert_eq!(now.get(), 2100, "the interval is longer");
+        });
+    }
+}
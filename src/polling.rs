@@ -0,0 +1,256 @@
+//! A non-blocking state machine for executor-less main loops and RTIC v1
+//! tasks, built on the [`embedded_hal_nb::serial`] traits.
+//!
+//! ```no_run
+//! # fn run<Tx, Rx>(tx: Tx, rx: Rx)
+//! # where
+//! #     Tx: embedded_hal_nb::serial::Write,
+//! #     Rx: embedded_hal_nb::serial::Read,
+//! # {
+//! use embedded_hal_nb::nb;
+//! use mhzx::polling::PollingMhz;
+//!
+//! let mut sensor = PollingMhz::new(tx, rx);
+//! sensor.start_read();
+//! loop {
+//!     match sensor.poll() {
+//!         Ok(measurement) => {
+//!             let _ = measurement.co2;
+//!             sensor.start_read();
+//!         }
+//!         Err(nb::Error::WouldBlock) => (), // do other work
+//!         Err(nb::Error::Other(_)) => sensor.start_read(),
+//!     }
+//! }
+//! # }
+//! ```
+
+use core::marker::PhantomData;
+
+use embedded_hal_nb::nb;
+use embedded_hal_nb::serial::{Read, Write};
+
+use crate::model::{Mhz19B, Model};
+use crate::{Command, Deframer, Error, Measurement, DEFAULT_RESYNC_LIMIT, PAYLOAD_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Draining,
+    Sending { sent: usize },
+    Flushing,
+    Receiving { discarded: usize },
+}
+
+/// Sensor interface that never blocks, see the [module docs](self).
+pub struct PollingMhz<Tx, Rx, M = Mhz19B> {
+    uart_tx: Tx,
+    uart_rx: Rx,
+    state: State,
+    deframer: Deframer,
+    resync_limit: usize,
+    model: PhantomData<M>,
+}
+
+impl<Tx, Rx> PollingMhz<Tx, Rx>
+where
+    Tx: Write,
+    Rx: Read,
+{
+    /// See [`MHZ::from_tx_rx`](crate::MHZ::from_tx_rx) for the required
+    /// UART settings.
+    pub fn new(uart_tx: Tx, uart_rx: Rx) -> Self {
+        PollingMhz::with_model(uart_tx, uart_rx, Mhz19B)
+    }
+}
+
+impl<Tx, Rx, M> PollingMhz<Tx, Rx, M>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+{
+    /// Like [`new`](PollingMhz::new) but for a specific [`Model`].
+    pub fn with_model(uart_tx: Tx, uart_rx: Rx, _model: M) -> Self {
+        PollingMhz {
+            uart_tx,
+            uart_rx,
+            state: State::Idle,
+            deframer: Deframer::new(),
+            resync_limit: DEFAULT_RESYNC_LIMIT,
+            model: PhantomData,
+        }
+    }
+
+    /// Give up with [`Error::Desynchronized`] after discarding more then
+    /// `bytes` while searching for a response. Defaults to
+    /// [`DEFAULT_RESYNC_LIMIT`].
+    pub fn set_resync_limit(&mut self, bytes: usize) {
+        self.resync_limit = bytes;
+    }
+
+    /// Begin reading the CO2 concentration, aborts a read in progress.
+    /// Drive it to completion using [`poll`](PollingMhz::poll).
+    pub fn start_read(&mut self) {
+        self.deframer.reset();
+        self.state = State::Draining;
+    }
+
+    /// True if no read is in progress.
+    pub fn is_idle(&self) -> bool {
+        self.state == State::Idle
+    }
+
+    /// Make as much progress as possible without blocking. Returns
+    /// [`nb::Error::WouldBlock`] until the measurement is available, also
+    /// when no read has been started. After an error the read is aborted.
+    pub fn poll(&mut self) -> nb::Result<Measurement, Error<Tx::Error, Rx::Error>> {
+        let res = self.step();
+        if !matches!(res, Err(nb::Error::WouldBlock)) {
+            self.state = State::Idle;
+        }
+        res
+    }
+
+    fn step(&mut self) -> nb::Result<Measurement, Error<Tx::Error, Rx::Error>> {
+        loop {
+            match self.state {
+                State::Idle => return Err(nb::Error::WouldBlock),
+                State::Draining => match self.uart_rx.read() {
+                    Ok(_) => trace!("discarded stale byte"),
+                    Err(nb::Error::WouldBlock) => self.state = State::Sending { sent: 0 },
+                    Err(nb::Error::Other(e)) => return Err(nb::Error::Other(Error::Reading(e))),
+                },
+                State::Sending { sent } if sent == PAYLOAD_SIZE => self.state = State::Flushing,
+                State::Sending { sent } => {
+                    self.uart_tx
+                        .write(Command::ReadCo2.to_frame().as_bytes()[sent])
+                        .map_err(|e| e.map(Error::WritingToUart))?;
+                    self.state = State::Sending { sent: sent + 1 };
+                }
+                State::Flushing => {
+                    self.uart_tx
+                        .flush()
+                        .map_err(|e| e.map(Error::FlushingUart))?;
+                    trace_frame!(Tx, Command::ReadCo2.to_frame().as_bytes());
+                    self.state = State::Receiving { discarded: 0 };
+                }
+                State::Receiving { discarded } => {
+                    let byte = self.uart_rx.read().map_err(|e| e.map(Error::Reading))?;
+                    match self.deframer.push(byte) {
+                        Some(frame) if frame.command() == Command::ReadCo2.code() => {
+                            trace_frame!(Rx, frame.as_bytes());
+                            return Measurement::parse_response(frame.into_bytes())
+                                .map_err(nb::Error::Other);
+                        }
+                        Some(_) => trace!("skipping response to another command"),
+                        None => (),
+                    }
+                    let discarded = discarded.saturating_add(1);
+                    if discarded > self.resync_limit.saturating_add(PAYLOAD_SIZE) {
+                        return Err(nb::Error::Other(Error::Desynchronized {
+                            discarded: discarded - PAYLOAD_SIZE,
+                        }));
+                    }
+                    self.state = State::Receiving { discarded };
+                }
+            }
+        }
+    }
+
+    /// Give back the UART halves.
+    pub fn release(self) -> (Tx, Rx) {
+        (self.uart_tx, self.uart_rx)
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal_nb::serial::ErrorType;
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    const RESPONSE: [u8; 9] = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0, 0, 0, 0x97];
+
+    #[derive(Default)]
+    struct Tx {
+        written: Vec<u8>,
+        busy: bool,
+    }
+
+    impl ErrorType for Tx {
+        type Error = Infallible;
+    }
+
+    impl Write for Tx {
+        fn write(&mut self, word: u8) -> nb::Result<(), Infallible> {
+            // accept every other byte to exercise WouldBlock
+            self.busy = !self.busy;
+            if self.busy {
+                return Err(nb::Error::WouldBlock);
+            }
+            self.written.push(word);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> nb::Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Rx(VecDeque<u8>);
+
+    impl ErrorType for Rx {
+        type Error = Infallible;
+    }
+
+    impl Read for Rx {
+        fn read(&mut self) -> nb::Result<u8, Infallible> {
+            self.0.pop_front().ok_or(nb::Error::WouldBlock)
+        }
+    }
+
+    #[test]
+    fn drives_read_to_completion() {
+        let mut rx = Rx::default();
+        rx.0.extend([0x12, 0x34]);
+        let mut sensor = PollingMhz::new(Tx::default(), rx);
+
+        assert_eq!(sensor.poll().unwrap_err(), nb::Error::WouldBlock);
+        sensor.start_read();
+        while sensor.uart_tx.written.len() < PAYLOAD_SIZE {
+            assert_eq!(sensor.poll().unwrap_err(), nb::Error::WouldBlock);
+        }
+        assert_eq!(
+            sensor.uart_tx.written,
+            Command::ReadCo2.to_frame().as_bytes()
+        );
+        assert!(sensor.uart_rx.0.is_empty(), "stale bytes are drained");
+
+        sensor.uart_rx.0.extend(&RESPONSE[..4]);
+        assert_eq!(sensor.poll().unwrap_err(), nb::Error::WouldBlock);
+        sensor.uart_rx.0.extend(&RESPONSE[4..]);
+        let measurement = sensor.poll().unwrap();
+        assert_eq!(measurement.co2, 420);
+        assert!(sensor.is_idle());
+    }
+
+    #[test]
+    fn gives_up_on_garbage() {
+        let mut sensor = PollingMhz::new(Tx::default(), Rx::default());
+        sensor.set_resync_limit(4);
+        sensor.start_read();
+        while sensor.uart_tx.written.len() < PAYLOAD_SIZE {
+            let _ = sensor.poll();
+        }
+        sensor.uart_rx.0.extend([0u8; 20]);
+        assert_eq!(
+            sensor.poll().unwrap_err(),
+            nb::Error::Other(Error::Desynchronized { discarded: 5 })
+        );
+        assert!(sensor.is_idle());
+    }
+}
@@ -0,0 +1,213 @@
+//! Power cycle a sensor that stopped responding, as a last resort.
+//!
+//! The sensor is powered through a switch, for example a MOSFET, controlled
+//! by an enable pin. After [`DEFAULT_OFFLINE_AFTER`] failed reads in a row it
+//! is switched off for [`POWER_OFF_MS`] and then given the
+//! [warm-up time](crate::model::Model::WARMUP_MS) of its model.
+//!
+//! ```no_run
+//! # async fn run<Tx, Rx, P, D>(tx: Tx, rx: Rx, enable: P, delay: D)
+//! # where
+//! #     Tx: embedded_io_async::Write,
+//! #     Rx: embedded_io_async::Read,
+//! #     P: embedded_hal::digital::OutputPin,
+//! #     D: embedded_hal_async::delay::DelayNs,
+//! # {
+//! use mhzx::power::PowerError;
+//! use mhzx::MHZ;
+//!
+//! let mut sensor = MHZ::from_tx_rx(tx, rx)
+//!     .with_power_pin(enable, delay)
+//!     .offline_after(3);
+//! match sensor.read_co2().await {
+//!     Ok(measurement) => (),
+//!     Err(PowerError::PowerCycled(_)) => (), // sensor is back up
+//!     Err(_) => (),
+//! }
+//! # }
+//! ```
+
+use core::fmt;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+
+use crate::model::Model;
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::watchdog::{Supervised, Watchdog};
+use crate::{Error, Measurement, MHZ};
+
+/// Failed reads in a row after which the sensor is power cycled.
+pub const DEFAULT_OFFLINE_AFTER: u32 = 5;
+
+/// Time the sensor is kept switched off while power cycling.
+pub const POWER_OFF_MS: u32 = 1000;
+
+/// Error reading a sensor with a power pin.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PowerError<PinError, TxError, RxError> {
+    /// Switching the power failed.
+    Pin(PinError),
+    Sensor(Error<TxError, RxError>),
+    /// The read failed and the sensor was power cycled and warmed up again.
+    PowerCycled(Error<TxError, RxError>),
+}
+
+impl<PinError, TxError, RxError> fmt::Display for PowerError<PinError, TxError, RxError>
+where
+    PinError: fmt::Debug,
+    TxError: fmt::Display,
+    RxError: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PowerError::Pin(e) => write!(f, "Could not switch sensor power: {e:?}"),
+            PowerError::Sensor(e) => write!(f, "{e}"),
+            PowerError::PowerCycled(e) => write!(f, "{e}, power cycled the sensor"),
+        }
+    }
+}
+
+/// A sensor with a power enable pin, see the [module docs](self).
+pub struct PoweredMhz<Tx, Rx, M, const RX_BUF: usize, P, O, Pin, D> {
+    sensor: MHZ<Tx, Rx, M, RX_BUF, P, O>,
+    enable: Pin,
+    delay: D,
+    watchdog: Watchdog,
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Power cycle the sensor through `enable`, which switches the sensor
+    /// on when high, once it stops responding.
+    pub fn with_power_pin<Pin: OutputPin, D: DelayNs>(
+        self,
+        enable: Pin,
+        delay: D,
+    ) -> PoweredMhz<Tx, Rx, M, RX_BUF, P, O, Pin, D> {
+        PoweredMhz {
+            sensor: self,
+            enable,
+            delay,
+            watchdog: Watchdog::new(DEFAULT_OFFLINE_AFTER),
+        }
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O, Pin, D> PoweredMhz<Tx, Rx, M, RX_BUF, P, O, Pin, D>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+    Pin: OutputPin,
+    D: DelayNs,
+{
+    /// Power cycle after `failures` failed reads in a row. Defaults to
+    /// [`DEFAULT_OFFLINE_AFTER`].
+    pub fn offline_after(mut self, failures: u32) -> Self {
+        self.watchdog = Watchdog::new(failures);
+        self
+    }
+
+    /// Like [`MHZ::read_co2`], power cycles the sensor if this was the last
+    /// allowed failure in a row.
+    pub async fn read_co2(
+        &mut self,
+    ) -> Result<Measurement, PowerError<Pin::Error, Tx::Error, Rx::Error>> {
+        let result = self.sensor.read_co2().await;
+        match self.watchdog.check(result) {
+            Ok(measurement) => Ok(measurement),
+            Err(Supervised::Failed(e)) => Err(PowerError::Sensor(e)),
+            Err(Supervised::SensorOffline { last_error, .. }) => {
+                self.power_cycle().await?;
+                Err(PowerError::PowerCycled(last_error))
+            }
+        }
+    }
+
+    /// Switch the sensor off for [`POWER_OFF_MS`], then on and wait for
+    /// it to warm up.
+    pub async fn power_cycle(
+        &mut self,
+    ) -> Result<(), PowerError<Pin::Error, Tx::Error, Rx::Error>> {
+        debug!("power cycling sensor");
+        self.enable.set_low().map_err(PowerError::Pin)?;
+        self.delay.delay_ms(POWER_OFF_MS).await;
+        self.enable.set_high().map_err(PowerError::Pin)?;
+        self.delay.delay_ms(M::WARMUP_MS).await;
+        self.watchdog.reset();
+        Ok(())
+    }
+
+    /// Access the sensor for other commands.
+    pub fn sensor(&mut self) -> &mut MHZ<Tx, Rx, M, RX_BUF, P, O> {
+        &mut self.sensor
+    }
+
+    /// Give back the sensor, pin and delay.
+    pub fn release(self) -> (MHZ<Tx, Rx, M, RX_BUF, P, O>, Pin, D) {
+        (self.sensor, self.enable, self.delay)
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::model::Mhz19B;
+    use crate::sim::{CountingDelay, FakeMhz};
+    use core::convert::Infallible;
+    use embedded_hal::digital::ErrorType;
+    use futures::executor::block_on;
+
+    /// Power cycling stops the simulated sensor from sending garbage.
+    struct Enable<'a>(&'a FakeMhz);
+
+    impl ErrorType for Enable<'_> {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Enable<'_> {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.0.corrupt_next(0);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn power_cycles_when_offline() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx)
+            .with_power_pin(Enable(&sim), CountingDelay::default())
+            .offline_after(2);
+
+        sim.corrupt_next(10);
+        block_on(async {
+            assert!(matches!(
+                sensor.read_co2().await,
+                Err(PowerError::Sensor(_))
+            ));
+            assert!(matches!(
+                sensor.read_co2().await,
+                Err(PowerError::PowerCycled(_))
+            ));
+            assert_eq!(sensor.read_co2().await.unwrap().co2, 400);
+        });
+        assert_eq!(sensor.delay.0, POWER_OFF_MS + Mhz19B::WARMUP_MS);
+    }
+}
@@ -0,0 +1,105 @@
+use core::fmt;
+
+use embedded_io_async::{Read, Write};
+
+use crate::model::{reports_firmware_version, Model};
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::warmup::WARMUP_VALUES;
+use crate::{Command, Error, FirmwareVersion, Measurement, MHZ};
+
+/// A sensor found by [`MHZ::probe`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy)]
+pub struct SensorInfo {
+    /// [`Model::NAME`] of the model the driver was configured for. It is
+    /// not detected, the sensors do not report their model so a
+    /// misconfigured driver reports the wrong one here.
+    pub configured_model: &'static str,
+    /// None for models that do not report it.
+    pub firmware: Option<FirmwareVersion>,
+    pub measurement: Measurement,
+    /// The reading is one of the placeholder values reported during
+    /// warm-up. A warmed up sensor can report these too, use a
+    /// [`WarmupDetector`](crate::warmup::WarmupDetector) to be sure.
+    pub maybe_warming_up: bool,
+}
+
+/// Why [`MHZ::probe`] found no sensor.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeError<TxError, RxError> {
+    /// Nothing answered, the sensor is probably not connected or not
+    /// powered.
+    NoResponse(Error<TxError, RxError>),
+    /// Something answered but not with valid responses. Check the wiring
+    /// and the UART settings.
+    Garbled(Error<TxError, RxError>),
+    /// The UART itself failed.
+    Uart(Error<TxError, RxError>),
+}
+
+impl<TxError, RxError> fmt::Display for ProbeError<TxError, RxError>
+where
+    TxError: fmt::Display,
+    RxError: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeError::NoResponse(e) => write!(f, "No sensor found: {e}"),
+            ProbeError::Garbled(e) => write!(f, "Sensor responses are garbled: {e}"),
+            ProbeError::Uart(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<TxError, RxError> From<Error<TxError, RxError>> for ProbeError<TxError, RxError> {
+    fn from(e: Error<TxError, RxError>) -> Self {
+        match e {
+            Error::ReadingEOF | Error::Desynchronized { .. } | Error::Timeout => {
+                ProbeError::NoResponse(e)
+            }
+            Error::InvalidChecksum { .. }
+            | Error::WrongStartByte(_)
+            | Error::UnexpectedCommand { .. }
+            | Error::ConfigNotApplied
+            | Error::Implausible(_) => ProbeError::Garbled(e),
+            Error::WritingToUart(_) | Error::FlushingUart(_) | Error::Reading(_) => {
+                ProbeError::Uart(e)
+            }
+        }
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Check a sensor is connected by reading it and, if the model
+    /// supports it, querying the firmware version.
+    ///
+    /// Like every other read this waits for the sensor indefinitely if the
+    /// UART never returns, wrap it in a timeout.
+    pub async fn probe(&mut self) -> Result<SensorInfo, ProbeError<Tx::Error, Rx::Error>> {
+        let measurement = self.read_co2().await?;
+        let firmware = if reports_firmware_version::<M>() {
+            let package = self
+                .transact(&Command::ReadFirmwareVersion.to_frame())
+                .await?;
+            Some(FirmwareVersion::parse_response(package)?)
+        } else {
+            None
+        };
+
+        Ok(SensorInfo {
+            configured_model: M::NAME,
+            firmware,
+            measurement,
+            maybe_warming_up: WARMUP_VALUES.contains(&measurement.co2.get()),
+        })
+    }
+}
@@ -0,0 +1,273 @@
+//! Sans-IO implementation of the request/response protocol.
+//!
+//! [`Protocol`] owns no UART, you move the bytes yourself. This makes it
+//! possible to drive the sensor from a DMA ring buffer, an interrupt handler
+//! or a test. It is the same state machine [`MHZ`](crate::MHZ) drives:
+//! stray bytes and corrupt frames are resynced past, responses to other
+//! commands are skipped and it gives up after the resync limit.
+//!
+//! ```
+//! use mhzx::protocol::{Event, Protocol};
+//! use mhzx::Command;
+//!
+//! let mut protocol = Protocol::new();
+//! protocol.request(Command::ReadCo2);
+//! let frame = protocol.next_frame_to_send().unwrap();
+//! // write `frame` to the sensor, then feed back whatever it sends
+//! let response = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97];
+//! match protocol.push_bytes(&response) {
+//!     Some(Event::Measurement(m)) => assert_eq!(m.co2, 420),
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use core::convert::Infallible;
+
+use crate::measurement::{self, FirmwareVersion, Measurement, RawMeasurement};
+use crate::observer::{NoObserver, Observer};
+use crate::read_package::{check_discarded, Framer};
+use crate::{Command, Error, Frame, DEFAULT_RESYNC_LIMIT, PAYLOAD_SIZE};
+
+/// Outcome of a request.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug)]
+pub enum Event {
+    /// Response to [`Command::ReadCo2`].
+    Measurement(Measurement),
+    /// Response to [`Command::ReadRawCo2`].
+    RawMeasurement(RawMeasurement),
+    /// Response to [`Command::ReadFirmwareVersion`].
+    FirmwareVersion(FirmwareVersion),
+    /// Response to any other command, the checksum is valid.
+    Response(Frame),
+    /// The response was corrupt or none was found within the resync limit.
+    Error(Error<Infallible, Infallible>),
+}
+
+/// Request/response state machine.
+#[derive(Debug)]
+pub struct Protocol {
+    queued: Option<Frame>,
+    /// Command byte of the response we wait for.
+    awaiting: Option<u8>,
+    framer: Framer,
+    received: usize,
+    resync_limit: usize,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Protocol::new()
+    }
+}
+
+impl Protocol {
+    pub const fn new() -> Self {
+        Protocol {
+            queued: None,
+            awaiting: None,
+            framer: Framer::new(),
+            received: 0,
+            resync_limit: DEFAULT_RESYNC_LIMIT,
+        }
+    }
+
+    /// Already waiting for the response to `expected_command`, for reading
+    /// a response to a command someone else send.
+    pub(crate) fn awaiting(expected_command: u8, resync_limit: usize) -> Self {
+        Protocol {
+            awaiting: Some(expected_command),
+            resync_limit,
+            ..Protocol::new()
+        }
+    }
+
+    /// Give up with [`Error::Desynchronized`] after discarding more then
+    /// `bytes` while searching for a response. Defaults to
+    /// [`DEFAULT_RESYNC_LIMIT`].
+    pub fn set_resync_limit(&mut self, bytes: usize) {
+        self.resync_limit = bytes;
+    }
+
+    /// Queue a command. Abandons any request still waiting for a response.
+    /// Commands the sensor does not answer, such as
+    /// [`Command::SetAbc`], leave the protocol idle once send.
+    pub fn request(&mut self, command: Command) {
+        self.request_frame(command.to_frame());
+    }
+
+    /// Like [`request`](Protocol::request) for any frame, the response is
+    /// expected to repeat its command byte.
+    pub(crate) fn request_frame(&mut self, frame: Frame) {
+        self.queued = Some(frame);
+        self.awaiting = None;
+    }
+
+    /// The frame that should be written to the sensor next, if any. From
+    /// the moment this returns a frame the protocol waits for the response.
+    pub fn next_frame_to_send(&mut self) -> Option<Frame> {
+        let frame = self.queued.take()?;
+        let command = frame.command();
+        let answered = Command::from_frame(&frame).is_none_or(expects_response);
+        self.awaiting = answered.then_some(command);
+        self.framer = Framer::new();
+        self.received = 0;
+        Some(frame)
+    }
+
+    /// True if no request is queued or waiting for a response.
+    pub fn is_idle(&self) -> bool {
+        self.queued.is_none() && self.awaiting.is_none()
+    }
+
+    /// Feed the bytes of one read from the sensor. Returns an event once the
+    /// response to the current request is complete or can not be found.
+    ///
+    /// Like the driver a response is only accepted if it ends the bytes
+    /// pushed in one call, when more bytes follow it is outdated. Bytes
+    /// received while no response is expected are discarded.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Option<Event> {
+        let res = self.receive(bytes, &mut NoObserver)?;
+        Some(match res {
+            Ok(package) => decode(package),
+            Err(e) => Event::Error(e),
+        })
+    }
+
+    /// Feed the bytes of one read, returns the package once the response
+    /// is found. Its checksum is not verified.
+    pub(crate) fn receive<TxError, RxError>(
+        &mut self,
+        bytes: &[u8],
+        observer: &mut impl Observer,
+    ) -> Option<Result<[u8; PAYLOAD_SIZE], Error<TxError, RxError>>> {
+        let expected_command = self.awaiting?;
+        self.received = self.received.saturating_add(bytes.len());
+        if let Err(e) = check_discarded(self.received, self.resync_limit) {
+            self.awaiting = None;
+            return Some(Err(e));
+        }
+
+        self.framer.push(bytes, observer);
+        let package = self.framer.end_of_read(expected_command, observer)?;
+        self.awaiting = None;
+        Some(Ok(package))
+    }
+}
+
+fn expects_response(command: Command) -> bool {
+    matches!(
+        command,
+        Command::ReadCo2
+            | Command::ReadRawCo2
+            | Command::ReadFirmwareVersion
+            | Command::ReadAbc
+            | Command::ReadRange
+    )
+}
+
+fn decode(package: [u8; PAYLOAD_SIZE]) -> Event {
+    if let Err(e) = measurement::verify_checksum(&package) {
+        return Event::Error(e);
+    }
+    let [_, command, ..] = package;
+    let res = match command {
+        0x86 => Measurement::parse_response(package).map(Event::Measurement),
+        0x85 => RawMeasurement::parse_response(package).map(Event::RawMeasurement),
+        0xA0 => FirmwareVersion::parse_response(package).map(Event::FirmwareVersion),
+        _ => Ok(Event::Response(Frame::from_bytes(package))),
+    };
+    res.unwrap_or_else(Event::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CO2_RESPONSE: [u8; 9] = [0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97];
+    const RAW_RESPONSE: [u8; 9] = [0xFF, 0x85, 0x01, 0x02, 0x05, 0x14, 0x00, 0x07, 0x58];
+
+    fn sent(protocol: &mut Protocol, command: Command) {
+        protocol.request(command);
+        assert_eq!(protocol.next_frame_to_send(), Some(command.to_frame()));
+    }
+
+    #[test]
+    fn full_transaction() {
+        let mut protocol = Protocol::new();
+        assert!(protocol.next_frame_to_send().is_none());
+
+        sent(&mut protocol, Command::ReadCo2);
+        assert!(protocol.next_frame_to_send().is_none());
+
+        assert!(protocol.push_bytes(&[0x00, 0x12]).is_none());
+        assert!(protocol.push_bytes(&CO2_RESPONSE[..4]).is_none());
+        let Some(Event::Measurement(m)) = protocol.push_bytes(&CO2_RESPONSE[4..]) else {
+            panic!("expected a measurement");
+        };
+        assert_eq!(m.co2, 420);
+        assert!(protocol.is_idle());
+    }
+
+    #[test]
+    fn ignores_bytes_without_request() {
+        let mut protocol = Protocol::new();
+        assert!(protocol.push_bytes(&CO2_RESPONSE).is_none());
+
+        sent(&mut protocol, Command::SetAbc(false));
+        assert!(protocol.is_idle());
+        assert!(protocol.push_bytes(&CO2_RESPONSE).is_none());
+    }
+
+    #[test]
+    fn resyncs_like_the_driver() {
+        let mut protocol = Protocol::new();
+
+        // a stray start byte before the response
+        sent(&mut protocol, Command::ReadCo2);
+        assert!(protocol.push_bytes(&[0xFF]).is_none());
+        assert!(matches!(
+            protocol.push_bytes(&CO2_RESPONSE),
+            Some(Event::Measurement(m)) if m.co2 == 420
+        ));
+
+        // a late response to another command is skipped
+        sent(&mut protocol, Command::ReadCo2);
+        assert!(protocol.push_bytes(&RAW_RESPONSE).is_none());
+        assert!(matches!(
+            protocol.push_bytes(&CO2_RESPONSE),
+            Some(Event::Measurement(_))
+        ));
+
+        sent(&mut protocol, Command::ReadRawCo2);
+        assert!(matches!(
+            protocol.push_bytes(&RAW_RESPONSE),
+            Some(Event::RawMeasurement(m)) if m.co2 == 1300
+        ));
+    }
+
+    #[test]
+    fn gives_up() {
+        let mut protocol = Protocol::new();
+        protocol.set_resync_limit(16);
+        sent(&mut protocol, Command::ReadCo2);
+        assert!(protocol.push_bytes(&[0x00; 20]).is_none());
+        assert!(matches!(
+            protocol.push_bytes(&[0x00; 20]),
+            Some(Event::Error(Error::Desynchronized { discarded: 31 }))
+        ));
+        assert!(protocol.is_idle());
+    }
+
+    #[test]
+    fn corrupt_response() {
+        let mut protocol = Protocol::new();
+        sent(&mut protocol, Command::ReadCo2);
+        let mut corrupt = CO2_RESPONSE;
+        corrupt[3] = 0;
+        assert!(matches!(
+            protocol.push_bytes(&corrupt),
+            Some(Event::Error(Error::InvalidChecksum { .. }))
+        ));
+    }
+}
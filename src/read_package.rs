@@ -1,81 +1,269 @@
-use core::cmp::Ordering;
-use defmt::debug;
-use embedded_io_async::{Read, Write};
-use heapless::Vec;
+use core::convert::Infallible;
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use embedded_io_async::{BufRead, ErrorType, Read};
 
-use crate::{Error, PAYLOAD_SIZE};
+use crate::measurement::checksum_valid;
+use crate::observer::{NoObserver, Observer};
+use crate::protocol::Protocol;
+use crate::{Error, Frame, PAYLOAD_SIZE};
 
 /// reads a whole package, if the start of a next package is already
-/// available skip the just read package and finish reading that instead
+/// available skip the just read package and finish reading that instead.
+/// Packages that are not a response to `expected_command` are skipped.
+/// Gives up with [`Error::Desynchronized`] once more then `max_discarded`
+/// bytes have been thrown away.
 ///
-// todo needs unit testing
-pub async fn read_package<Tx, Rx>(
+/// `N` is the size of the scratch buffer each read goes into. Only reads,
+/// `TxError` merely fills in the error type of the caller.
+pub async fn read_package<TxError, Rx, const N: usize>(
     rx: &mut Rx,
-) -> Result<[u8; PAYLOAD_SIZE], Error<Tx::Error, Rx::Error>>
+    expected_command: u8,
+    max_discarded: usize,
+) -> Result<[u8; PAYLOAD_SIZE], Error<TxError, Rx::Error>>
 where
-    Tx: Write,
-    Tx::Error: defmt::Format,
     Rx: Read,
-    Rx::Error: defmt::Format,
 {
-    let mut buf = [0u8; 5 * PAYLOAD_SIZE];
-    let mut package: Vec<u8, PAYLOAD_SIZE> = Vec::new();
-    let mut needed = PAYLOAD_SIZE - package.len();
+    let mut protocol = Protocol::awaiting(expected_command, max_discarded);
+    read_response::<TxError, Rx, N>(rx, &mut protocol, &mut NoObserver).await
+}
+
+/// Feed reads from `rx` to `protocol` until it finds the response it waits
+/// for, reporting resyncs to `observer`.
+pub(crate) async fn read_response<TxError, Rx, const N: usize>(
+    rx: &mut Rx,
+    protocol: &mut Protocol,
+    observer: &mut impl Observer,
+) -> Result<[u8; PAYLOAD_SIZE], Error<TxError, Rx::Error>>
+where
+    Rx: Read,
+{
+    const { assert!(N > 0, "the receive buffer can not be empty") };
+    let mut buf = [0u8; N];
 
     loop {
         let n = rx.read(&mut buf).await.map_err(Error::Reading)?;
         if n == 0 {
             return Err(Error::ReadingEOF);
         }
+        // a reader claiming more then fits is buggy, do not panic over it
+        let read = buf.get(..n).unwrap_or(&buf);
+        if let Some(res) = protocol.receive(read, observer) {
+            return res;
+        }
+    }
+}
 
-        let package_start = buf.iter().rev().skip_while(|byte| **byte != 0xff).count();
-        let offset = if package_start == 0 {
-            continue;
-        } else {
-            package_start - 1
-        };
+/// Like [`read_response`] but parses straight from the buffer of `rx`,
+/// without copying into a scratch buffer first. Each
+/// [`fill_buf`](BufRead::fill_buf) counts as one read.
+pub(crate) async fn read_response_buffered<TxError, Rx>(
+    rx: &mut Rx,
+    protocol: &mut Protocol,
+    observer: &mut impl Observer,
+) -> Result<[u8; PAYLOAD_SIZE], Error<TxError, Rx::Error>>
+where
+    Rx: BufRead,
+{
+    loop {
+        let buf = rx.fill_buf().await.map_err(Error::Reading)?;
+        let n = buf.len();
+        if n == 0 {
+            return Err(Error::ReadingEOF);
+        }
+        let res = protocol.receive(buf, observer);
+        rx.consume(n);
+        if let Some(res) = res {
+            return res;
+        }
+    }
+}
 
-        // this we know contains a body
-        let mut body = &buf[offset..n];
-        while needed > 0 {
-            match body.len().cmp(&needed) {
-                Ordering::Equal => {
-                    package
-                        .extend_from_slice(&body[..])
-                        .expect("body.len() is the same length as left capacity");
-                    return Ok(package
-                        .into_array()
-                        .expect("just verified package is filled"));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Waiting for a start byte.
+    Hunting,
+    /// Part of a package is in the frame, the number of bytes it has.
+    Collecting(usize),
+    /// A whole package is in the frame.
+    Complete,
+}
+
+/// Finds the newest package in the received bytes in a single pass. A
+/// package is only handed out if it ends a read, when more bytes follow it
+/// in the same read it is outdated.
+///
+/// Never panics, whatever the bytes: it only indexes through `get`,
+/// iterators and constant indices into the frame. A panic in a CO2 monitor
+/// would take the alarm down with it.
+#[derive(Debug)]
+pub(crate) struct Framer {
+    frame: [u8; PAYLOAD_SIZE],
+    state: State,
+}
+
+impl Framer {
+    pub(crate) const fn new() -> Self {
+        Framer {
+            frame: [0u8; PAYLOAD_SIZE],
+            state: State::Hunting,
+        }
+    }
+
+    /// Feed the bytes of a read, they are copied straight into the frame.
+    pub(crate) fn push(&mut self, mut bytes: &[u8], observer: &mut impl Observer) {
+        while !bytes.is_empty() {
+            match self.state {
+                State::Hunting => {
+                    let Some(start) = bytes.iter().position(|b| *b == 0xFF) else {
+                        return;
+                    };
+                    self.frame[0] = 0xFF;
+                    self.state = State::Collecting(1);
+                    bytes = bytes.get(start + 1..).unwrap_or_default();
                 }
-                Ordering::Less => {
-                    package
-                        .extend_from_slice(&body[..])
-                        .expect("body.len() is less then left capacity");
-                    needed -= body.len();
-
-                    let n = rx.read(&mut buf).await.map_err(Error::Reading)?;
-                    if n == 0 {
-                        return Err(Error::ReadingEOF);
+                State::Collecting(len) => {
+                    let mut n = 0;
+                    for (slot, byte) in self.frame.iter_mut().skip(len).zip(bytes) {
+                        *slot = *byte;
+                        n += 1;
                     }
-                    body = &buf[..n];
-                }
-                Ordering::Greater => {
-                    debug!("skipping outdated package");
-                    package.clear();
-                    needed = PAYLOAD_SIZE;
-                    // limit search to new packages at the end of the body
-                    body = &body[body.len().saturating_sub(PAYLOAD_SIZE)..];
-                    let newest_starts = body.iter().rev().skip_while(|byte| **byte != 0xff).count();
-                    // no package start in body
-                    if newest_starts == 0 {
-                        break;
+                    self.state = if len.saturating_add(n) >= PAYLOAD_SIZE {
+                        State::Complete
                     } else {
-                        body = &body[newest_starts - 1..];
-                    }
+                        State::Collecting(len + n)
+                    };
+                    bytes = bytes.get(n..).unwrap_or_default();
+                }
+                State::Complete => {
+                    trace!("skipping outdated package");
+                    observer.stale_frame();
+                    observer.resync();
+                    self.resync();
+                }
+            }
+        }
+    }
+
+    /// Called once all bytes of a read have been pushed, returns the package
+    /// if one ends the read and responds to `expected_command`.
+    pub(crate) fn end_of_read(
+        &mut self,
+        expected_command: u8,
+        observer: &mut impl Observer,
+    ) -> Option<[u8; PAYLOAD_SIZE]> {
+        if self.state != State::Complete {
+            return None;
+        }
+        // 0xFF is a valid data byte, if the checksum does not add up the
+        // package probably starts at the 0xFF in the body
+        let [_, command, ..] = self.frame;
+        let [_, after_start @ ..] = &self.frame;
+        if !checksum_valid(&self.frame) && after_start.contains(&0xFF) {
+            trace!("package corrupt, resyncing to start byte in body");
+            observer.checksum_failure(&Frame::from_bytes(self.frame));
+            observer.resync();
+            self.resync();
+            return None;
+        }
+        if command != expected_command {
+            trace!("skipping response to another command");
+            observer.stale_frame();
+            self.state = State::Hunting;
+            return None;
+        }
+        Some(self.frame)
+    }
+
+    /// Drop the current start byte, continue from the next one in the frame.
+    fn resync(&mut self) {
+        let len = match self.state {
+            State::Hunting => return,
+            State::Collecting(len) => len,
+            State::Complete => PAYLOAD_SIZE,
+        };
+        let next_start = self.frame.iter().take(len).skip(1).position(|b| *b == 0xFF);
+        self.state = match next_start {
+            Some(pos) => {
+                let start = pos + 1;
+                let mut frame = [0u8; PAYLOAD_SIZE];
+                for (slot, byte) in frame
+                    .iter_mut()
+                    .zip(self.frame.iter().take(len).skip(start))
+                {
+                    *slot = *byte;
                 }
+                self.frame = frame;
+                State::Collecting(len.saturating_sub(start))
+            }
+            None => State::Hunting,
+        };
+    }
+}
+
+/// Find a package the same way the driver does, without any IO. `chunks`
+/// are the results of consecutive reads from the UART, running out of chunks
+/// is treated as EOF. Deterministic, intended for fuzzing and for decoding
+/// captured traffic.
+pub fn decode_chunks<'a, I>(
+    chunks: I,
+    expected_command: u8,
+    max_discarded: usize,
+) -> Result<Frame, Error<Infallible, Infallible>>
+where
+    I: IntoIterator<Item = &'a [u8]>,
+{
+    let mut rx = ChunkReader {
+        chunks: chunks.into_iter(),
+        current: &[],
+    };
+    let future = pin!(read_package::<Infallible, _, { 5 * PAYLOAD_SIZE }>(
+        &mut rx,
+        expected_command,
+        max_discarded,
+    ));
+    match future.poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(res) => res.map(Frame::from_bytes),
+        Poll::Pending => unreachable!("ChunkReader never blocks"),
+    }
+}
+
+struct ChunkReader<'a, I> {
+    chunks: I,
+    current: &'a [u8],
+}
+
+impl<I> ErrorType for ChunkReader<'_, I> {
+    type Error = Infallible;
+}
+
+impl<'a, I: Iterator<Item = &'a [u8]>> Read for ChunkReader<'a, I> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        while self.current.is_empty() {
+            match self.chunks.next() {
+                Some(chunk) => self.current = chunk,
+                None => return Ok(0),
             }
-        } // break out of this
-    } // into this
+        }
+        let n = self.current.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.current[..n]);
+        self.current = &self.current[n..];
+        Ok(n)
+    }
+}
+
+/// Everything but the package we are working on has been discarded
+pub(crate) fn check_discarded<TxError, RxError>(
+    received: usize,
+    max_discarded: usize,
+) -> Result<(), Error<TxError, RxError>> {
+    let discarded = received.saturating_sub(PAYLOAD_SIZE);
+    if discarded > max_discarded {
+        Err(Error::Desynchronized { discarded })
+    } else {
+        Ok(())
+    }
 }
 
 #[cfg(all(target_os = "linux", test))]
@@ -83,22 +271,11 @@ mod test {
     use crate::Error;
 
     use super::read_package;
+    use crate::DEFAULT_RESYNC_LIMIT;
     use core::convert::Infallible;
-    use embedded_io_async::{ErrorType, Read, Write};
+    use embedded_io_async::{ErrorType, Read};
     use futures::executor::block_on;
 
-    struct MockTx;
-
-    impl ErrorType for MockTx {
-        type Error = Infallible;
-    }
-
-    impl Write for MockTx {
-        async fn write(&mut self, _buf: &[u8]) -> Result<usize, Self::Error> {
-            unimplemented!()
-        }
-    }
-
     struct MockRx {
         curr_read: usize,
         reads: &'static [&'static [u8]],
@@ -138,7 +315,12 @@ mod test {
                 curr_read: 0,
                 reads: &[&[255, 2, 3, 4, 5, 6, 7, 8, 9, 10]],
             };
-            let eof_err = block_on(read_package::<MockTx, MockRx>(&mut rx)).unwrap_err();
+            let eof_err = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap_err();
             assert_eq!(eof_err, Error::ReadingEOF)
         }
 
@@ -148,7 +330,12 @@ mod test {
                 curr_read: 0,
                 reads: &[&[255, 2, 3, 4, 5, 6, 7, 8, 9, 10], &[11, 12, 13]],
             };
-            let eof_err = block_on(read_package::<MockTx, MockRx>(&mut rx)).unwrap_err();
+            let eof_err = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap_err();
             assert_eq!(eof_err, Error::ReadingEOF)
         }
 
@@ -162,7 +349,12 @@ mod test {
                     &[15, 16, 17, 18, 19],       // package ends without newer data available
                 ],
             };
-            let package = block_on(read_package::<MockTx, MockRx>(&mut rx)).unwrap();
+            let package = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap();
             assert_eq!(package, [255, 12, 13, 14, 15, 16, 17, 18, 19])
         }
 
@@ -176,7 +368,12 @@ mod test {
                     &[255, 12, 13, 14, 15, 16, 17, 18, 19],
                 ],
             };
-            let package = block_on(read_package::<MockTx, MockRx>(&mut rx)).unwrap();
+            let package = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap();
             assert_eq!(package, [255, 12, 13, 14, 15, 16, 17, 18, 19])
         }
     }
@@ -197,7 +394,12 @@ mod test {
                         &[255, 12, 13, 14, 15, 16, 17, 18, 19], // a whole package again
                     ],
                 };
-                let package = block_on(read_package::<MockTx, MockRx>(&mut rx)).unwrap();
+                let package = block_on(read_package::<Infallible, MockRx, 45>(
+                    &mut rx,
+                    12,
+                    DEFAULT_RESYNC_LIMIT,
+                ))
+                .unwrap();
                 assert_eq!(package, [255, 12, 13, 14, 15, 16, 17, 18, 19])
             }
         }
@@ -208,11 +410,216 @@ mod test {
                 curr_read: 0,
                 reads: &[&[255, 2, 3, 4], &[5, 6]],
             };
-            let err = block_on(read_package::<MockTx, MockRx>(&mut rx)).unwrap_err();
+            let err = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap_err();
             assert_eq!(err, Error::ReadingEOF)
         }
     }
 
+    mod other_command {
+        use super::*;
+
+        #[test]
+        fn skip_response_to_other_command() {
+            let mut rx = MockRx {
+                curr_read: 0,
+                reads: &[
+                    &[255, 2, 3, 4, 5, 6, 7, 8, 9],
+                    &[255, 12, 13, 14, 15, 16, 17, 18, 19],
+                ],
+            };
+            let package = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap();
+            assert_eq!(package, [255, 12, 13, 14, 15, 16, 17, 18, 19])
+        }
+
+        #[test]
+        fn eof_without_expected_response() {
+            let mut rx = MockRx {
+                curr_read: 0,
+                reads: &[&[255, 2, 3, 4, 5, 6, 7, 8, 9]],
+            };
+            let err = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap_err();
+            assert_eq!(err, Error::ReadingEOF)
+        }
+    }
+
+    mod start_byte_in_payload {
+        use super::*;
+
+        // 0xFF as data byte
+        const PACKAGE: [u8; 9] = [255, 12, 1, 255, 0, 0, 0, 0, 244];
+        // checksum of 0xFF
+        const PACKAGE2: [u8; 9] = [255, 12, 245, 0, 0, 0, 0, 0, 255];
+
+        #[test]
+        fn checksums_are_valid() {
+            assert!(crate::measurement::checksum_valid(&PACKAGE));
+            assert!(crate::measurement::checksum_valid(&PACKAGE2));
+        }
+
+        #[test]
+        fn accept_whole_package() {
+            let mut rx = MockRx {
+                curr_read: 0,
+                reads: &[&PACKAGE],
+            };
+            let package = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap();
+            assert_eq!(package, PACKAGE)
+        }
+
+        #[test]
+        fn accept_after_stale_package() {
+            let mut rx = MockRx {
+                curr_read: 0,
+                reads: &[&[255, 2, 3, 4, 5, 6, 7, 8, 9, 10], &PACKAGE2],
+            };
+            let package = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap();
+            assert_eq!(package, PACKAGE2)
+        }
+
+        #[test]
+        fn resync_on_start_byte_in_corrupt_package() {
+            let mut rx = MockRx {
+                curr_read: 0,
+                reads: &[&[255, 2, 3, 255, 12, 13, 14, 15, 16], &[17, 18, 19]],
+            };
+            let package = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap();
+            assert_eq!(package, [255, 12, 13, 14, 15, 16, 17, 18, 19])
+        }
+    }
+
+    mod garbage {
+        use super::*;
+
+        #[test]
+        fn give_up_after_limit() {
+            let mut rx = MockRx {
+                curr_read: 0,
+                reads: &[&[0; 10], &[1; 10], &[2; 10], &[3; 10]],
+            };
+            let err =
+                block_on(read_package::<Infallible, MockRx, 45>(&mut rx, 12, 15)).unwrap_err();
+            assert_eq!(err, Error::Desynchronized { discarded: 21 })
+        }
+    }
+
+    mod small_buffer {
+        use super::*;
+
+        #[test]
+        fn package_over_multiple_reads() {
+            let mut rx = MockRx {
+                curr_read: 0,
+                reads: &[&[255, 12, 13, 14], &[15, 16, 17, 18], &[19]],
+            };
+            let package = block_on(read_package::<Infallible, MockRx, 4>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap();
+            assert_eq!(package, [255, 12, 13, 14, 15, 16, 17, 18, 19])
+        }
+    }
+
+    mod decode {
+        use super::super::decode_chunks;
+        use super::*;
+
+        #[test]
+        fn same_result_as_async() {
+            let chunks: [&[u8]; 3] = [
+                &[255, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+                &[1, 2, 3, 255, 12, 13, 14],
+                &[15, 16, 17, 18, 19],
+            ];
+            let package = decode_chunks(chunks, 12, DEFAULT_RESYNC_LIMIT).unwrap();
+            assert_eq!(package, [255, 12, 13, 14, 15, 16, 17, 18, 19]);
+            assert_eq!(
+                decode_chunks([], 12, DEFAULT_RESYNC_LIMIT).unwrap_err(),
+                Error::ReadingEOF
+            );
+        }
+    }
+
+    mod buffered {
+        use super::super::read_response_buffered;
+        use super::*;
+        use crate::observer::EventCounts;
+        use crate::protocol::Protocol;
+        use embedded_io_async::BufRead;
+
+        impl BufRead for MockRx {
+            async fn fill_buf(&mut self) -> Result<&[u8], Self::Error> {
+                Ok(self.reads.get(self.curr_read).copied().unwrap_or_default())
+            }
+
+            fn consume(&mut self, amt: usize) {
+                assert_eq!(amt, self.reads[self.curr_read].len());
+                self.curr_read += 1;
+            }
+        }
+
+        #[test]
+        fn two_packages_accept_last() {
+            let mut rx = MockRx {
+                curr_read: 0,
+                reads: &[
+                    &[255, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+                    &[1, 2, 3, 255, 12, 13, 14],
+                    &[15, 16, 17, 18, 19],
+                ],
+            };
+            let mut counts = EventCounts::default();
+            let package = block_on(read_response_buffered::<Infallible, MockRx>(
+                &mut rx,
+                &mut Protocol::awaiting(12, DEFAULT_RESYNC_LIMIT),
+                &mut counts,
+            ))
+            .unwrap();
+            assert_eq!(package, [255, 12, 13, 14, 15, 16, 17, 18, 19]);
+            assert_eq!(counts.resyncs, 1);
+            assert_eq!(
+                block_on(read_response_buffered::<Infallible, MockRx>(
+                    &mut rx,
+                    &mut Protocol::awaiting(12, DEFAULT_RESYNC_LIMIT),
+                    &mut counts,
+                ))
+                .unwrap_err(),
+                Error::ReadingEOF
+            );
+        }
+    }
+
     mod huge_read {
         use super::*;
 
@@ -225,8 +632,77 @@ mod test {
                     3, 4, 5, 6, 7, 8, 9, 10, 255, 12, 13, 14, 15, 16, 17, 18, 19,
                 ]],
             };
-            let package = block_on(read_package::<MockTx, MockRx>(&mut rx)).unwrap();
+            let package = block_on(read_package::<Infallible, MockRx, 45>(
+                &mut rx,
+                12,
+                DEFAULT_RESYNC_LIMIT,
+            ))
+            .unwrap();
             assert_eq!(package, [255, 12, 13, 14, 15, 16, 17, 18, 19])
         }
     }
+
+    /// Panic detection: random bytes biased towards start and command
+    /// bytes, split into random reads, must never panic the parser.
+    mod never_panics {
+        use super::*;
+        use crate::{decode_chunks, Deframer};
+
+        /// Claims to have read more then fits in the buffer.
+        struct LyingRx;
+
+        impl ErrorType for LyingRx {
+            type Error = Infallible;
+        }
+
+        impl Read for LyingRx {
+            async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+                buf.fill(0xFF);
+                Ok(buf.len() + 3)
+            }
+        }
+
+        /// xorshift, deterministic so failures reproduce.
+        fn next(state: &mut u32) -> u32 {
+            *state ^= *state << 13;
+            *state ^= *state >> 17;
+            *state ^= *state << 5;
+            *state
+        }
+
+        #[test]
+        fn random_reads() {
+            let mut state = 0x1234_5678;
+            for _ in 0..2_000 {
+                let len = next(&mut state) as usize % 64;
+                let bytes: Vec<u8> = (0..len)
+                    .map(|_| match next(&mut state) % 4 {
+                        0 => 0xFF,
+                        1 => 0x86,
+                        _ => next(&mut state) as u8,
+                    })
+                    .collect();
+                let mut chunks = Vec::new();
+                let mut rest = &bytes[..];
+                while !rest.is_empty() {
+                    let n = 1 + next(&mut state) as usize % rest.len();
+                    let (chunk, tail) = rest.split_at(n);
+                    chunks.push(chunk);
+                    rest = tail;
+                }
+
+                let _ = decode_chunks(chunks.iter().copied(), 0x86, 16);
+                let mut deframer = Deframer::new();
+                for byte in &bytes {
+                    let _ = deframer.push(*byte);
+                }
+            }
+        }
+
+        #[test]
+        fn lying_reader() {
+            let res = block_on(read_package::<Infallible, _, 4>(&mut LyingRx, 0x86, 16));
+            assert!(matches!(res, Err(Error::Desynchronized { .. })));
+        }
+    }
 }
@@ -0,0 +1,71 @@
+/// Fixed capacity FIFO queue, an array plus a start and length.
+#[derive(Debug, Clone)]
+pub(crate) struct Ring<T, const N: usize> {
+    items: [Option<T>; N],
+    start: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> Ring<T, N> {
+    pub(crate) const fn new() -> Self {
+        Ring {
+            items: [const { None }; N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Gives `item` back if the queue is full.
+    pub(crate) fn push_back(&mut self, item: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(item);
+        }
+        self.items[(self.start + self.len) % N] = Some(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub(crate) fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let item = self.items[self.start].take();
+        self.start = (self.start + 1) % N;
+        self.len -= 1;
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_around() {
+        let mut ring = Ring::<u8, 3>::new();
+        assert_eq!(ring.pop_front(), None);
+        for i in 0..3 {
+            ring.push_back(i).unwrap();
+        }
+        assert_eq!(ring.push_back(3), Err(3));
+        assert_eq!(ring.pop_front(), Some(0));
+        ring.push_back(3).unwrap();
+        assert_eq!(ring.len(), 3);
+        for i in 1..4 {
+            assert_eq!(ring.pop_front(), Some(i));
+        }
+        assert!(ring.is_empty());
+    }
+}
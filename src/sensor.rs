@@ -0,0 +1,113 @@
+use embedded_io_async::{Read, Write};
+
+use crate::any::AnyMhz;
+use crate::mock::MockSensor;
+use crate::model::{reports_temperature, Model};
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Error, Measurement, Ppm, MHZ};
+
+/// One reading of a [`Co2Sensor`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Co2Reading {
+    pub co2: Ppm,
+    /// Degrees Celsius, None if the sensor does not report temperature.
+    pub temp_celsius: Option<i16>,
+}
+
+/// Any CO2 sensor. Write application code against this trait to be able to
+/// swap the MH-Z* for a different sensor later.
+#[allow(async_fn_in_trait)]
+pub trait Co2Sensor {
+    type Error;
+
+    async fn read(&mut self) -> Result<Co2Reading, Self::Error>;
+}
+
+fn reading<M: Model>(measurement: Measurement) -> Co2Reading {
+    Co2Reading {
+        co2: measurement.co2,
+        temp_celsius: reports_temperature::<M>().then(|| measurement.temp_celsius()),
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> Co2Sensor for MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    type Error = Error<Tx::Error, Rx::Error>;
+
+    async fn read(&mut self) -> Result<Co2Reading, Self::Error> {
+        self.read_co2().await.map(reading::<M>)
+    }
+}
+
+impl<Tx, Rx, const RX_BUF: usize, P, O> Co2Sensor for AnyMhz<Tx, Rx, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    P: Pacing,
+    O: Observer,
+{
+    type Error = Error<Tx::Error, Rx::Error>;
+
+    async fn read(&mut self) -> Result<Co2Reading, Self::Error> {
+        let reports_temperature = self.reports_temperature();
+        let measurement = self.read_co2().await?;
+        Ok(Co2Reading {
+            co2: measurement.co2,
+            temp_celsius: reports_temperature.then(|| measurement.temp_celsius()),
+        })
+    }
+}
+
+impl<M: Model, TxError, RxError, const N: usize> Co2Sensor for MockSensor<M, TxError, RxError, N> {
+    type Error = Error<TxError, RxError>;
+
+    async fn read(&mut self) -> Result<Co2Reading, Self::Error> {
+        self.read_co2().await.map(reading::<M>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::MovingAverage;
+    use crate::model::Mhz16;
+    use futures::executor::block_on;
+
+    /// Application code only knows about the trait.
+    async fn smoothed(sensor: &mut impl Co2Sensor, reads: usize) -> Option<u16> {
+        let mut average = MovingAverage::<4>::new();
+        for _ in 0..reads {
+            if let Ok(reading) = sensor.read().await {
+                average.push(reading.co2.get());
+            }
+        }
+        average.average()
+    }
+
+    #[test]
+    fn generic_over_sensor() {
+        let mut sensor: MockSensor<Mhz16> = MockSensor::new();
+        for co2 in [400, 500] {
+            sensor.push_co2(Ok(Measurement {
+                co2: Ppm(co2),
+                temp: 60,
+                calib_ticks: 0,
+                calib_cycles: 0,
+                undocumented_u: 0,
+            }));
+        }
+        assert_eq!(block_on(smoothed(&mut sensor, 2)), Some(450));
+
+        sensor.push_co2(Err(Error::ReadingEOF));
+        assert!(block_on(sensor.read()).is_err());
+    }
+}
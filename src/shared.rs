@@ -0,0 +1,107 @@
+//! One sensor used from multiple embassy tasks.
+//!
+//! Every call locks the sensor for a complete command and response. Wrap a
+//! sensor using [`Paced`](crate::pacing::Paced) to also keep commands from
+//! different tasks far enough apart.
+//!
+//! ```no_run
+//! # async fn run<Tx, Rx>(tx: Tx, rx: Rx)
+//! # where
+//! #     Tx: embedded_io_async::Write,
+//! #     Rx: embedded_io_async::Read,
+//! # {
+//! use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+//! use mhzx::shared::SharedMhz;
+//! use mhzx::MHZ;
+//!
+//! let sensor: SharedMhz<NoopRawMutex, _, _> = SharedMhz::new(MHZ::from_tx_rx(tx, rx));
+//! // in the display task
+//! let measurement = sensor.read_co2().await;
+//! // in the logging task
+//! let measurement = sensor.read_co2().await;
+//! # }
+//! ```
+
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embassy_sync::mutex::{Mutex, MutexGuard};
+use embedded_io_async::{Read, Write};
+
+use crate::model::{Mhz19B, Model};
+use crate::observer::{NoObserver, Observer};
+use crate::pacing::{NoPacing, Pacing};
+use crate::{Error, Measurement, RawMeasurement, DEFAULT_RX_BUFFER, MHZ};
+
+/// A sensor behind an [`embassy_sync::mutex::Mutex`], share it by
+/// reference or put it in a `static`.
+pub struct SharedMhz<
+    R: RawMutex,
+    Tx,
+    Rx,
+    M = Mhz19B,
+    const RX_BUF: usize = DEFAULT_RX_BUFFER,
+    P = NoPacing,
+    O = NoObserver,
+> {
+    sensor: Mutex<R, MHZ<Tx, Rx, M, RX_BUF, P, O>>,
+}
+
+impl<R: RawMutex, Tx, Rx, M, const RX_BUF: usize, P, O> SharedMhz<R, Tx, Rx, M, RX_BUF, P, O> {
+    pub const fn new(sensor: MHZ<Tx, Rx, M, RX_BUF, P, O>) -> Self {
+        SharedMhz {
+            sensor: Mutex::new(sensor),
+        }
+    }
+
+    /// Exclusive access to the sensor for other commands or a sequence of
+    /// commands that must not be interleaved.
+    pub async fn lock(&self) -> MutexGuard<'_, R, MHZ<Tx, Rx, M, RX_BUF, P, O>> {
+        self.sensor.lock().await
+    }
+
+    /// Give back the sensor.
+    pub fn into_inner(self) -> MHZ<Tx, Rx, M, RX_BUF, P, O> {
+        self.sensor.into_inner()
+    }
+}
+
+impl<R, Tx, Rx, M, const RX_BUF: usize, P, O> SharedMhz<R, Tx, Rx, M, RX_BUF, P, O>
+where
+    R: RawMutex,
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Waits for other tasks using the sensor, then
+    /// [`read_co2`](MHZ::read_co2).
+    pub async fn read_co2(&self) -> Result<Measurement, Error<Tx::Error, Rx::Error>> {
+        self.sensor.lock().await.read_co2().await
+    }
+
+    /// Waits for other tasks using the sensor, then
+    /// [`read_co2_raw`](MHZ::read_co2_raw).
+    pub async fn read_co2_raw(&self) -> Result<RawMeasurement, Error<Tx::Error, Rx::Error>> {
+        self.sensor.lock().await.read_co2_raw().await
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::sim::FakeMhz;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use futures::executor::block_on;
+    use futures::future::join;
+
+    #[test]
+    fn tasks_take_turns() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let sensor: SharedMhz<NoopRawMutex, _, _> = SharedMhz::new(MHZ::from_tx_rx(tx, rx));
+
+        let (a, b) = block_on(join(sensor.read_co2(), sensor.read_co2_raw()));
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(sim.state().commands_received, 2);
+    }
+}
@@ -0,0 +1,184 @@
+//! Decode UART traffic recorded with a logic analyzer. Frames are found
+//! using [`Deframer`], noise is resynced past the way the driver does.
+//!
+//! Export the annotations of the sigrok UART decoder as CSV, for example
+//! from PulseView, with the data format of the decoder set to hex. Every
+//! line with a `RX data` or `TX data` annotation is one byte. The first
+//! column is taken as its sample number and the last as the value, other
+//! lines such as the header, start and stop bits are skipped.
+//!
+//! ```
+//! use mhzx::capture::Direction;
+//! use mhzx::sigrok;
+//!
+//! let csv = "\
+//! \"Start Sample\",\"End Sample\",\"Row\",\"Value\"
+//! 0,10,\"TX data\",\"FF\"
+//! 10,20,\"TX data\",\"01\"
+//! ";
+//! let bytes = sigrok::parse_csv(csv).unwrap();
+//! assert_eq!(bytes[1].value, 0x01);
+//! assert_eq!(bytes[1].direction, Direction::Tx);
+//! ```
+
+extern crate std;
+
+use core::fmt;
+use std::vec::Vec;
+
+use crate::capture::Direction;
+use crate::decode::{decode, Decoded};
+use crate::{Deframer, PAYLOAD_SIZE};
+
+/// A single byte decoded by the logic analyzer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UartByte {
+    pub direction: Direction,
+    /// Sample at which the byte started.
+    pub sample: u64,
+    pub value: u8,
+}
+
+/// A frame found in the capture.
+#[derive(Debug, Clone, Copy)]
+pub struct CapturedFrame {
+    pub direction: Direction,
+    /// Sample at which the first byte of the frame started.
+    pub sample: u64,
+    pub decoded: Decoded,
+}
+
+/// A data annotation that could not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line in the export, starting at 1.
+    pub line: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} is not a sample number and hex byte, is the decoder set to hex?",
+            self.line
+        )
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+fn direction(annotation: &str) -> Option<Direction> {
+    let annotation = annotation.to_ascii_lowercase();
+    if !annotation.contains("data") {
+        None
+    } else if annotation.contains("rx") {
+        Some(Direction::Rx)
+    } else if annotation.contains("tx") {
+        Some(Direction::Tx)
+    } else {
+        None
+    }
+}
+
+/// Every byte in a CSV export of the sigrok UART decoder, see the
+/// [module docs](self) for the expected format.
+pub fn parse_csv(csv: &str) -> Result<Vec<UartByte>, ParseError> {
+    let mut bytes = Vec::new();
+    for (i, line) in csv.lines().enumerate() {
+        let fields: Vec<_> = line
+            .split(',')
+            .map(|f| f.trim().trim_matches('"'))
+            .collect();
+        let Some(direction) = fields.iter().find_map(|f| direction(f)) else {
+            continue;
+        };
+
+        let error = ParseError { line: i + 1 };
+        let sample = fields[0].parse().map_err(|_| error)?;
+        let value = fields[fields.len() - 1];
+        let value = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+            .unwrap_or(value);
+        let value = u8::from_str_radix(value, 16).map_err(|_| error)?;
+        bytes.push(UartByte {
+            direction,
+            sample,
+            value,
+        });
+    }
+    Ok(bytes)
+}
+
+/// Every frame send and received in a CSV export of the sigrok UART
+/// decoder, in the order they completed.
+pub fn decode_csv(csv: &str) -> Result<Vec<CapturedFrame>, ParseError> {
+    // the samples of the last frame worth of bytes, a frame always ends
+    // with the byte pushed last
+    let mut tx = (Deframer::new(), [0u64; PAYLOAD_SIZE], 0usize);
+    let mut rx = (Deframer::new(), [0u64; PAYLOAD_SIZE], 0usize);
+    let mut frames = Vec::new();
+    for byte in parse_csv(csv)? {
+        let (deframer, samples, pushed) = match byte.direction {
+            Direction::Tx => &mut tx,
+            Direction::Rx => &mut rx,
+        };
+        samples[*pushed % PAYLOAD_SIZE] = byte.sample;
+        *pushed = pushed.wrapping_add(1);
+        if let Some(frame) = deframer.push_any(byte.value) {
+            frames.push(CapturedFrame {
+                direction: byte.direction,
+                sample: samples[*pushed % PAYLOAD_SIZE],
+                decoded: decode(frame.unwrap_or_else(|corrupt| corrupt)),
+            });
+        }
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Command;
+    use std::fmt::Write;
+    use std::string::String;
+
+    #[test]
+    fn request_and_response() {
+        let mut csv = String::from("\"Start Sample\",\"End Sample\",\"Row\",\"Value\"\n");
+        let mut sample = 0;
+        let mut add = |row: &str, bytes: &[u8]| {
+            for byte in bytes {
+                let end = sample + 100;
+                writeln!(csv, "{sample},{end},\"{row} data\",\"{byte:02X}\"").unwrap();
+                writeln!(csv, "{end},{},\"{row} bits\",\"Stop bit\"", end + 10).unwrap();
+                sample += 110;
+            }
+        };
+        add("TX", Command::ReadCo2.to_frame().as_bytes());
+        // noise, including a start byte
+        add("RX", &[0x00, 0xFF]);
+        add(
+            "RX",
+            &[0xFF, 0x86, 0x01, 0xA4, 0x3E, 0x00, 0x00, 0x00, 0x97],
+        );
+
+        let frames = decode_csv(&csv).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].direction, Direction::Tx);
+        assert_eq!(frames[0].sample, 0);
+        assert!(matches!(
+            frames[0].decoded,
+            Decoded::Command(Command::ReadCo2)
+        ));
+        assert!(matches!(frames[1].decoded, Decoded::Corrupt(_)));
+        assert_eq!(frames[2].direction, Direction::Rx);
+        assert_eq!(frames[2].sample, 11 * 110);
+        match frames[2].decoded {
+            Decoded::Measurement(m) => assert_eq!(m.co2, 420),
+            other => panic!("{other:?}"),
+        }
+
+        assert_eq!(parse_csv("0,10,RX data,ZZ"), Err(ParseError { line: 1 }));
+    }
+}
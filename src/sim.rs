@@ -0,0 +1,501 @@
+//! A simulated sensor for exercising the real driver end-to-end on the host.
+//!
+//! ```
+//! # futures::executor::block_on(async {
+//! use mhzx::sim::FakeMhz;
+//! use mhzx::MHZ;
+//!
+//! let sim = FakeMhz::new();
+//! sim.set_co2(612);
+//! let (tx, rx) = sim.split();
+//! let mut sensor = MHZ::from_tx_rx(tx, rx);
+//! assert_eq!(sensor.read_co2().await.unwrap().co2, 612);
+//! # });
+//! ```
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+use core::future::poll_fn;
+use core::task::Poll;
+
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{ErrorType, Read, ReadReady, Write};
+
+use crate::measurement::checksum;
+use crate::ring::Ring;
+use crate::{Deframer, FirmwareVersion, Frame, PAYLOAD_SIZE};
+
+/// A delay that returns at once, for methods that wait between commands.
+/// The simulation needs no time to settle.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// Like [`NoDelay`] but adds up the milliseconds waited, to check a method
+/// waits as long as it should.
+#[derive(Debug, Default)]
+pub struct CountingDelay(pub u32);
+
+impl DelayNs for CountingDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+
+    async fn delay_ms(&mut self, ms: u32) {
+        self.0 += ms;
+    }
+}
+
+/// Most bytes [`FakeMhz::prefix_next_response`] accepts.
+pub const MAX_PREFIX: usize = 32;
+
+/// Simulated MH-Z* sensor. Use [`split`](FakeMhz::split) to get the two
+/// UART halves to pass to [`MHZ`](crate::MHZ).
+///
+/// Answers the read commands (0x86, 0x85, 0xA0, 0x7D, 0x9B and 0x9C) and records the
+/// configuration commands. Responses can be corrupted, delayed, preceded by
+/// stale bytes and delivered in chunks. Reading while no response is
+/// pending returns EOF.
+pub struct FakeMhz {
+    state: RefCell<State>,
+}
+
+/// Everything the simulated sensor reports and has been told.
+#[derive(Debug, Clone)]
+pub struct State {
+    pub co2: u16,
+    /// Temperature in degrees Celsius.
+    pub temp: i16,
+    pub raw_co2: u16,
+    pub adc_temp: u16,
+    pub adc_min_light: u16,
+    pub firmware_version: FirmwareVersion,
+    /// Last range set using command 0x99.
+    pub range: Option<u16>,
+    /// Last ABC state set using command 0x79.
+    pub abc: Option<bool>,
+    /// Number of upcoming configuration commands (0x99 and 0x79) to
+    /// silently ignore, as the real sensor sometimes does.
+    pub ignore_config: usize,
+    /// Number of zero point calibrations received.
+    pub zero_calibrations: usize,
+    /// Number of valid command frames received.
+    pub commands_received: usize,
+    /// Number of upcoming responses to send with a corrupt checksum.
+    pub corrupt_responses: usize,
+    /// Maximum number of bytes handed out per read.
+    pub chunk_size: usize,
+    /// Times a read is pending before it returns data.
+    pub latency_polls: usize,
+    /// Loop everything written back into the receiver, as on half-duplex
+    /// wiring.
+    pub echo: bool,
+    deframer: Deframer,
+    /// Bytes to send before the next response.
+    stale_prefix: Ring<u8, MAX_PREFIX>,
+    output: Ring<u8, 64>,
+}
+
+impl FakeMhz {
+    pub fn new() -> Self {
+        FakeMhz {
+            state: RefCell::new(State {
+                co2: 400,
+                temp: 20,
+                raw_co2: 400,
+                adc_temp: 0,
+                adc_min_light: 0,
+                firmware_version: FirmwareVersion(*b"0443"),
+                range: None,
+                abc: None,
+                ignore_config: 0,
+                zero_calibrations: 0,
+                commands_received: 0,
+                corrupt_responses: 0,
+                chunk_size: usize::MAX,
+                latency_polls: 0,
+                echo: false,
+                deframer: Deframer::new(),
+                stale_prefix: Ring::new(),
+                output: Ring::new(),
+            }),
+        }
+    }
+
+    /// The transmit and receive half of the UART the sensor is on.
+    pub fn split(&self) -> (FakeTx<'_>, FakeRx<'_>) {
+        (
+            FakeTx { sim: self },
+            FakeRx {
+                sim: self,
+                polls: 0,
+            },
+        )
+    }
+
+    /// Change the simulated state.
+    pub fn with<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        f(&mut self.state.borrow_mut())
+    }
+
+    /// A copy of the simulated state.
+    pub fn state(&self) -> State {
+        self.state.borrow().clone()
+    }
+
+    pub fn set_co2(&self, co2: u16) {
+        self.with(|s| s.co2 = co2)
+    }
+
+    /// Send the next `n` responses with a corrupt checksum.
+    pub fn corrupt_next(&self, n: usize) {
+        self.with(|s| s.corrupt_responses = n)
+    }
+
+    /// Send `bytes` right before the next response, as if they were left
+    /// over from an earlier transaction.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is longer than [`MAX_PREFIX`].
+    pub fn prefix_next_response(&self, bytes: &[u8]) {
+        assert!(
+            bytes.len() <= MAX_PREFIX,
+            "prefix is at most {MAX_PREFIX} bytes"
+        );
+        self.with(|s| {
+            s.stale_prefix = Ring::new();
+            for byte in bytes {
+                let _ = s.stale_prefix.push_back(*byte);
+            }
+        })
+    }
+}
+
+impl Default for FakeMhz {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl State {
+    fn handle(&mut self, frame: Frame) {
+        let frame = frame.as_bytes();
+        self.commands_received += 1;
+        let payload = match frame[2] {
+            0x86 => {
+                let [high, low] = self.co2.to_be_bytes();
+                let temp = (self.temp + 40).clamp(0, 255) as u8;
+                [0x86, high, low, temp, 0, 0, 0]
+            }
+            0x85 => {
+                let [th, tl] = self.adc_temp.to_be_bytes();
+                let [ch, cl] = self.raw_co2.to_be_bytes();
+                let [lh, ll] = self.adc_min_light.to_be_bytes();
+                [0x85, th, tl, ch, cl, lh, ll]
+            }
+            0xA0 => {
+                let [a, b, c, d] = self.firmware_version.0;
+                [0xA0, a, b, c, d, 0, 0]
+            }
+            0x7D => [0x7D, 0, 0, 0, 0, 0, self.abc.unwrap_or(true).into()],
+            0x9B => {
+                let [high, low] = self.range.unwrap_or(5000).to_be_bytes();
+                [0x9B, 0, 0, high, low, 0, 0]
+            }
+            0x9C => {
+                let [high, low] = 400u16.to_be_bytes();
+                [0x9C, 0, 0, high, low, 0, 0]
+            }
+            0x99 | 0x79 if self.ignore_config > 0 => {
+                self.ignore_config -= 1;
+                return;
+            }
+            0x99 => {
+                self.range = Some(u16::from_be_bytes([frame[6], frame[7]]));
+                return;
+            }
+            0x79 => {
+                self.abc = Some(frame[3] == 0xA0);
+                return;
+            }
+            0x87 => {
+                self.zero_calibrations += 1;
+                return;
+            }
+            _ => return,
+        };
+
+        let mut response = [0u8; PAYLOAD_SIZE];
+        response[0] = 0xFF;
+        response[1..8].copy_from_slice(&payload);
+        response[8] = checksum(&response);
+        if self.corrupt_responses > 0 {
+            self.corrupt_responses -= 1;
+            response[8] = response[8].wrapping_add(1);
+        }
+
+        while let Some(byte) = self.stale_prefix.pop_front() {
+            self.output(byte);
+        }
+        for byte in response {
+            self.output(byte);
+        }
+    }
+
+    fn output(&mut self, byte: u8) {
+        // the real sensor would overwrite its output as well
+        if self.output.is_full() {
+            self.output.pop_front();
+        }
+        let _ = self.output.push_back(byte);
+    }
+}
+
+/// Transmit half of a [`FakeMhz`].
+pub struct FakeTx<'a> {
+    sim: &'a FakeMhz,
+}
+
+/// Receive half of a [`FakeMhz`].
+pub struct FakeRx<'a> {
+    sim: &'a FakeMhz,
+    polls: usize,
+}
+
+impl ErrorType for FakeTx<'_> {
+    type Error = Infallible;
+}
+
+impl ErrorType for FakeRx<'_> {
+    type Error = Infallible;
+}
+
+impl Write for FakeTx<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.sim.with(|state| {
+            for byte in buf {
+                if state.echo {
+                    state.output(*byte);
+                }
+                if let Some(frame) = state.deframer.push(*byte) {
+                    state.handle(frame);
+                }
+            }
+        });
+        Ok(buf.len())
+    }
+}
+
+impl Read for FakeRx<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        poll_fn(|cx| {
+            let latency = self.sim.with(|s| s.latency_polls);
+            if self.polls < latency {
+                self.polls += 1;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.polls = 0;
+            Poll::Ready(())
+        })
+        .await;
+
+        Ok(self.sim.with(|state| {
+            let n = buf.len().min(state.chunk_size).min(state.output.len());
+            for byte in &mut buf[..n] {
+                *byte = state.output.pop_front().expect("n <= output.len()");
+            }
+            n
+        }))
+    }
+}
+
+impl ReadReady for FakeRx<'_> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.sim.with(|state| !state.output.is_empty()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, Ppm, MHZ};
+    use futures::executor::block_on;
+
+    #[test]
+    fn read_values() {
+        let sim = FakeMhz::new();
+        sim.with(|s| {
+            s.co2 = 1234;
+            s.temp = -5;
+            s.raw_co2 = 1300;
+            s.chunk_size = 4;
+            s.latency_polls = 2;
+        });
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        let m = block_on(sensor.read_co2()).unwrap();
+        assert_eq!(m.co2, 1234);
+        assert_eq!(m.temp_celsius(), -5);
+        assert_eq!(block_on(sensor.read_temperature()).unwrap(), -5);
+        assert_eq!(block_on(sensor.read_co2_raw()).unwrap().co2, 1300);
+        assert_eq!(
+            block_on(sensor.read_firmware_version()).unwrap().as_str(),
+            Some("0443")
+        );
+    }
+
+    #[test]
+    fn multiple_reads() {
+        let sim = FakeMhz::new();
+        sim.with(|s| {
+            s.co2 = 5000;
+            s.raw_co2 = 6100;
+        });
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        let (m, raw) = block_on(sensor.read_both(&mut NoDelay)).unwrap();
+        assert_eq!((m.co2.get(), raw.co2), (5000, 6100));
+        assert_eq!(sim.state().commands_received, 2);
+
+        let averaged = block_on(sensor.read_co2_averaged::<3>(&mut NoDelay)).unwrap();
+        assert_eq!((averaged.co2(), averaged.summary.count), (Ppm(5000), 3));
+    }
+
+    #[test]
+    fn probe() {
+        use crate::ProbeError;
+
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        let info = block_on(sensor.probe()).unwrap();
+        assert_eq!(info.firmware.unwrap().as_str(), Some("0443"));
+        assert!(info.maybe_warming_up);
+
+        sim.corrupt_next(1);
+        assert!(matches!(
+            block_on(sensor.probe()),
+            Err(ProbeError::Garbled(Error::InvalidChecksum { .. }))
+        ));
+
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx_with_model(tx, rx, crate::model::Mhz14A);
+        sim.set_co2(812);
+        let info = block_on(sensor.probe()).unwrap();
+        assert_eq!((info.configured_model, info.firmware), ("MH-Z14A", None));
+        assert!(!info.maybe_warming_up);
+    }
+
+    #[test]
+    fn read_only() {
+        let sim = FakeMhz::new();
+        sim.set_co2(950);
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx).read_only();
+
+        assert_eq!(block_on(sensor.read_co2()).unwrap().co2, 950);
+        assert_eq!(block_on(sensor.read_temperature()).unwrap(), 20);
+        assert!(block_on(sensor.read_firmware_version()).is_ok());
+        assert_eq!(sim.state().zero_calibrations, 0);
+    }
+
+    #[test]
+    fn deadline() {
+        use core::cell::Cell;
+
+        /// Time only passes while waiting on the delay.
+        struct FakeDelay<'a>(&'a Cell<u64>);
+        impl embedded_hal_async::delay::DelayNs for FakeDelay<'_> {
+            async fn delay_ns(&mut self, ns: u32) {
+                self.0.set(self.0.get() + u64::from(ns) / 1_000_000);
+            }
+        }
+
+        let now = Cell::new(1000);
+        let clock = || now.get();
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        let m = block_on(sensor.read_co2_before(1100, &clock, &mut FakeDelay(&now)));
+        assert_eq!(m.unwrap().co2, 400);
+        assert_eq!(
+            block_on(sensor.read_co2_before(1000, &clock, &mut FakeDelay(&now))).unwrap_err(),
+            Error::Timeout
+        );
+
+        // the response never arrives
+        sim.with(|s| s.latency_polls = usize::MAX);
+        let err = block_on(sensor.read_co2_before(1050, &clock, &mut FakeDelay(&now)));
+        assert_eq!(err.unwrap_err(), Error::Timeout);
+        assert_eq!(now.get(), 1050);
+    }
+
+    #[test]
+    fn corrupt_response() {
+        let sim = FakeMhz::new();
+        sim.corrupt_next(1);
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        let err = block_on(sensor.read_co2()).unwrap_err();
+        assert!(matches!(err, Error::InvalidChecksum { .. }));
+        block_on(sensor.read_co2()).unwrap();
+    }
+
+    #[test]
+    fn stale_bytes_before_response() {
+        let sim = FakeMhz::new();
+        sim.prefix_next_response(&[0x12, 0xFF, 0x85, 0x00]);
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        assert_eq!(block_on(sensor.read_co2()).unwrap().co2, 400);
+    }
+
+    #[test]
+    #[should_panic(expected = "prefix is at most 32 bytes")]
+    fn prefix_too_long() {
+        FakeMhz::new().prefix_next_response(&[0; MAX_PREFIX + 1]);
+    }
+
+    #[test]
+    fn echo_cancel() {
+        let sim = FakeMhz::new();
+        sim.with(|s| {
+            s.echo = true;
+            s.chunk_size = 4;
+        });
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        sensor.set_echo_cancel(true);
+        block_on(async {
+            sensor.set_abc(true).await.unwrap();
+            assert!(sim.state().output.is_empty(), "echo is discarded");
+            assert_eq!(sensor.read_co2().await.unwrap().co2, 400);
+            assert!(sim.state().output.is_empty());
+        });
+    }
+
+    #[test]
+    fn configuration() {
+        use crate::model::Range;
+        use crate::ZeroCalibration;
+
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+        block_on(sensor.set_range(Range::Ppm2000)).unwrap();
+        block_on(sensor.set_abc(false)).unwrap();
+        block_on(sensor.calibrate_zero(ZeroCalibration::confirm_fresh_air())).unwrap();
+
+        let state = sim.state();
+        assert_eq!(state.range, Some(2000));
+        assert_eq!(state.abc, Some(false));
+        assert_eq!(state.zero_calibrations, 1);
+    }
+}
@@ -0,0 +1,132 @@
+//! Summaries of CO2 readings, so a data logger can report once per period
+//! instead of every sample.
+//!
+//! ```
+//! use mhzx::stats::Accumulator;
+//!
+//! let mut acc = Accumulator::new();
+//! for co2 in [400, 420, 440] {
+//!     acc.push(co2);
+//! }
+//! let summary = acc.take().unwrap();
+//! assert_eq!((summary.min, summary.mean, summary.max), (400, 420, 440));
+//! assert!(acc.summary().is_none());
+//! ```
+
+/// Statistics of the readings pushed to an [`Accumulator`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub count: u32,
+    pub min: u16,
+    pub max: u16,
+    /// Rounded to the nearest ppm.
+    pub mean: u16,
+    /// Population standard deviation, rounded down to whole ppm.
+    pub stddev: u16,
+}
+
+/// Tracks min, max, mean and standard deviation of readings since the last
+/// reset. Uses constant memory regardless of the number of readings.
+#[derive(Debug, Clone)]
+pub struct Accumulator {
+    count: u32,
+    min: u16,
+    max: u16,
+    sum: u64,
+    sum_of_squares: u64,
+}
+
+impl Accumulator {
+    pub const fn new() -> Self {
+        Accumulator {
+            count: 0,
+            min: u16::MAX,
+            max: 0,
+            sum: 0,
+            sum_of_squares: 0,
+        }
+    }
+
+    pub fn push(&mut self, co2: u16) {
+        self.count += 1;
+        self.min = self.min.min(co2);
+        self.max = self.max.max(co2);
+        self.sum += u64::from(co2);
+        self.sum_of_squares += u64::from(co2) * u64::from(co2);
+    }
+
+    /// Number of readings since the last reset.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// None if no readings were pushed since the last reset.
+    pub fn summary(&self) -> Option<Summary> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let count = u128::from(self.count);
+        let sum = u128::from(self.sum);
+        let mean = (sum + count / 2) / count;
+        // n * sum(x^2) - sum(x)^2 is never negative and does not lose
+        // precision the way the mean of the squares would
+        let scaled_variance = count * u128::from(self.sum_of_squares) - sum * sum;
+        let variance = scaled_variance / (count * count);
+
+        Some(Summary {
+            count: self.count,
+            min: self.min,
+            max: self.max,
+            mean: mean as u16,
+            stddev: (variance as u64).isqrt() as u16,
+        })
+    }
+
+    /// The summary so far, then start over.
+    pub fn take(&mut self) -> Option<Summary> {
+        let summary = self.summary();
+        self.reset();
+        summary
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl Default for Accumulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let mut acc = Accumulator::new();
+        for co2 in [2, 4, 4, 4, 5, 5, 7, 9] {
+            acc.push(co2);
+        }
+        assert_eq!(
+            acc.summary(),
+            Some(Summary {
+                count: 8,
+                min: 2,
+                max: 9,
+                mean: 5,
+                stddev: 2,
+            })
+        );
+
+        acc.reset();
+        assert_eq!(acc.count(), 0);
+        acc.push(u16::MAX);
+        assert_eq!(acc.summary().unwrap().stddev, 0);
+    }
+}
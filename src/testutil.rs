@@ -0,0 +1,131 @@
+//! Generators for frames and the byte streams a sensor sends, for property
+//! based tests. Also usable in downstream integration tests, for example to
+//! feed [`sim`](crate::sim) or [`capture::Replay`](crate::capture::Replay).
+//!
+//! The `proptest` strategies need std.
+
+extern crate std;
+
+use std::vec::Vec;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::measurement::checksum;
+use crate::PAYLOAD_SIZE;
+
+/// A frame with a valid checksum: start byte, `command`, `payload`, checksum.
+pub fn frame(command: u8, payload: [u8; 6]) -> [u8; PAYLOAD_SIZE] {
+    let mut frame = [0xFF, command, 0, 0, 0, 0, 0, 0, 0];
+    frame[2..8].copy_from_slice(&payload);
+    frame[8] = checksum(&frame);
+    frame
+}
+
+/// `frame` with its checksum broken.
+pub fn corrupt(mut frame: [u8; PAYLOAD_SIZE]) -> [u8; PAYLOAD_SIZE] {
+    frame[8] = checksum(&frame).wrapping_add(1);
+    frame
+}
+
+/// The response to [`Command::ReadCo2`](crate::Command::ReadCo2).
+pub fn co2_response(co2: u16, temp_celsius: i16) -> [u8; PAYLOAD_SIZE] {
+    let [high, low] = co2.to_be_bytes();
+    let temp = (temp_celsius + 40).clamp(0, u8::MAX as i16) as u8;
+    frame(0x86, [high, low, temp, 0, 0, 0])
+}
+
+/// Valid frames responding to `command`.
+pub fn valid_frame(command: u8) -> impl Strategy<Value = [u8; PAYLOAD_SIZE]> {
+    any::<[u8; 6]>().prop_map(move |payload| frame(command, payload))
+}
+
+/// Frames responding to `command` with a broken checksum.
+pub fn corrupted_frame(command: u8) -> impl Strategy<Value = [u8; PAYLOAD_SIZE]> {
+    valid_frame(command).prop_map(corrupt)
+}
+
+/// Random bytes, may contain start bytes.
+pub fn garbage(max_len: usize) -> impl Strategy<Value = Vec<u8>> {
+    vec(any::<u8>(), 0..=max_len)
+}
+
+/// Stale traffic preceding a response: a mix of garbage, corrupt frames,
+/// and valid frames for `command` as well as other commands. At most
+/// `max_parts` parts are concatenated.
+pub fn interleaved(command: u8, max_parts: usize) -> impl Strategy<Value = Vec<u8>> {
+    let part = prop_oneof![
+        garbage(PAYLOAD_SIZE),
+        corrupted_frame(command).prop_map(Vec::from),
+        valid_frame(command).prop_map(Vec::from),
+        any::<u8>().prop_flat_map(valid_frame).prop_map(Vec::from),
+    ];
+    vec(part, 0..=max_parts).prop_map(|parts| parts.concat())
+}
+
+/// Splits `bytes` into the chunks consecutive UART reads return.
+pub fn chunked(bytes: Vec<u8>) -> impl Strategy<Value = Vec<Vec<u8>>> {
+    let len = bytes.len();
+    vec(1..=PAYLOAD_SIZE, 0..=len).prop_map(move |sizes| {
+        let mut chunks = Vec::new();
+        let mut rest = bytes.as_slice();
+        for size in sizes.into_iter().chain(core::iter::repeat(PAYLOAD_SIZE)) {
+            if rest.is_empty() {
+                break;
+            }
+            let (chunk, tail) = rest.split_at(size.min(rest.len()));
+            chunks.push(chunk.to_vec());
+            rest = tail;
+        }
+        chunks
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode_chunks, Deframer, Frame, DEFAULT_RESYNC_LIMIT, DEFAULT_RX_BUFFER};
+
+    const COMMAND: u8 = 0x86;
+
+    proptest! {
+        #[test]
+        fn newest_frame_in_one_read(
+            stale in interleaved(COMMAND, 3),
+            newest in valid_frame(COMMAND),
+        ) {
+            let mut bytes = stale;
+            bytes.extend_from_slice(&newest);
+            let start = bytes.len().saturating_sub(DEFAULT_RX_BUFFER);
+            let read = &bytes[start..];
+
+            let package = decode_chunks([read], COMMAND, DEFAULT_RESYNC_LIMIT);
+            prop_assert_eq!(package, Ok(Frame::from_bytes(newest)));
+        }
+
+        #[test]
+        fn frame_split_over_reads(
+            chunks in valid_frame(COMMAND)
+                .prop_flat_map(|f| chunked(f.to_vec()).prop_map(move |c| (f, c)))
+        ) {
+            let (frame, chunks) = chunks;
+            let package = decode_chunks(
+                chunks.iter().map(Vec::as_slice),
+                COMMAND,
+                DEFAULT_RESYNC_LIMIT,
+            );
+            prop_assert_eq!(package, Ok(Frame::from_bytes(frame)));
+        }
+
+        #[test]
+        fn deframer_finds_every_frame(frames in vec(valid_frame(COMMAND), 0..8)) {
+            let mut deframer = Deframer::new();
+            let found: Vec<_> = frames
+                .iter()
+                .flatten()
+                .filter_map(|byte| deframer.push(*byte))
+                .collect();
+            prop_assert_eq!(found, frames);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
+
+use embedded_hal_async::delay::DelayNs;
+
+/// A monotonic source of time.
+pub trait Clock {
+    /// Milliseconds since some fixed point in the past.
+    fn now_ms(&self) -> u64;
+}
+
+impl<F: Fn() -> u64> Clock for F {
+    fn now_ms(&self) -> u64 {
+        self()
+    }
+}
+
+/// A span of time, accepted by the driver wherever it waits. Implemented
+/// for milliseconds as `u32`, [`core::time::Duration`], with the `embassy`
+/// feature `embassy_time::Duration`, with the `embedded-time` feature the
+/// `embedded_time::duration` units and with the `fugit` feature
+/// `fugit::Duration`. Spans longer than `u32::MAX`
+/// milliseconds saturate, shorter than a millisecond round down.
+pub trait IntoMillis {
+    fn into_millis(self) -> u32;
+}
+
+impl IntoMillis for u32 {
+    fn into_millis(self) -> u32 {
+        self
+    }
+}
+
+impl IntoMillis for core::time::Duration {
+    fn into_millis(self) -> u32 {
+        self.as_millis().try_into().unwrap_or(u32::MAX)
+    }
+}
+
+#[cfg(feature = "embassy")]
+impl IntoMillis for embassy_time::Duration {
+    fn into_millis(self) -> u32 {
+        self.as_millis().try_into().unwrap_or(u32::MAX)
+    }
+}
+
+#[cfg(feature = "embedded-time")]
+mod embedded_time_impls {
+    use embedded_time::duration::{
+        Hours, Microseconds, Milliseconds, Minutes, Nanoseconds, Seconds,
+    };
+
+    use super::IntoMillis;
+
+    macro_rules! impl_into_millis {
+        ($($unit:ident: $numer:literal / $denom:literal),*) => {$(
+            impl IntoMillis for $unit<u32> {
+                fn into_millis(self) -> u32 {
+                    $unit(u64::from(self.0)).into_millis()
+                }
+            }
+
+            impl IntoMillis for $unit<u64> {
+                fn into_millis(self) -> u32 {
+                    let ms = self.0.saturating_mul($numer) / $denom;
+                    ms.try_into().unwrap_or(u32::MAX)
+                }
+            }
+        )*};
+    }
+
+    impl_into_millis!(
+        Hours: 3_600_000 / 1,
+        Minutes: 60_000 / 1,
+        Seconds: 1_000 / 1,
+        Milliseconds: 1 / 1,
+        Microseconds: 1 / 1_000,
+        Nanoseconds: 1 / 1_000_000
+    );
+}
+
+/// `fugit::Duration` counts ticks of `NOM / DENOM` seconds.
+#[cfg(feature = "fugit")]
+fn fugit_millis(ticks: u64, nom: u32, denom: u32) -> u32 {
+    let ms = u128::from(ticks) * u128::from(nom) * 1000 / u128::from(denom.max(1));
+    ms.try_into().unwrap_or(u32::MAX)
+}
+
+#[cfg(feature = "fugit")]
+impl<const NOM: u32, const DENOM: u32> IntoMillis for fugit::Duration<u32, NOM, DENOM> {
+    fn into_millis(self) -> u32 {
+        fugit_millis(u64::from(self.ticks()), NOM, DENOM)
+    }
+}
+
+#[cfg(feature = "fugit")]
+impl<const NOM: u32, const DENOM: u32> IntoMillis for fugit::Duration<u64, NOM, DENOM> {
+    fn into_millis(self) -> u32 {
+        fugit_millis(self.ticks(), NOM, DENOM)
+    }
+}
+
+/// Run `future` for at most `timeout_ms`, None if it did not finish in time.
+pub(crate) async fn with_timeout<F: Future>(
+    delay: &mut impl DelayNs,
+    timeout_ms: u32,
+    future: F,
+) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut timer = pin!(delay.delay_ms(timeout_ms));
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        timer.as_mut().poll(cx).map(|()| None)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn core_duration() {
+        assert_eq!(core::time::Duration::from_secs(5).into_millis(), 5000);
+        assert_eq!(core::time::Duration::MAX.into_millis(), u32::MAX);
+    }
+
+    #[cfg(feature = "embedded-time")]
+    #[test]
+    fn embedded_time() {
+        use embedded_time::duration::{Microseconds, Minutes, Seconds};
+
+        assert_eq!(Seconds(5u32).into_millis(), 5000);
+        assert_eq!(Minutes(2u64).into_millis(), 120_000);
+        assert_eq!(Microseconds(1500u32).into_millis(), 1);
+        assert_eq!(Minutes(u32::MAX).into_millis(), u32::MAX);
+    }
+
+    #[cfg(feature = "fugit")]
+    #[test]
+    fn fugit() {
+        assert_eq!(fugit::SecsDurationU32::secs(5).into_millis(), 5000);
+        assert_eq!(
+            fugit::Duration::<u32, 1, 32_768>::from_ticks(65_536).into_millis(),
+            2000
+        );
+        assert_eq!(
+            fugit::Duration::<u64, 60, 1>::from_ticks(u64::MAX).into_millis(),
+            u32::MAX
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Datasheet timings, see [`Model::TIMING`] for those of a model.
+//!
+//! ```no_run
+//! # async fn run(mut delay: impl embedded_hal_async::delay::DelayNs) {
+//! use mhzx::model::Mhz19C;
+//! use mhzx::timing;
+//!
+//! // right after powering on the sensor
+//! timing::wait_warmup::<Mhz19C>(&mut delay).await;
+//! # }
+//! ```
+
+use embedded_hal_async::delay::DelayNs;
+
+use crate::model::Model;
+use crate::MIN_COMMAND_INTERVAL_MS;
+
+/// Timings of a model.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    /// Time after power on before readings can be trusted.
+    pub warmup_ms: u32,
+    /// Time the reading needs to cover 90% of a step in concentration.
+    pub t90_ms: u32,
+    /// Minimum time between two commands.
+    pub min_poll_interval_ms: u32,
+    /// Time after a zero point calibration before readings can be trusted.
+    pub calibration_settle_ms: u32,
+    /// Time after changing the detection range or ABC before the next
+    /// command, sooner and the sensor can answer with garbage.
+    pub config_settle_ms: u32,
+}
+
+impl Timing {
+    /// Timings shared by all models, the MH-Z19B datasheet values.
+    pub const DEFAULT: Timing = Timing {
+        warmup_ms: 3 * 60 * 1000,
+        t90_ms: 120 * 1000,
+        min_poll_interval_ms: MIN_COMMAND_INTERVAL_MS,
+        calibration_settle_ms: 60 * 1000,
+        config_settle_ms: 1000,
+    };
+}
+
+/// Wait for a sensor that was just powered on to warm up.
+pub async fn wait_warmup<M: Model>(delay: &mut impl DelayNs) {
+    delay.delay_ms(M::TIMING.warmup_ms).await;
+}
+
+/// Wait for the reading to follow a change in concentration, for example
+/// after moving the sensor.
+pub async fn settling<M: Model>(delay: &mut impl DelayNs) {
+    delay.delay_ms(M::TIMING.t90_ms).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Mhz16, Mhz19B, Mhz19C};
+    use crate::sim::CountingDelay;
+    use futures::executor::block_on;
+
+    #[test]
+    fn timings_match_warmup() {
+        assert_eq!(Mhz19B::TIMING, Timing::DEFAULT);
+        assert_eq!(Mhz19C::TIMING.warmup_ms, Mhz19C::WARMUP_MS);
+
+        let mut delay = CountingDelay::default();
+        block_on(wait_warmup::<Mhz19C>(&mut delay));
+        assert_eq!(delay.0, Mhz19C::WARMUP_MS);
+        block_on(settling::<Mhz16>(&mut delay));
+        assert_eq!(delay.0, Mhz19C::WARMUP_MS + 30 * 1000);
+    }
+}
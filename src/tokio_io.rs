@@ -0,0 +1,146 @@
+//! Use any [`tokio::io`] reader and writer as the UART, for example the
+//! halves of `tokio::io::split` or a serial port opened by another crate.
+//!
+//! ```no_run
+//! # async fn run(port: tokio::net::TcpStream) {
+//! use mhzx::MHZ;
+//!
+//! let (rx, tx) = port.into_split();
+//! let mut sensor = MHZ::from_tokio(tx, rx);
+//! let measurement = sensor.read_co2().await.unwrap();
+//! # }
+//! ```
+
+extern crate std;
+
+use core::future::poll_fn;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use std::io;
+
+use ::tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use embedded_io_async::{ErrorType, Read, ReadReady, Write};
+
+use crate::MHZ;
+
+/// Adapts a [`tokio::io`] reader or writer to the [`embedded_io_async`]
+/// traits the driver uses.
+///
+/// Tokio readers can not tell if data is available without reading it.
+/// [`ReadReady`] therefore polls the reader once and keeps what it got
+/// for the next read.
+pub struct FromTokio<T> {
+    inner: T,
+    peeked: [u8; 16],
+    start: usize,
+    end: usize,
+}
+
+impl<T> FromTokio<T> {
+    pub fn new(inner: T) -> Self {
+        FromTokio {
+            inner,
+            peeked: [0; 16],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Give back the wrapped reader or writer. Bytes kept by
+    /// [`read_ready`](ReadReady::read_ready) are lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<W, R> MHZ<FromTokio<W>, FromTokio<R>>
+where
+    W: AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+{
+    /// Construct the sensor from tokio halves of a UART, see
+    /// [`from_tx_rx`](MHZ::from_tx_rx) for the required settings.
+    pub fn from_tokio(uart_tx: W, uart_rx: R) -> Self {
+        MHZ::from_tx_rx(FromTokio::new(uart_tx), FromTokio::new(uart_rx))
+    }
+}
+
+impl<T> ErrorType for FromTokio<T> {
+    type Error = io::Error;
+}
+
+impl<T: AsyncWrite + Unpin> Write for FromTokio<T> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_write(cx, buf)).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        poll_fn(|cx| Pin::new(&mut self.inner).poll_flush(cx)).await
+    }
+}
+
+impl<T: AsyncRead + Unpin> Read for FromTokio<T> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.start < self.end {
+            let n = (self.end - self.start).min(buf.len());
+            buf[..n].copy_from_slice(&self.peeked[self.start..self.start + n]);
+            self.start += n;
+            return Ok(n);
+        }
+
+        poll_fn(|cx| {
+            let mut buf = ReadBuf::new(buf);
+            Pin::new(&mut self.inner)
+                .poll_read(cx, &mut buf)
+                .map_ok(|()| buf.filled().len())
+        })
+        .await
+    }
+}
+
+impl<T: AsyncRead + Unpin> ReadReady for FromTokio<T> {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        if self.start < self.end {
+            return Ok(true);
+        }
+
+        let mut buf = ReadBuf::new(&mut self.peeked);
+        let mut cx = Context::from_waker(Waker::noop());
+        match Pin::new(&mut self.inner).poll_read(&mut cx, &mut buf) {
+            Poll::Ready(Ok(())) => {
+                self.start = 0;
+                self.end = buf.filled().len();
+                Ok(self.end > 0)
+            }
+            Poll::Ready(Err(e)) => Err(e),
+            Poll::Pending => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::tokio::io::AsyncWriteExt;
+    use futures::executor::block_on;
+
+    #[test]
+    fn keeps_peeked_bytes() {
+        block_on(async {
+            let (mut remote, local) = ::tokio::io::duplex(64);
+            let mut rx = FromTokio::new(local);
+            assert!(!rx.read_ready().unwrap());
+
+            remote.write_all(&[1, 2, 3]).await.unwrap();
+            assert!(rx.read_ready().unwrap());
+
+            let mut buf = [0u8; 2];
+            assert_eq!(rx.read(&mut buf).await.unwrap(), 2);
+            assert_eq!(buf, [1, 2]);
+            assert!(rx.read_ready().unwrap());
+            assert_eq!(rx.read(&mut buf).await.unwrap(), 1);
+            assert_eq!(buf[0], 3);
+            assert!(!rx.read_ready().unwrap());
+        });
+    }
+}
@@ -0,0 +1,140 @@
+//! Whether the concentration is rising, falling or stable, so a ventilation
+//! controller can ramp up fans before a threshold is reached.
+//!
+//! The slope is a least squares fit over the readings in the window, using
+//! integer math only.
+//!
+//! ```
+//! use mhzx::trend::{Direction, TrendDetector};
+//!
+//! // last 5 minutes, within ±20ppm/min counts as stable
+//! let mut trend = TrendDetector::<16>::new(5 * 60_000, 20);
+//! for (i, co2) in [600, 640, 690, 720].into_iter().enumerate() {
+//!     trend.push(co2, i as u64 * 60_000);
+//! }
+//! assert_eq!(trend.direction(), Some(Direction::Rising));
+//! ```
+
+/// See [`TrendDetector::direction`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Rising,
+    Falling,
+    Stable,
+}
+
+/// Fits a line through at most `N` readings no older than `window_ms`.
+#[derive(Debug, Clone)]
+pub struct TrendDetector<const N: usize> {
+    samples: [(u16, u64); N],
+    len: usize,
+    next: usize,
+    window_ms: u64,
+    stable_ppm_per_min: u32,
+}
+
+impl<const N: usize> TrendDetector<N> {
+    /// A slope within ±`stable_ppm_per_min` counts as
+    /// [`Stable`](Direction::Stable).
+    pub const fn new(window_ms: u64, stable_ppm_per_min: u32) -> Self {
+        const { assert!(N > 1, "need room for at least two samples") };
+        TrendDetector {
+            samples: [(0, 0); N],
+            len: 0,
+            next: 0,
+            window_ms,
+            stable_ppm_per_min,
+        }
+    }
+
+    /// Add a reading taken at `now_ms`. Once `N` readings are held the
+    /// oldest is replaced.
+    pub fn push(&mut self, co2: u16, now_ms: u64) {
+        self.samples[self.next] = (co2, now_ms);
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Change in ppm per minute, None with fewer than two readings in the
+    /// window or if they were all taken at the same time.
+    pub fn slope_ppm_per_min(&self) -> Option<i32> {
+        let newest = self.samples[(self.next + N - 1) % N].1;
+        let start = newest.saturating_sub(self.window_ms);
+        let in_window = || {
+            self.samples[..self.len]
+                .iter()
+                .filter(move |(_, ms)| (start..=newest).contains(ms))
+                // seconds before the newest reading, keeps the sums small
+                .map(move |(co2, ms)| (i128::from(*co2), -(((newest - ms) / 1000) as i128)))
+        };
+
+        let n = in_window().count() as i128;
+        if n < 2 {
+            return None;
+        }
+        let (mut sum_x, mut sum_y, mut sum_xy, mut sum_xx) = (0, 0, 0, 0);
+        for (y, x) in in_window() {
+            sum_x += x;
+            sum_y += y;
+            sum_xy += x * y;
+            sum_xx += x * x;
+        }
+        let denominator = n * sum_xx - sum_x * sum_x;
+        if denominator == 0 {
+            return None;
+        }
+        let per_min = 60 * (n * sum_xy - sum_x * sum_y) / denominator;
+        Some(per_min.clamp(i32::MIN.into(), i32::MAX.into()) as i32)
+    }
+
+    /// None while [`slope_ppm_per_min`](TrendDetector::slope_ppm_per_min)
+    /// is.
+    pub fn direction(&self) -> Option<Direction> {
+        let slope = self.slope_ppm_per_min()?;
+        Some(if slope.unsigned_abs() <= self.stable_ppm_per_min {
+            Direction::Stable
+        } else if slope > 0 {
+            Direction::Rising
+        } else {
+            Direction::Falling
+        })
+    }
+
+    /// Forget all readings.
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+
+    #[test]
+    fn slope_over_window() {
+        let mut trend = TrendDetector::<8>::new(5 * MINUTE, 10);
+        trend.push(800, 0);
+        assert_eq!(trend.direction(), None);
+
+        for (i, co2) in [760, 720, 680, 640].into_iter().enumerate() {
+            trend.push(co2, (i as u64 + 1) * MINUTE);
+        }
+        assert_eq!(trend.slope_ppm_per_min(), Some(-40));
+        assert_eq!(trend.direction(), Some(Direction::Falling));
+
+        // the falling readings leave the window
+        for minute in 5..=11 {
+            trend.push(645, minute * MINUTE);
+        }
+        assert_eq!(trend.slope_ppm_per_min(), Some(0));
+        assert_eq!(trend.direction(), Some(Direction::Stable));
+
+        trend.reset();
+        assert_eq!(trend.direction(), None);
+    }
+}
@@ -0,0 +1,104 @@
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+
+use embedded_io_async::{Read, ReadReady, Write};
+
+use crate::model::Model;
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Command, Deframer, Error, Measurement, MHZ, PAYLOAD_SIZE};
+
+/// A read started by [`request_co2`](MHZ::request_co2), holds the part of
+/// the response received so far. Pass it to
+/// [`try_read_co2`](MHZ::try_read_co2) until the measurement arrives.
+#[must_use = "the response is only collected by try_read_co2"]
+#[derive(Debug, Clone)]
+pub struct PendingCo2 {
+    deframer: Deframer,
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read + ReadReady,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Send the command to read the CO2 concentration without waiting for
+    /// the response, collect it using [`try_read_co2`](MHZ::try_read_co2).
+    pub async fn request_co2(&mut self) -> Result<PendingCo2, Error<Tx::Error, Rx::Error>> {
+        self.drain_rx().await?;
+        self.write_command(&Command::ReadCo2.to_frame()).await?;
+        Ok(PendingCo2 {
+            deframer: Deframer::new(),
+        })
+    }
+
+    /// Takes whatever part of the response to the
+    /// [`request_co2`](MHZ::request_co2) that returned `pending` has arrived
+    /// and returns immediately. Returns `Ok(None)` until the
+    /// response is complete. Frames with an invalid checksum are skipped.
+    ///
+    /// Only reads while [`ReadReady`] reports data, so this never waits on
+    /// the UART.
+    pub fn try_read_co2(
+        &mut self,
+        pending: &mut PendingCo2,
+    ) -> Result<Option<Measurement>, Error<Tx::Error, Rx::Error>> {
+        let mut buf = [0u8; PAYLOAD_SIZE];
+        while self.uart_rx.read_ready().map_err(Error::Reading)? {
+            let poll = {
+                let read = pin!(self.uart_rx.read(&mut buf));
+                read.poll(&mut Context::from_waker(Waker::noop()))
+            };
+            let n = match poll {
+                Poll::Ready(res) => res.map_err(Error::Reading)?,
+                Poll::Pending => return Ok(None),
+            };
+            if n == 0 {
+                return Err(Error::ReadingEOF);
+            }
+
+            for byte in &buf[..n] {
+                match pending.deframer.push(*byte) {
+                    Some(frame) if frame.command() == Command::ReadCo2.code() => {
+                        trace_frame!(Rx, frame.as_bytes());
+                        self.observe().frame_received(&frame);
+                        self.observe().frame_accepted(&frame);
+                        return self.parse_measurement(frame.into_bytes()).map(Some);
+                    }
+                    Some(_) => {
+                        trace!("skipping response to another command");
+                        self.observe().stale_frame();
+                    }
+                    None => (),
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use crate::sim::FakeMhz;
+    use crate::MHZ;
+    use futures::executor::block_on;
+
+    #[test]
+    fn request_then_try_read() {
+        let sim = FakeMhz::new();
+        sim.with(|s| {
+            s.co2 = 812;
+            s.chunk_size = 4;
+        });
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        let mut pending = block_on(sensor.request_co2()).unwrap();
+        assert_eq!(sensor.try_read_co2(&mut pending).unwrap().unwrap().co2, 812);
+        assert!(matches!(sensor.try_read_co2(&mut pending), Ok(None)));
+    }
+}
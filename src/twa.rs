@@ -0,0 +1,166 @@
+//! Rolling time-weighted averages, for example the 8 hour average
+//! occupational exposure limits are specified in.
+//!
+//! Each reading counts until the next one, so irregular sampling does not
+//! skew the average. Time is split into `N` buckets covering the window,
+//! memory use is fixed and the window moves forward one bucket at a time.
+//!
+//! ```
+//! use mhzx::twa::{TimeWeightedAverage, EIGHT_HOURS_MS};
+//!
+//! // 48 buckets of 10 minutes
+//! let mut twa = TimeWeightedAverage::<48>::new(EIGHT_HOURS_MS);
+//! twa.push(600, 0);
+//! twa.push(1000, 3_600_000);
+//! twa.push(1000, 4 * 3_600_000);
+//! // an hour at 600ppm, three at 1000ppm
+//! assert_eq!(twa.average(), Some(900));
+//! ```
+
+/// Window of the usual occupational exposure limit.
+pub const EIGHT_HOURS_MS: u64 = 8 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    /// Start time divided by the bucket length.
+    index: u64,
+    /// Sum of ppm times milliseconds.
+    integral: u64,
+    /// Milliseconds with a reading.
+    covered_ms: u64,
+}
+
+const EMPTY: Bucket = Bucket {
+    index: u64::MAX,
+    integral: 0,
+    covered_ms: 0,
+};
+
+/// Time-weighted average over the last `window_ms`, see the
+/// [module docs](self).
+#[derive(Debug, Clone)]
+pub struct TimeWeightedAverage<const N: usize> {
+    buckets: [Bucket; N],
+    bucket_ms: u64,
+    last: Option<(u16, u64)>,
+}
+
+impl<const N: usize> TimeWeightedAverage<N> {
+    /// `window_ms` is rounded up to a multiple of `N`, a window of zero
+    /// becomes one millisecond per bucket.
+    pub const fn new(window_ms: u64) -> Self {
+        const { assert!(N > 0, "need at least one bucket") };
+        let bucket_ms = window_ms.div_ceil(N as u64);
+        TimeWeightedAverage {
+            buckets: [EMPTY; N],
+            bucket_ms: if bucket_ms == 0 { 1 } else { bucket_ms },
+            last: None,
+        }
+    }
+
+    /// Add a reading taken at `now_ms`. The previous reading is taken to
+    /// have lasted until now. Readings older than the previous one restart
+    /// the average.
+    pub fn push(&mut self, co2: u16, now_ms: u64) {
+        match self.last {
+            Some((last_co2, last_ms)) if now_ms >= last_ms => {
+                let window_start = now_ms.saturating_sub(self.bucket_ms * N as u64);
+                self.integrate(last_co2, last_ms.max(window_start), now_ms);
+            }
+            Some(_) => self.reset(),
+            None => (),
+        }
+        self.last = Some((co2, now_ms));
+    }
+
+    /// Average over the window ending at the last reading, None until two
+    /// readings were pushed. Only the time covered by readings counts. The
+    /// window includes the bucket the last reading is in, so its start moves
+    /// in steps of `window_ms / N`.
+    pub fn average(&self) -> Option<u16> {
+        let (integral, covered_ms) = self.current().fold((0, 0), |(integral, covered), bucket| {
+            (integral + bucket.integral, covered + bucket.covered_ms)
+        });
+        (covered_ms > 0).then(|| ((integral + covered_ms / 2) / covered_ms) as u16)
+    }
+
+    /// How much of the window is covered by readings.
+    pub fn covered_ms(&self) -> u64 {
+        self.current().map(|bucket| bucket.covered_ms).sum()
+    }
+
+    /// Forget all readings.
+    pub fn reset(&mut self) {
+        self.buckets = [EMPTY; N];
+        self.last = None;
+    }
+
+    /// Buckets inside the window.
+    fn current(&self) -> impl Iterator<Item = &Bucket> {
+        let newest = self.last.map_or(0, |(_, ms)| ms / self.bucket_ms);
+        let oldest = newest.saturating_sub(N as u64 - 1);
+        self.buckets
+            .iter()
+            .filter(move |bucket| (oldest..=newest).contains(&bucket.index))
+    }
+
+    fn integrate(&mut self, co2: u16, mut from_ms: u64, to_ms: u64) {
+        while from_ms < to_ms {
+            let index = from_ms / self.bucket_ms;
+            let end = ((index + 1) * self.bucket_ms).min(to_ms);
+            let bucket = &mut self.buckets[(index % N as u64) as usize];
+            if bucket.index != index {
+                *bucket = Bucket {
+                    index,
+                    integral: 0,
+                    covered_ms: 0,
+                };
+            }
+            bucket.integral += u64::from(co2) * (end - from_ms);
+            bucket.covered_ms += end - from_ms;
+            from_ms = end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 60 * 60 * 1000;
+
+    #[test]
+    fn zero_window() {
+        let mut twa = TimeWeightedAverage::<4>::new(0);
+        twa.push(600, 0);
+        twa.push(800, 2);
+        twa.push(800, 10);
+        assert_eq!(twa.average(), Some(800));
+        assert_eq!(twa.covered_ms(), 3);
+    }
+
+    #[test]
+    fn window_moves() {
+        let mut twa = TimeWeightedAverage::<8>::new(EIGHT_HOURS_MS);
+        assert_eq!(twa.average(), None);
+        twa.push(2000, 0);
+        assert_eq!(twa.average(), None);
+
+        // two hours at 2000ppm then four at 500ppm
+        twa.push(500, 2 * HOUR);
+        twa.push(500, 6 * HOUR);
+        assert_eq!(twa.average(), Some(1000));
+        assert_eq!(twa.covered_ms(), 6 * HOUR);
+        // the first two hours dropped out of the window
+        twa.push(500, 10 * HOUR + HOUR / 2);
+        assert_eq!(twa.average(), Some(500));
+        assert_eq!(twa.covered_ms(), 7 * HOUR + HOUR / 2);
+
+        // a long gap does not loop over every bucket in between
+        twa.push(800, 1000 * HOUR);
+        assert_eq!(twa.average(), Some(500));
+
+        twa.push(800, 0);
+        assert_eq!(twa.average(), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! The [`Ppm`] unit and conversion of the volume fraction the sensor
+//! reports to a mass concentration, as required for some regulatory
+//! reporting.
+//!
+//! mg/m³ = ppm × M × p / (R × T) with M the molar mass of CO2. At 25°C and
+//! sea level pressure 1ppm is about 1.8mg/m³.
+
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::compensation::REFERENCE_PRESSURE_PA;
+
+/// A CO2 concentration in parts per million. Displays as "1234 ppm".
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "postcard-schema", derive(postcard_schema::Schema))]
+#[cfg_attr(
+    feature = "minicbor",
+    derive(minicbor::Encode, minicbor::Decode),
+    cbor(transparent)
+)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ppm(#[cfg_attr(feature = "minicbor", n(0))] pub u16);
+
+impl Ppm {
+    pub const ZERO: Ppm = Ppm(0);
+    pub const MAX: Ppm = Ppm(u16::MAX);
+
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+
+    pub const fn saturating_add(self, rhs: Ppm) -> Ppm {
+        Ppm(self.0.saturating_add(rhs.0))
+    }
+
+    pub const fn saturating_sub(self, rhs: Ppm) -> Ppm {
+        Ppm(self.0.saturating_sub(rhs.0))
+    }
+
+    /// Absolute difference between two concentrations.
+    pub const fn abs_diff(self, other: Ppm) -> Ppm {
+        Ppm(self.0.abs_diff(other.0))
+    }
+
+    /// The concentration as mass per volume, see [`ppm_to_mg_per_m3`].
+    pub fn to_mg_per_m3(self, temp_celsius: i16, pressure_pa: u32) -> u32 {
+        ppm_to_mg_per_m3(self.0, temp_celsius, pressure_pa)
+    }
+}
+
+impl From<u16> for Ppm {
+    fn from(ppm: u16) -> Self {
+        Ppm(ppm)
+    }
+}
+
+impl From<Ppm> for u16 {
+    fn from(ppm: Ppm) -> Self {
+        ppm.0
+    }
+}
+
+impl PartialEq<u16> for Ppm {
+    fn eq(&self, other: &u16) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialOrd<u16> for Ppm {
+    fn partial_cmp(&self, other: &u16) -> Option<Ordering> {
+        Some(self.0.cmp(other))
+    }
+}
+
+impl fmt::Display for Ppm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ppm", self.0)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Ppm {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{} ppm", self.0)
+    }
+}
+
+/// Molar mass of CO2 in milligram per mol.
+pub const CO2_MOLAR_MASS_MG: u32 = 44_010;
+/// Temperature the conversion is commonly done at in degrees Celsius.
+pub const REFERENCE_TEMPERATURE_CELSIUS: i16 = 25;
+
+/// Gas constant in µJ/(mol·K).
+const GAS_CONSTANT_MICRO: u64 = 8_314_462;
+/// 0°C in centi Kelvin.
+const ZERO_CELSIUS_CK: i64 = 27_315;
+
+/// CO2 concentration in mg/m³ of air at `temp_celsius` and `pressure_pa`.
+/// Integer math only, rounded to the nearest mg/m³ and saturating at
+/// `u32::MAX`. Temperatures below absolute zero are treated as 1K.
+pub fn ppm_to_mg_per_m3(ppm: u16, temp_celsius: i16, pressure_pa: u32) -> u32 {
+    let temp_ck = (i64::from(temp_celsius) * 100 + ZERO_CELSIUS_CK).max(100) as u128;
+    // ppm × 1e-6 × M[mg/mol] × p / (R × T) with T in cK and R in µJ/(mol·K)
+    // simplifies to ppm × M × p × 100 / (R_micro × T_ck). The numerator
+    // overflows a u64 above about 6.4 MPa.
+    let numerator = u128::from(ppm) * u128::from(CO2_MOLAR_MASS_MG) * u128::from(pressure_pa) * 100;
+    let denominator = u128::from(GAS_CONSTANT_MICRO) * temp_ck;
+    u32::try_from((numerator + denominator / 2) / denominator).unwrap_or(u32::MAX)
+}
+
+/// [`ppm_to_mg_per_m3`] at 25°C and sea level pressure.
+pub fn ppm_to_mg_per_m3_standard(ppm: u16) -> u32 {
+    ppm_to_mg_per_m3(ppm, REFERENCE_TEMPERATURE_CELSIUS, REFERENCE_PRESSURE_PA)
+}
+
+/// Like [`ppm_to_mg_per_m3`] without rounding.
+pub fn ppm_to_mg_per_m3_f32(ppm: f32, temp_celsius: f32, pressure_pa: f32) -> f32 {
+    let molar_mass_g = CO2_MOLAR_MASS_MG as f32 / 1000.0;
+    let gas_constant = GAS_CONSTANT_MICRO as f32 / 1e6;
+    let temp_k = temp_celsius + ZERO_CELSIUS_CK as f32 / 100.0;
+    ppm * molar_mass_g * pressure_pa / (gas_constant * temp_k) / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ppm() {
+        extern crate std;
+        use std::string::ToString;
+
+        assert_eq!(Ppm(1234).to_string(), "1234 ppm");
+        assert_eq!(Ppm(u16::MAX).saturating_add(Ppm(1)), Ppm::MAX);
+        assert_eq!(Ppm(400).saturating_sub(Ppm(500)), Ppm::ZERO);
+        assert!(Ppm(1000) > 999);
+    }
+
+    #[test]
+    fn standard_conditions() {
+        assert_eq!(ppm_to_mg_per_m3_standard(1000), 1799);
+        assert_eq!(ppm_to_mg_per_m3_standard(u16::MAX), 117_889);
+        // colder air is denser
+        assert_eq!(ppm_to_mg_per_m3(1000, 0, REFERENCE_PRESSURE_PA), 1964);
+        // the intermediate product no longer fits a u64 at 10 MPa
+        assert_eq!(ppm_to_mg_per_m3(1000, 25, 10_000_000), 177_534);
+        assert_eq!(ppm_to_mg_per_m3(u16::MAX, -273, u32::MAX), u32::MAX);
+
+        let float = ppm_to_mg_per_m3_f32(1000.0, 25.0, REFERENCE_PRESSURE_PA as f32);
+        assert!((float - 1798.9).abs() < 0.5, "{float}");
+    }
+}
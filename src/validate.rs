@@ -0,0 +1,210 @@
+//! Reject measurements that passed the checksum but can not be right, so
+//! they never reach control logic. Use [`MHZ::read_co2_validated`] with the
+//! default [`Plausibility`] check or your own [`Validator`].
+//!
+//! ```no_run
+//! # async fn run<Tx, Rx>(mut sensor: mhzx::MHZ<Tx, Rx>, uptime_ms: fn() -> u64)
+//! # where
+//! #     Tx: embedded_io_async::Write,
+//! #     Rx: embedded_io_async::Read,
+//! # {
+//! use mhzx::model::{Mhz19B, Range};
+//! use mhzx::validate::Plausibility;
+//!
+//! let mut validator = Plausibility::new(Mhz19B, Range::Ppm5000, uptime_ms);
+//! let measurement = sensor.read_co2_validated(&mut validator).await;
+//! # }
+//! ```
+
+use core::fmt;
+
+use embedded_io_async::{Read, Write};
+
+use crate::model::{reports_temperature, DetectionRange, Model};
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Clock, Error, Measurement, MHZ, PLAUSIBLE_TEMPERATURE};
+
+/// Fastest change in concentration [`Plausibility`] accepts by default.
+/// The sensor smooths its output, even breathing on it changes the reading
+/// slower than this.
+pub const DEFAULT_MAX_SLOPE_PPM_PER_S: u32 = 200;
+
+/// Why a measurement was rejected, see [`Error::Implausible`].
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "postcard-schema", derive(postcard_schema::Schema))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "minicbor", derive(minicbor::Encode, minicbor::Decode))]
+#[cfg_attr(
+    feature = "postcard",
+    derive(postcard::experimental::max_size::MaxSize)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Implausibility {
+    /// Zero or above the detection range.
+    #[cfg_attr(feature = "minicbor", n(0))]
+    OutOfRange {
+        #[cfg_attr(feature = "minicbor", n(0))]
+        co2: u16,
+    },
+    /// Changed faster than allowed since the last accepted measurement.
+    #[cfg_attr(feature = "minicbor", n(1))]
+    TooSteep {
+        #[cfg_attr(feature = "minicbor", n(0))]
+        ppm_per_s: u32,
+    },
+    /// Temperature outside [`PLAUSIBLE_TEMPERATURE`].
+    #[cfg_attr(feature = "minicbor", n(2))]
+    Temperature {
+        #[cfg_attr(feature = "minicbor", n(0))]
+        celsius: i16,
+    },
+}
+
+impl fmt::Display for Implausibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Implausibility::OutOfRange { co2 } => {
+                write!(f, "{co2}ppm is outside the detection range")
+            }
+            Implausibility::TooSteep { ppm_per_s } => {
+                write!(f, "concentration changed by {ppm_per_s}ppm/s")
+            }
+            Implausibility::Temperature { celsius } => {
+                write!(f, "temperature of {celsius}°C")
+            }
+        }
+    }
+}
+
+/// Checks every measurement read by [`MHZ::read_co2_validated`].
+pub trait Validator {
+    fn validate(&mut self, measurement: &Measurement) -> Result<(), Implausibility>;
+}
+
+/// Checks the concentration against the detection range, how fast it
+/// changes and the temperature against [`PLAUSIBLE_TEMPERATURE`].
+pub struct Plausibility<C> {
+    max_ppm: u16,
+    max_slope_ppm_per_s: u32,
+    check_temperature: bool,
+    clock: C,
+    last_accepted: Option<(u16, u64)>,
+}
+
+impl<C: Clock> Plausibility<C> {
+    /// The temperature is only checked if `model` reports it. The slope is
+    /// measured using `clock`.
+    pub fn new<M: Model>(_model: M, range: M::Range, clock: C) -> Self {
+        Plausibility {
+            max_ppm: range.max_ppm(),
+            max_slope_ppm_per_s: DEFAULT_MAX_SLOPE_PPM_PER_S,
+            check_temperature: reports_temperature::<M>(),
+            clock,
+            last_accepted: None,
+        }
+    }
+
+    /// Defaults to [`DEFAULT_MAX_SLOPE_PPM_PER_S`].
+    pub fn max_slope(mut self, ppm_per_s: u32) -> Self {
+        self.max_slope_ppm_per_s = ppm_per_s;
+        self
+    }
+}
+
+impl<C: Clock> Validator for Plausibility<C> {
+    fn validate(&mut self, measurement: &Measurement) -> Result<(), Implausibility> {
+        let co2 = measurement.co2.get();
+        if co2 == 0 || co2 > self.max_ppm {
+            return Err(Implausibility::OutOfRange { co2 });
+        }
+
+        let celsius = measurement.temp_celsius();
+        let (min, max) = PLAUSIBLE_TEMPERATURE;
+        if self.check_temperature && !(min..=max).contains(&celsius) {
+            return Err(Implausibility::Temperature { celsius });
+        }
+
+        // a rejected measurement is not used as reference, a real jump
+        // is accepted once enough time passed to explain it
+        let now = self.clock.now_ms();
+        if let Some((last_co2, last_ms)) = self.last_accepted {
+            let elapsed_ms = now.saturating_sub(last_ms).max(1);
+            let change = u64::from(co2.abs_diff(last_co2));
+            let ppm_per_s = u32::try_from(change * 1000 / elapsed_ms).unwrap_or(u32::MAX);
+            if ppm_per_s > self.max_slope_ppm_per_s {
+                return Err(Implausibility::TooSteep { ppm_per_s });
+            }
+        }
+        self.last_accepted = Some((co2, now));
+        Ok(())
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Like [`read_co2`](MHZ::read_co2) but fails with
+    /// [`Error::Implausible`] if `validator` rejects the measurement.
+    pub async fn read_co2_validated(
+        &mut self,
+        validator: &mut impl Validator,
+    ) -> Result<Measurement, Error<Tx::Error, Rx::Error>> {
+        let measurement = self.read_co2().await?;
+        validator
+            .validate(&measurement)
+            .map_err(Error::Implausible)?;
+        Ok(measurement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Mhz14A, Mhz19B, Range};
+    use crate::Ppm;
+    use core::cell::Cell;
+
+    fn measurement(co2: u16, celsius: i16) -> Measurement {
+        Measurement {
+            co2: Ppm(co2),
+            temp: (celsius + 40) as u8,
+            calib_ticks: 0,
+            calib_cycles: 0,
+            undocumented_u: 0,
+        }
+    }
+
+    #[test]
+    fn rejects_implausible() {
+        let now = Cell::new(0);
+        let mut validator = Plausibility::new(Mhz19B, Range::Ppm2000, || now.get());
+
+        assert_eq!(validator.validate(&measurement(500, 20)), Ok(()));
+        assert_eq!(
+            validator.validate(&measurement(2500, 20)),
+            Err(Implausibility::OutOfRange { co2: 2500 })
+        );
+        assert_eq!(
+            validator.validate(&measurement(500, 90)),
+            Err(Implausibility::Temperature { celsius: 90 })
+        );
+
+        now.set(1000);
+        assert_eq!(
+            validator.validate(&measurement(1500, 20)),
+            Err(Implausibility::TooSteep { ppm_per_s: 1000 })
+        );
+        now.set(5000);
+        assert_eq!(validator.validate(&measurement(1300, 20)), Ok(()));
+
+        // the MH-Z14A does not report temperature
+        let mut validator = Plausibility::new(Mhz14A, Range::Ppm2000, || 0);
+        assert_eq!(validator.validate(&measurement(500, 90)), Ok(()));
+    }
+}
@@ -0,0 +1,183 @@
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+
+use crate::measurement::check_header;
+use crate::model::{DetectionRange, Model, Range, SupportsAbc, SupportsRange};
+use crate::observer::Observer;
+use crate::pacing::Pacing;
+use crate::{Command, Error, MHZ, MIN_COMMAND_INTERVAL_MS, PAYLOAD_SIZE};
+
+/// A configuration change that can be read back from the sensor.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Setting {
+    Abc(bool),
+    Range(Range),
+}
+
+impl Setting {
+    fn command(self) -> Command {
+        match self {
+            Setting::Abc(enabled) => Command::SetAbc(enabled),
+            Setting::Range(range) => Command::SetRange(range),
+        }
+    }
+
+    fn read_back(self) -> Command {
+        match self {
+            Setting::Abc(_) => Command::ReadAbc,
+            Setting::Range(_) => Command::ReadRange,
+        }
+    }
+
+    fn applied(self, package: &[u8; PAYLOAD_SIZE]) -> bool {
+        match self {
+            Setting::Abc(enabled) => abc_enabled(package) == enabled,
+            Setting::Range(range) => range_max_ppm(package) == range.max_ppm(),
+        }
+    }
+}
+
+fn abc_enabled(package: &[u8; PAYLOAD_SIZE]) -> bool {
+    package[7] == 1
+}
+
+fn range_max_ppm(package: &[u8; PAYLOAD_SIZE]) -> u16 {
+    u16::from_be_bytes([package[4], package[5]])
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: Model,
+    P: Pacing,
+    O: Observer,
+{
+    /// Send `setting` until reading it back shows the sensor applied it,
+    /// at most `retries` times again. Before reading back it waits
+    /// [`config_settle_ms`](crate::timing::Timing::config_settle_ms), the
+    /// sensor answers with garbage sooner.
+    pub(crate) async fn apply_verified(
+        &mut self,
+        setting: Setting,
+        retries: u8,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        for _ in 0..=retries {
+            self.write_command(&setting.command().to_frame()).await?;
+            self.pacing.settle(M::TIMING.config_settle_ms);
+            delay.delay_ms(M::TIMING.config_settle_ms).await;
+
+            let read_back = setting.read_back();
+            let package = self.transact(&read_back.to_frame()).await?;
+            check_header(&package, read_back.code())?;
+            if setting.applied(&package) {
+                return Ok(());
+            }
+            debug!("configuration not applied");
+            delay.delay_ms(MIN_COMMAND_INTERVAL_MS).await;
+            self.observe().retry();
+        }
+        Err(Error::ConfigNotApplied)
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: SupportsAbc,
+    P: Pacing,
+    O: Observer,
+{
+    /// Whether automatic baseline correction is on (command 0x7D).
+    pub async fn read_abc(&mut self) -> Result<bool, Error<Tx::Error, Rx::Error>> {
+        let package = self.transact(&Command::ReadAbc.to_frame()).await?;
+        check_header(&package, 0x7D)?;
+        Ok(abc_enabled(&package))
+    }
+
+    /// Like [`set_abc`](MHZ::set_abc) but reads the state back to verify the
+    /// sensor applied it, after waiting
+    /// [`config_settle_ms`](crate::timing::Timing::config_settle_ms). The
+    /// sensor sometimes silently ignores commands, it is send again up to
+    /// `retries` times. Fails with [`Error::ConfigNotApplied`] if it never
+    /// took effect.
+    pub async fn set_abc_verified(
+        &mut self,
+        enabled: bool,
+        retries: u8,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        debug!("setting abc to: {}", enabled);
+        self.apply_verified(Setting::Abc(enabled), retries, delay)
+            .await
+    }
+}
+
+impl<Tx, Rx, M, const RX_BUF: usize, P, O> MHZ<Tx, Rx, M, RX_BUF, P, O>
+where
+    Tx: Write,
+    Rx: Read,
+    M: SupportsRange,
+    P: Pacing,
+    O: Observer,
+{
+    /// The upper bound of the detection range in ppm (command 0x9B).
+    pub async fn read_range_ppm(&mut self) -> Result<u16, Error<Tx::Error, Rx::Error>> {
+        let package = self.transact(&Command::ReadRange.to_frame()).await?;
+        check_header(&package, 0x9B)?;
+        Ok(range_max_ppm(&package))
+    }
+
+    /// Like [`set_range`](MHZ::set_range) but reads the range back, see
+    /// [`set_abc_verified`](MHZ::set_abc_verified).
+    pub async fn set_range_verified(
+        &mut self,
+        range: M::Range,
+        retries: u8,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Error<Tx::Error, Rx::Error>> {
+        debug!("setting range to {}ppm", range.max_ppm());
+        self.apply_verified(Setting::Range(range.into()), retries, delay)
+            .await
+    }
+}
+
+#[cfg(all(target_os = "linux", test))]
+mod tests {
+    use super::*;
+    use crate::model::Range;
+    use crate::sim::{CountingDelay, FakeMhz, NoDelay};
+    use futures::executor::block_on;
+
+    #[test]
+    fn waits_for_the_sensor_to_settle() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        let mut delay = CountingDelay(0);
+        block_on(sensor.set_abc_verified(true, 0, &mut delay)).unwrap();
+        assert_eq!(delay.0, crate::model::Mhz19B::TIMING.config_settle_ms);
+    }
+
+    #[test]
+    fn retries_ignored_commands() {
+        let sim = FakeMhz::new();
+        let (tx, rx) = sim.split();
+        let mut sensor = MHZ::from_tx_rx(tx, rx);
+
+        sim.with(|s| s.ignore_config = 2);
+        block_on(sensor.set_abc_verified(false, 2, &mut NoDelay)).unwrap();
+        assert!(!block_on(sensor.read_abc()).unwrap());
+
+        sim.with(|s| s.ignore_config = 2);
+        assert_eq!(
+            block_on(sensor.set_range_verified(Range::Ppm2000, 1, &mut NoDelay)),
+            Err(Error::ConfigNotApplied)
+        );
+        block_on(sensor.set_range_verified(Range::Ppm2000, 0, &mut NoDelay)).unwrap();
+        assert_eq!(block_on(sensor.read_range_ppm()).unwrap(), 2000);
+    }
+}
@@ -0,0 +1,114 @@
+//! Detect the end of the warm-up period by looking at the readings, as an
+//! alternative to waiting [`Model::WARMUP_MS`](crate::model::Model::WARMUP_MS).
+//!
+//! While warming up the sensor reports fixed placeholder values (400, 410,
+//! 500 or 5000ppm depending on model and firmware). Once it is done the
+//! output starts to change.
+
+/// Placeholder values reported during warm-up.
+pub const WARMUP_VALUES: [u16; 4] = [400, 410, 500, 5000];
+
+/// Number of readings [`WarmupDetector::default`] wants to see before
+/// considering the sensor warmed up.
+pub const DEFAULT_REQUIRED_READINGS: u8 = 3;
+
+/// Whether the sensor output can be trusted yet.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarmupState {
+    WarmingUp,
+    Stable,
+}
+
+/// Observes readings after power on and reports when they stabilized.
+///
+/// A reading counts as live if it is not one of the [`WARMUP_VALUES`], a
+/// switch from one placeholder to another does not. The sensor is
+/// considered warmed up after a number of consecutive live readings. In fresh air a warmed up sensor can read a steady 400ppm,
+/// always fall back to the datasheet warm-up time.
+#[derive(Debug, Clone)]
+pub struct WarmupDetector {
+    required: u8,
+    live: u8,
+}
+
+impl WarmupDetector {
+    /// Consider the sensor warmed up after `required_readings` consecutive
+    /// live readings.
+    pub const fn new(required_readings: u8) -> Self {
+        WarmupDetector {
+            required: required_readings,
+            live: 0,
+        }
+    }
+
+    /// Observe the next CO2 reading.
+    pub fn push(&mut self, co2: u16) -> WarmupState {
+        if self.is_stable() {
+            return WarmupState::Stable;
+        }
+
+        if !WARMUP_VALUES.contains(&co2) {
+            self.live += 1;
+        } else {
+            self.live = 0;
+        }
+        self.state()
+    }
+
+    pub fn state(&self) -> WarmupState {
+        if self.is_stable() {
+            WarmupState::Stable
+        } else {
+            WarmupState::WarmingUp
+        }
+    }
+
+    pub fn is_stable(&self) -> bool {
+        self.live >= self.required
+    }
+}
+
+impl Default for WarmupDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_REQUIRED_READINGS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_end_of_warmup() {
+        let mut detector = WarmupDetector::default();
+        for co2 in [5000, 5000, 410, 410, 410] {
+            assert_eq!(detector.push(co2), WarmupState::WarmingUp);
+        }
+        assert_eq!(detector.push(612), WarmupState::WarmingUp);
+        assert_eq!(detector.push(598), WarmupState::WarmingUp);
+        assert_eq!(detector.push(601), WarmupState::Stable);
+        // stays stable
+        assert_eq!(detector.push(400), WarmupState::Stable);
+    }
+
+    #[test]
+    fn placeholder_resets_progress() {
+        let mut detector = WarmupDetector::default();
+        detector.push(612);
+        detector.push(400);
+        detector.push(400);
+        assert!(!detector.is_stable());
+        detector.push(598);
+        detector.push(601);
+        assert!(!detector.is_stable());
+    }
+
+    #[test]
+    fn placeholder_changes_are_not_live() {
+        let mut detector = WarmupDetector::default();
+        for co2 in [5000, 400, 410, 500, 5000, 410] {
+            assert_eq!(detector.push(co2), WarmupState::WarmingUp);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Detect a sensor that stopped responding.
+//!
+//! ```
+//! # futures::executor::block_on(async {
+//! use mhzx::sim::FakeMhz;
+//! use mhzx::watchdog::{Supervised, Watchdog};
+//! use mhzx::MHZ;
+//!
+//! let sim = FakeMhz::new();
+//! let (tx, rx) = sim.split();
+//! let mut sensor = MHZ::from_tx_rx(tx, rx);
+//! let mut watchdog = Watchdog::new(3);
+//!
+//! match watchdog.check(sensor.read_co2().await) {
+//!     Ok(measurement) => println!("{}", measurement.co2),
+//!     Err(Supervised::Failed(_)) => (), // try again later
+//!     Err(Supervised::SensorOffline { streak, .. }) => {
+//!         println!("no valid response for {streak} reads, power cycling")
+//!     }
+//! }
+//! # });
+//! ```
+
+/// Outcome of a failed operation checked by a [`Watchdog`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Supervised<E> {
+    /// The operation failed, the streak is below the threshold.
+    Failed(E),
+    /// At least the threshold number of operations failed in a row.
+    SensorOffline {
+        /// Number of consecutive failures, including this one.
+        streak: u32,
+        last_error: E,
+    },
+}
+
+impl<E> Supervised<E> {
+    /// The error of the operation.
+    pub fn error(&self) -> &E {
+        match self {
+            Supervised::Failed(e) => e,
+            Supervised::SensorOffline { last_error, .. } => last_error,
+        }
+    }
+}
+
+/// Counts consecutive failures, see the [module docs](self).
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    offline_after: u32,
+    streak: u32,
+}
+
+impl Watchdog {
+    /// Consider the sensor offline after `offline_after` failures in a row.
+    pub const fn new(offline_after: u32) -> Self {
+        Watchdog {
+            offline_after,
+            streak: 0,
+        }
+    }
+
+    /// Track the outcome of an operation on the sensor. A success resets
+    /// the streak.
+    pub fn check<T, E>(&mut self, result: Result<T, E>) -> Result<T, Supervised<E>> {
+        match result {
+            Ok(value) => {
+                self.streak = 0;
+                Ok(value)
+            }
+            Err(error) => {
+                self.streak = self.streak.saturating_add(1);
+                if self.is_offline() {
+                    debug!("sensor offline, {} failures in a row", self.streak);
+                    Err(Supervised::SensorOffline {
+                        streak: self.streak,
+                        last_error: error,
+                    })
+                } else {
+                    Err(Supervised::Failed(error))
+                }
+            }
+        }
+    }
+
+    /// Number of consecutive failures.
+    pub fn streak(&self) -> u32 {
+        self.streak
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.streak >= self.offline_after
+    }
+
+    /// Forget the streak, for example after power cycling the sensor.
+    pub fn reset(&mut self) {
+        self.streak = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goes_offline_after_streak() {
+        let mut watchdog = Watchdog::new(2);
+        assert_eq!(watchdog.check::<(), _>(Err(1)), Err(Supervised::Failed(1)));
+        assert_eq!(
+            watchdog.check::<(), _>(Err(2)),
+            Err(Supervised::SensorOffline {
+                streak: 2,
+                last_error: 2
+            })
+        );
+        assert!(watchdog.is_offline());
+        assert_eq!(watchdog.check(Ok::<_, u8>(5)), Ok(5));
+        assert_eq!(watchdog.streak(), 0);
+    }
+}
@@ -0,0 +1,165 @@
+//! A compact binary representation of a [`Measurement`] for radio links
+//! such as LoRa or nRF24, where every byte counts. The node packs, the
+//! gateway unpacks using this same crate.
+//!
+//! Version 2 is [`PACKED_SIZE`] bytes:
+//!
+//! | byte | content                                     |
+//! |------|---------------------------------------------|
+//! | 0    | format version, [`VERSION`]                 |
+//! | 1-2  | CO2 concentration in ppm, big endian        |
+//! | 3    | temperature, degrees Celsius plus 40        |
+//! | 4    | ABC calibration ticks                       |
+//! | 5    | ABC calibration cycles                      |
+//! | 6    | low byte of [`Measurement::undocumented_u`] |
+//!
+//! The high byte of [`Measurement::undocumented_u`] is the calibration
+//! cycles byte, as in the sensor's response. Version 1 lacks byte 6, its
+//! low byte unpacks as zero.
+//!
+//! New versions get a new version byte, [`Measurement::unpack`] keeps
+//! accepting the old ones.
+//!
+//! ```
+//! use mhzx::{Measurement, Ppm};
+//!
+//! let measurement = Measurement {
+//!     co2: Ppm(612),
+//!     temp: 61,
+//!     calib_ticks: 0,
+//!     calib_cycles: 0,
+//!     undocumented_u: 0,
+//! };
+//! let packed = measurement.pack();
+//! assert_eq!(packed, [2, 0x02, 0x64, 61, 0, 0, 0]);
+//! assert_eq!(Measurement::unpack(&packed).unwrap().co2, 612);
+//! ```
+
+use core::fmt;
+
+use crate::{Measurement, Ppm};
+
+/// The version [`Measurement::pack`] produces.
+pub const VERSION: u8 = 2;
+/// Size of a packed measurement in bytes.
+pub const PACKED_SIZE: usize = 7;
+/// Size of a version 1 packed measurement.
+const V1_SIZE: usize = 6;
+
+/// Why [`Measurement::unpack`] failed.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnpackError {
+    /// Packed by a newer version of this crate.
+    UnknownVersion(u8),
+    /// The length does not match the version.
+    WrongLength { expected: usize, got: usize },
+}
+
+impl fmt::Display for UnpackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnpackError::UnknownVersion(version) => {
+                write!(f, "Unknown packed measurement version: {version}")
+            }
+            UnpackError::WrongLength { expected, got } => write!(
+                f,
+                "Packed measurement should be {expected} bytes, got {got}"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for UnpackError {}
+
+impl Measurement {
+    /// Pack into the [wire format](crate::wire).
+    pub fn pack(&self) -> [u8; PACKED_SIZE] {
+        let [high, low] = self.co2.get().to_be_bytes();
+        [
+            VERSION,
+            high,
+            low,
+            self.temp,
+            self.calib_ticks,
+            self.calib_cycles,
+            self.undocumented_u.to_be_bytes()[1],
+        ]
+    }
+
+    /// Unpack from the [wire format](crate::wire).
+    pub fn unpack(bytes: &[u8]) -> Result<Measurement, UnpackError> {
+        let Some(&version) = bytes.first() else {
+            return Err(UnpackError::WrongLength {
+                expected: PACKED_SIZE,
+                got: 0,
+            });
+        };
+        let packed: [u8; PACKED_SIZE] = match version {
+            1 => {
+                let v1: [u8; V1_SIZE] = exact(bytes)?;
+                let mut packed = [0; PACKED_SIZE];
+                packed[..V1_SIZE].copy_from_slice(&v1);
+                packed
+            }
+            VERSION => exact(bytes)?,
+            _ => return Err(UnpackError::UnknownVersion(version)),
+        };
+        let [_, high, low, temp, calib_ticks, calib_cycles, u_low] = packed;
+        Ok(Measurement {
+            co2: Ppm(u16::from_be_bytes([high, low])),
+            temp,
+            calib_ticks,
+            calib_cycles,
+            undocumented_u: u16::from_be_bytes([calib_cycles, u_low]),
+        })
+    }
+}
+
+fn exact<const N: usize>(bytes: &[u8]) -> Result<[u8; N], UnpackError> {
+    bytes.try_into().map_err(|_| UnpackError::WrongLength {
+        expected: N,
+        got: bytes.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let measurement = Measurement {
+            co2: Ppm(u16::MAX),
+            temp: 255,
+            calib_ticks: 1,
+            calib_cycles: 2,
+            undocumented_u: 0x0230,
+        };
+        let unpacked = Measurement::unpack(&measurement.pack()).unwrap();
+        assert_eq!(unpacked.pack(), measurement.pack());
+        assert_eq!(unpacked.undocumented_u, 0x0230);
+
+        let v1 = Measurement::unpack(&[1, 0x02, 0x64, 61, 1, 2]).unwrap();
+        assert_eq!((v1.co2, v1.undocumented_u), (Ppm(612), 0x0200));
+
+        assert_eq!(
+            Measurement::unpack(&[3, 0, 0, 0, 0, 0, 0]).unwrap_err(),
+            UnpackError::UnknownVersion(3)
+        );
+        assert_eq!(
+            Measurement::unpack(&[1, 0, 0]).unwrap_err(),
+            UnpackError::WrongLength {
+                expected: V1_SIZE,
+                got: 3
+            }
+        );
+        assert_eq!(
+            Measurement::unpack(&[2, 0, 0, 0, 0, 0]).unwrap_err(),
+            UnpackError::WrongLength {
+                expected: PACKED_SIZE,
+                got: 6
+            }
+        );
+    }
+}